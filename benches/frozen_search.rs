@@ -0,0 +1,85 @@
+//! Compare the bucketed search of `FrozenPeakSet` against the plain binary search of
+//! `PeakSet` for the random-access query pattern of a database search engine.
+//!
+//! Run with `cargo bench --bench frozen_search`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use mzpeaks::frozen::FrozenPeakSet;
+use mzpeaks::{CentroidPeak, PeakCollection, PeakSet, Tolerance};
+
+const N_PEAKS: usize = 20_000;
+const N_QUERIES: usize = 1_000_000;
+const N_ROUNDS: usize = 5;
+
+/// A small deterministic generator so the benchmark needs no dependencies
+struct Lcg(u64);
+
+impl Lcg {
+    fn next_f64(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn uniform(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+}
+
+/// The fastest of several rounds of calling `f` with every query
+fn time<F: Fn(f64) -> usize>(queries: &[f64], f: F) -> Duration {
+    (0..N_ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            let mut acc = 0usize;
+            for q in queries {
+                acc = acc.wrapping_add(f(black_box(*q)));
+            }
+            black_box(acc);
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, elapsed: Duration, baseline: Duration) {
+    println!(
+        "{name:<32} {:>8.1} ns/query {:>6.2}x",
+        elapsed.as_nanos() as f64 / N_QUERIES as f64,
+        baseline.as_secs_f64() / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let mut rng = Lcg(42);
+    let peaks = PeakSet::new(
+        (0..N_PEAKS)
+            .map(|_| CentroidPeak::new(rng.uniform(100.0, 2000.0), 1.0, 0))
+            .collect(),
+    );
+    let queries: Vec<f64> = (0..N_QUERIES).map(|_| rng.uniform(90.0, 2010.0)).collect();
+    let tol = Tolerance::PPM(10.0);
+
+    for width in [0.1, 1.0, 10.0] {
+        let frozen = FrozenPeakSet::with_bucket_width(peaks.clone(), width);
+        println!("{N_PEAKS} peaks, {N_QUERIES} queries, bucket width {width}");
+
+        let baseline = time(&queries, |q| match peaks.search_by(q) {
+            Ok(i) | Err(i) => i,
+        });
+        report("PeakSet::search_by", baseline, baseline);
+        let elapsed = time(&queries, |q| match frozen.search_by(q) {
+            Ok(i) | Err(i) => i,
+        });
+        report("FrozenPeakSet::search_by", elapsed, baseline);
+
+        let baseline = time(&queries, |q| peaks.search(q, tol).unwrap_or_default());
+        report("PeakSet::search", baseline, baseline);
+        let elapsed = time(&queries, |q| frozen.search(q, tol).unwrap_or_default());
+        report("FrozenPeakSet::search", elapsed, baseline);
+        println!();
+    }
+}
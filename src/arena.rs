@@ -0,0 +1,169 @@
+//! Hold many peak lists in a single allocation.
+//!
+//! Keeping each spectrum of a large MS/MS run in its own [`PeakSetVec`] means one
+//! heap allocation per spectrum. A [`SpectrumArena`] instead appends every spectrum's
+//! peaks to one shared buffer and records where each spectrum starts, handing out
+//! [`PeakSetView`]s which support the same searches as an owned peak set.
+use std::marker::PhantomData;
+
+use crate::coordinate::{IndexedCoordinate, MZ};
+use crate::peak::CentroidPeak;
+use crate::peak_set::{PeakSetVec, PeakSetView};
+
+/// A collection of peak lists stored back-to-back in one contiguous buffer,
+/// with an offset table marking where each peak list begins.
+///
+/// Each peak list is sorted and indexed independently, so the peak indices
+/// are positions within that list, not within the whole arena.
+#[derive(Debug, Clone)]
+pub struct SpectrumArena<P: IndexedCoordinate<C>, C> {
+    peaks: Vec<P>,
+    offsets: Vec<usize>,
+    _c: PhantomData<C>,
+}
+
+impl<P: IndexedCoordinate<C>, C> Default for SpectrumArena<P, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> SpectrumArena<P, C> {
+    pub fn new() -> Self {
+        Self::with_capacity(0, 0)
+    }
+
+    /// Pre-allocate space for `spectra` peak lists holding `peaks` peaks in total
+    pub fn with_capacity(spectra: usize, peaks: usize) -> Self {
+        let mut offsets = Vec::with_capacity(spectra + 1);
+        offsets.push(0);
+        Self {
+            peaks: Vec::with_capacity(peaks),
+            offsets,
+            _c: PhantomData,
+        }
+    }
+
+    /// Append a new peak list to the arena, sorting and indexing it, and return
+    /// its position in the arena.
+    pub fn push<I: IntoIterator<Item = P>>(&mut self, peaks: I) -> usize {
+        let start = self.peaks.len();
+        self.peaks.extend(peaks);
+        PeakSetVec::<P, C>::_sort(&mut self.peaks[start..]);
+        self.offsets.push(self.peaks.len());
+        self.len() - 1
+    }
+
+    /// Append the contents of an owned peak set to the arena, returning its
+    /// position in the arena.
+    pub fn push_peak_set(&mut self, peaks: PeakSetVec<P, C>) -> usize {
+        self.push(peaks.peaks)
+    }
+
+    /// The number of peak lists in the arena
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of peaks across all peak lists in the arena
+    pub fn total_peaks(&self) -> usize {
+        self.peaks.len()
+    }
+
+    /// Get a view of the `i`th peak list, if it exists
+    pub fn get(&self, i: usize) -> Option<PeakSetView<'_, P, C>> {
+        if i >= self.len() {
+            return None;
+        }
+        let peaks = &self.peaks[self.offsets[i]..self.offsets[i + 1]];
+        // Safety: every segment is sorted when it is pushed and is never mutated afterwards
+        Some(unsafe { PeakSetView::wrap(peaks) })
+    }
+
+    /// Iterate over views of each peak list in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = PeakSetView<'_, P, C>> + '_ {
+        (0..self.len()).map(|i| self.get(i).unwrap())
+    }
+
+    /// Remove all peak lists, keeping the allocated memory
+    pub fn clear(&mut self) {
+        self.peaks.clear();
+        self.offsets.truncate(1);
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.peaks.shrink_to_fit();
+        self.offsets.shrink_to_fit();
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> FromIterator<PeakSetVec<P, C>> for SpectrumArena<P, C> {
+    fn from_iter<T: IntoIterator<Item = PeakSetVec<P, C>>>(iter: T) -> Self {
+        let mut arena = Self::new();
+        arena.extend(iter);
+        arena
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> Extend<PeakSetVec<P, C>> for SpectrumArena<P, C> {
+    fn extend<T: IntoIterator<Item = PeakSetVec<P, C>>>(&mut self, iter: T) {
+        for peaks in iter {
+            self.push_peak_set(peaks);
+        }
+    }
+}
+
+/// A [`SpectrumArena`] of [`CentroidPeak`] peak lists ordered by m/z
+pub type CentroidSpectrumArena = SpectrumArena<CentroidPeak, MZ>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use crate::test_data;
+    use crate::PeakSet;
+
+    #[test]
+    fn test_arena() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let n = peaks.len();
+
+        let mut arena = CentroidSpectrumArena::new();
+        assert!(arena.is_empty());
+        arena.push_peak_set(peaks.clone());
+        let i = arena.push(vec![
+            CentroidPeak::new(300.0, 5.0, 0),
+            CentroidPeak::new(100.0, 10.0, 0),
+        ]);
+        arena.push(Vec::new());
+        assert_eq!(i, 1);
+        assert_eq!(arena.len(), 3);
+        assert_eq!(arena.total_peaks(), n + 2);
+
+        let view = arena.get(0).unwrap();
+        assert_eq!(view.len(), n);
+        assert_eq!(
+            view.has_peak(773.4414, Tolerance::Da(0.01))
+                .map(|p| p.index),
+            peaks
+                .has_peak(773.4414, Tolerance::Da(0.01))
+                .map(|p| p.index)
+        );
+
+        let view = arena.get(1).unwrap();
+        assert_eq!(view[0].mz, 100.0);
+        assert_eq!(view[1].index, 1);
+        assert!(arena.get(2).unwrap().is_empty());
+        assert!(arena.get(3).is_none());
+
+        assert_eq!(arena.iter().map(|v| v.len()).sum::<usize>(), n + 2);
+
+        let arena: CentroidSpectrumArena = vec![PeakSet::empty(), peaks].into_iter().collect();
+        assert_eq!(arena.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+//! Estimate and remove a slowly varying intensity baseline.
+//!
+//! Chemical noise raises the floor of a spectrum or chromatogram unevenly along its
+//! coordinate, which inflates the intensity of every peak sitting on it and distorts
+//! anything scored on intensity. [`estimate_baseline`] follows that floor with a sliding
+//! window, and `subtract_baseline` on [`PeakSetVec`] and [`ProfileSignal`] removes it,
+//! clamping at zero and reporting how much intensity was removed.
+//!
+//! [`intensity_envelope`] follows both the floor and the ceiling of the signal, for drawing
+//! the band a signal occupies or for choosing thresholds that adapt to the local intensity.
+use std::collections::VecDeque;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::coordinate::IndexedCoordinate;
+use crate::peak::IntensityMeasurementMut;
+use crate::peak_set::PeakSetVec;
+use crate::profile::ProfileSignal;
+
+/// How to estimate the baseline from the intensities within a window
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BaselineMethod {
+    /// The smallest intensity in the window
+    #[default]
+    RollingMinimum,
+    /// The intensity at this percentile of the window, between 0 and 100, which is less
+    /// sensitive than the minimum to isolated dropouts
+    Percentile(f32),
+}
+
+/// An account of the intensity removed by a baseline correction
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BaselineReport {
+    /// The total intensity removed across all points
+    pub total_removed: f64,
+    /// The total intensity before correction
+    pub total_before: f64,
+    /// The number of points whose intensity was clamped to zero
+    pub clamped: usize,
+}
+
+impl BaselineReport {
+    /// The fraction of the original total intensity that was removed
+    pub fn fraction_removed(&self) -> f64 {
+        if self.total_before > 0.0 {
+            self.total_removed / self.total_before
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Estimate the baseline beneath each point of `intensities`, sampled at `coordinates`
+/// in ascending order, from the points within `window_width / 2` of it on either side.
+///
+/// # Panics
+/// If `coordinates` and `intensities` have different lengths, if `window_width` is negative
+/// or NaN, or if `method` is a percentile outside of `0..=100`
+pub fn estimate_baseline(
+    coordinates: &[f64],
+    intensities: &[f32],
+    window_width: f64,
+    method: BaselineMethod,
+) -> Vec<f32> {
+    assert_eq!(
+        coordinates.len(),
+        intensities.len(),
+        "Coordinates and intensities must be the same length"
+    );
+    assert!(
+        window_width >= 0.0,
+        "The window width must be a non-negative number, got {window_width}"
+    );
+    if let BaselineMethod::Percentile(p) = method {
+        assert!(
+            (0.0..=100.0).contains(&p),
+            "The baseline percentile must be between 0 and 100, got {p}"
+        );
+    }
+    let half_width = window_width / 2.0;
+    let mut start = 0;
+    let mut end = 0;
+    let mut window = Vec::new();
+    coordinates
+        .iter()
+        .map(|x| {
+            while coordinates[start] < x - half_width {
+                start += 1;
+            }
+            while end < coordinates.len() && coordinates[end] <= x + half_width {
+                end += 1;
+            }
+            let values = &intensities[start..end];
+            match method {
+                BaselineMethod::RollingMinimum => {
+                    values.iter().copied().fold(f32::INFINITY, f32::min)
+                }
+                BaselineMethod::Percentile(p) => {
+                    window.clear();
+                    window.extend_from_slice(values);
+                    window.sort_by(|a, b| a.total_cmp(b));
+                    let rank = (p / 100.0 * (window.len() - 1) as f32).round() as usize;
+                    window[rank]
+                }
+            }
+        })
+        .collect()
+}
+
+/// The smallest and largest intensity in a window around each point of a signal, found by
+/// [`intensity_envelope`]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IntensityEnvelope {
+    pub lower: Vec<f32>,
+    pub upper: Vec<f32>,
+}
+
+impl IntensityEnvelope {
+    /// The number of points covered
+    pub fn len(&self) -> usize {
+        self.lower.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_empty()
+    }
+
+    /// The difference between the upper and lower envelope at point `i`
+    pub fn spread(&self, i: usize) -> f32 {
+        self.upper[i] - self.lower[i]
+    }
+
+    /// Iterate over the lower and upper envelope at each point
+    pub fn iter(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.lower.iter().copied().zip(self.upper.iter().copied())
+    }
+}
+
+/// Find the smallest and largest intensity within `window_width / 2` on either side of each
+/// point of `intensities`, sampled at `coordinates` in ascending order.
+///
+/// Each envelope is maintained with a monotonic queue of the points in the window, so the
+/// whole signal is covered in linear time regardless of the window width.
+///
+/// # Panics
+/// If `coordinates` and `intensities` have different lengths, or if `window_width` is
+/// negative or NaN
+pub fn intensity_envelope(
+    coordinates: &[f64],
+    intensities: &[f32],
+    window_width: f64,
+) -> IntensityEnvelope {
+    assert_eq!(
+        coordinates.len(),
+        intensities.len(),
+        "Coordinates and intensities must be the same length"
+    );
+    assert!(
+        window_width >= 0.0,
+        "The window width must be a non-negative number, got {window_width}"
+    );
+    let half_width = window_width / 2.0;
+    let mut envelope = IntensityEnvelope {
+        lower: Vec::with_capacity(coordinates.len()),
+        upper: Vec::with_capacity(coordinates.len()),
+    };
+    // The positions of candidate minima with increasing intensity, and of candidate maxima
+    // with decreasing intensity
+    let mut minima: VecDeque<usize> = VecDeque::new();
+    let mut maxima: VecDeque<usize> = VecDeque::new();
+    let mut end = 0;
+    for x in coordinates {
+        let (first, last) = (x - half_width, x + half_width);
+        while end < coordinates.len() && coordinates[end] <= last {
+            let y = intensities[end];
+            while minima.back().is_some_and(|j| intensities[*j] >= y) {
+                minima.pop_back();
+            }
+            minima.push_back(end);
+            while maxima.back().is_some_and(|j| intensities[*j] <= y) {
+                maxima.pop_back();
+            }
+            maxima.push_back(end);
+            end += 1;
+        }
+        while minima.front().is_some_and(|j| coordinates[*j] < first) {
+            minima.pop_front();
+        }
+        while maxima.front().is_some_and(|j| coordinates[*j] < first) {
+            maxima.pop_front();
+        }
+        envelope.lower.push(intensities[minima[0]]);
+        envelope.upper.push(intensities[maxima[0]]);
+    }
+    envelope
+}
+
+/// Subtract `baseline` from `intensities` in place, clamping at zero
+fn subtract<'a>(
+    intensities: impl Iterator<Item = &'a mut f32>,
+    baseline: &[f32],
+) -> BaselineReport {
+    let mut report = BaselineReport::default();
+    for (intensity, base) in intensities.zip(baseline) {
+        report.total_before += *intensity as f64;
+        let corrected = (*intensity - base).max(0.0);
+        report.total_removed += (*intensity - corrected) as f64;
+        if corrected == 0.0 && *intensity > 0.0 {
+            report.clamped += 1;
+        }
+        *intensity = corrected;
+    }
+    report
+}
+
+impl<P: IndexedCoordinate<C> + IntensityMeasurementMut, C> PeakSetVec<P, C> {
+    /// Subtract the baseline estimated by [`estimate_baseline`] from every peak. Peaks
+    /// left with no intensity are kept, but counted in the returned report.
+    ///
+    /// # Panics
+    /// As [`estimate_baseline`] does
+    pub fn subtract_baseline(
+        &mut self,
+        window_width: f64,
+        method: BaselineMethod,
+    ) -> BaselineReport {
+        let coordinates: Vec<f64> = self.iter().map(|p| p.coordinate()).collect();
+        let intensities: Vec<f32> = self.iter().map(|p| p.intensity()).collect();
+        let baseline = estimate_baseline(&coordinates, &intensities, window_width, method);
+        subtract(self.iter_mut().map(|p| p.intensity_mut()), &baseline)
+    }
+
+    /// Find the [`intensity_envelope`] of the peaks
+    ///
+    /// # Panics
+    /// As [`intensity_envelope`] does
+    pub fn intensity_envelope(&self, window_width: f64) -> IntensityEnvelope {
+        let coordinates: Vec<f64> = self.iter().map(|p| p.coordinate()).collect();
+        let intensities: Vec<f32> = self.iter().map(|p| p.intensity()).collect();
+        intensity_envelope(&coordinates, &intensities, window_width)
+    }
+}
+
+impl<C> ProfileSignal<C> {
+    /// Subtract the baseline estimated by [`estimate_baseline`] from every sample
+    ///
+    /// # Panics
+    /// As [`estimate_baseline`] does
+    pub fn subtract_baseline(
+        &mut self,
+        window_width: f64,
+        method: BaselineMethod,
+    ) -> BaselineReport {
+        let baseline =
+            estimate_baseline(self.coordinates(), self.intensities(), window_width, method);
+        subtract(self.intensities_mut().iter_mut(), &baseline)
+    }
+
+    /// Find the [`intensity_envelope`] of the samples
+    ///
+    /// # Panics
+    /// As [`intensity_envelope`] does
+    pub fn intensity_envelope(&self, window_width: f64) -> IntensityEnvelope {
+        intensity_envelope(self.coordinates(), self.intensities(), window_width)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CentroidPeak, PeakSet, MZ};
+
+    #[test]
+    fn test_subtract_baseline() {
+        let coordinates: Vec<f64> = (0..20).map(|i| i as f64).collect();
+        let mut intensities: Vec<f32> = coordinates.iter().map(|x| 10.0 + *x as f32).collect();
+        intensities[10] += 100.0;
+
+        let mut signal = ProfileSignal::<MZ>::new(coordinates.clone(), intensities.clone());
+        let report = signal.subtract_baseline(4.0, BaselineMethod::RollingMinimum);
+        // Each point sits 2 units above the minimum 2 points to its left, except at the start
+        assert_eq!(signal.intensities()[0], 0.0);
+        assert_eq!(signal.intensities()[5], 2.0);
+        assert_eq!(signal.intensities()[10], 102.0);
+        assert_eq!(report.clamped, 1);
+        assert!(report.fraction_removed() > 0.5);
+
+        let mut peaks: PeakSet = coordinates
+            .iter()
+            .zip(intensities.iter())
+            .map(|(x, y)| CentroidPeak::new(*x + 100.0, *y, 0))
+            .collect();
+        let report = peaks.subtract_baseline(4.0, BaselineMethod::Percentile(50.0));
+        // The median of the window around the spike is its right neighbor
+        assert_eq!(peaks[10].intensity, 99.0);
+        assert_eq!(peaks.len(), 20);
+        let remaining: f64 = peaks.iter().map(|p| p.intensity as f64).sum();
+        assert!((report.total_before - report.total_removed - remaining).abs() < 1e-3);
+
+        for width in [-2.0, f64::NAN] {
+            let mut peaks = peaks.clone();
+            assert!(std::panic::catch_unwind(move || {
+                peaks.subtract_baseline(width, BaselineMethod::RollingMinimum)
+            })
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_intensity_envelope() {
+        let coordinates: Vec<f64> = (0..10).map(|i| i as f64).collect();
+        let intensities = vec![5.0, 1.0, 4.0, 8.0, 2.0, 2.0, 9.0, 3.0, 7.0, 6.0];
+        let signal = ProfileSignal::<MZ>::new(coordinates.clone(), intensities.clone());
+        let envelope = signal.intensity_envelope(2.0);
+        assert_eq!(envelope.len(), 10);
+        assert_eq!(
+            envelope.lower,
+            vec![1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 3.0, 3.0, 6.0]
+        );
+        assert_eq!(
+            envelope.upper,
+            vec![5.0, 5.0, 8.0, 8.0, 8.0, 9.0, 9.0, 9.0, 7.0, 7.0]
+        );
+        assert_eq!(envelope.spread(6), 7.0);
+
+        // The minimum envelope agrees with the rolling minimum baseline
+        let baseline = estimate_baseline(
+            &coordinates,
+            &intensities,
+            5.0,
+            BaselineMethod::RollingMinimum,
+        );
+        let envelope = intensity_envelope(&coordinates, &intensities, 5.0);
+        assert_eq!(envelope.lower, baseline);
+        assert!(envelope.iter().all(|(lo, hi)| lo <= hi));
+
+        // A zero-width window covers only the point itself
+        assert_eq!(signal.intensity_envelope(0.0).upper, intensities);
+        for width in [-2.0, f64::NAN] {
+            let signal = signal.clone();
+            assert!(std::panic::catch_unwind(move || signal.intensity_envelope(width)).is_err());
+        }
+
+        let peaks: PeakSet = coordinates
+            .iter()
+            .zip(intensities.iter())
+            .map(|(x, y)| CentroidPeak::new(*x, *y, 0))
+            .collect();
+        assert_eq!(peaks.intensity_envelope(5.0), envelope);
+        assert!(intensity_envelope(&[], &[], 1.0).is_empty());
+    }
+}
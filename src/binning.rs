@@ -0,0 +1,251 @@
+//! Rasterize peak lists onto a grid of fixed-width bins.
+//!
+//! Machine learning models and fast spectrum comparisons want every spectrum as a vector of
+//! the same length, with each position standing for the same coordinate interval.
+//! [`bin_peaks`] sums the intensities of a peak list into bins of a fixed width over a
+//! range, and [`BinnedSpectrum`] does the same with a choice of [`BinAggregation`] while
+//! remembering the grid, so that two spectra binned alike can be compared with
+//! [`BinnedSpectrum::dot`] and converted back into centroids with [`BinnedSpectrum::unbin`].
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::coordinate::{CoordinateLike, CoordinateRange, MZ};
+use crate::peak::{CentroidPeak, IntensityMeasurement};
+use crate::peak_set::PeakSet;
+use crate::queryable::PeakQueryable;
+
+/// How to combine the intensities of the peaks falling in the same bin
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BinAggregation {
+    /// The total intensity of the peaks in the bin
+    #[default]
+    Sum,
+    /// The intensity of the most intense peak in the bin
+    Max,
+    /// The mean intensity of the peaks in the bin
+    Mean,
+}
+
+/// The intensities of a peak list in bins of a fixed width along coordinate `C`, where bin
+/// `i` spans `[start + i * bin_width, start + (i + 1) * bin_width)`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BinnedSpectrum<C> {
+    start: f64,
+    bin_width: f64,
+    aggregation: BinAggregation,
+    values: Vec<f32>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _c: PhantomData<C>,
+}
+
+impl<C> BinnedSpectrum<C> {
+    /// Bin `peaks` into bins of `bin_width` covering `range`, combining the peaks of each
+    /// bin by `aggregation`. An open start begins the grid at zero, and an open end ends it
+    /// at the last peak, leaving no bins at all if there are no peaks. Peaks outside of
+    /// `range` are ignored.
+    ///
+    /// # Panics
+    /// If `bin_width` is not positive
+    pub fn new<A>(
+        peaks: &A,
+        bin_width: f64,
+        range: CoordinateRange<C>,
+        aggregation: BinAggregation,
+    ) -> Self
+    where
+        A: PeakQueryable<C> + ?Sized,
+        A::Peak: IntensityMeasurement,
+    {
+        assert!(
+            bin_width > 0.0,
+            "The bin width must be positive, got {bin_width}"
+        );
+        let start = range.start.unwrap_or(0.0);
+        let last = peaks.len().checked_sub(1).and_then(|i| peaks.get(i));
+        let end = range
+            .end
+            .or_else(|| last.map(|p| p.coordinate()))
+            .unwrap_or(f64::NEG_INFINITY);
+        let n_bins = if end < start {
+            0
+        } else {
+            ((end - start) / bin_width).floor() as usize + 1
+        };
+
+        let mut values = vec![0.0f32; n_bins];
+        let mut counts = vec![0usize; n_bins];
+        for peak in (0..peaks.len()).filter_map(|i| peaks.get(i)) {
+            let x = peak.coordinate();
+            if x < start || x > end {
+                continue;
+            }
+            let i = ((x - start) / bin_width).floor() as usize;
+            let (Some(value), Some(count)) = (values.get_mut(i), counts.get_mut(i)) else {
+                continue;
+            };
+            let intensity = peak.intensity();
+            *value = match aggregation {
+                BinAggregation::Sum | BinAggregation::Mean => *value + intensity,
+                BinAggregation::Max => value.max(intensity),
+            };
+            *count += 1;
+        }
+        if aggregation == BinAggregation::Mean {
+            for (value, count) in values.iter_mut().zip(counts) {
+                if count > 0 {
+                    *value /= count as f32;
+                }
+            }
+        }
+
+        Self {
+            start,
+            bin_width,
+            aggregation,
+            values,
+            _c: PhantomData,
+        }
+    }
+
+    /// The coordinate at which the first bin starts
+    pub fn start(&self) -> f64 {
+        self.start
+    }
+
+    pub fn bin_width(&self) -> f64 {
+        self.bin_width
+    }
+
+    pub fn aggregation(&self) -> BinAggregation {
+        self.aggregation
+    }
+
+    /// The number of bins
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The aggregated intensity of each bin
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn into_values(self) -> Vec<f32> {
+        self.values
+    }
+
+    /// The center of bin `i`
+    pub fn bin_center(&self, i: usize) -> f64 {
+        self.start + (i as f64 + 0.5) * self.bin_width
+    }
+
+    /// The bin containing coordinate `x`, or `None` if `x` is outside of the grid
+    pub fn bin_of(&self, x: f64) -> Option<usize> {
+        if x < self.start {
+            return None;
+        }
+        let i = ((x - self.start) / self.bin_width).floor() as usize;
+        (i < self.len()).then_some(i)
+    }
+
+    /// Whether `other` was binned on the same grid as this spectrum, so that their bins
+    /// correspond one-to-one
+    pub fn is_aligned_with(&self, other: &Self) -> bool {
+        self.start == other.start && self.bin_width == other.bin_width && self.len() == other.len()
+    }
+
+    /// The dot product of the bins of this spectrum and `other`, or `None` if they were not
+    /// binned on the same grid
+    pub fn dot(&self, other: &Self) -> Option<f64> {
+        self.is_aligned_with(other).then(|| {
+            self.values
+                .iter()
+                .zip(other.values.iter())
+                .map(|(a, b)| *a as f64 * *b as f64)
+                .sum()
+        })
+    }
+}
+
+impl BinnedSpectrum<MZ> {
+    /// Convert the non-empty bins back into centroids, as [`unbin`] does
+    pub fn unbin(&self) -> PeakSet {
+        unbin(&self.values, self.start, self.bin_width)
+    }
+}
+
+/// Sum the intensities of `peaks` into bins of `bin_width` covering `range`, as
+/// [`BinnedSpectrum::new`] does with [`BinAggregation::Sum`]
+///
+/// # Panics
+/// If `bin_width` is not positive
+pub fn bin_peaks<C, A>(peaks: &A, bin_width: f64, range: CoordinateRange<C>) -> Vec<f32>
+where
+    A: PeakQueryable<C> + ?Sized,
+    A::Peak: IntensityMeasurement,
+{
+    BinnedSpectrum::new(peaks, bin_width, range, BinAggregation::Sum).into_values()
+}
+
+/// Convert binned intensities back into centroids, one at the center of each bin with a
+/// positive intensity, where the first bin starts at `start`. The coordinates are only as
+/// precise as the bin width.
+pub fn unbin(values: &[f32], start: f64, bin_width: f64) -> PeakSet {
+    PeakSet::new(
+        values
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value > 0.0)
+            .map(|(i, value)| CentroidPeak::new(start + (i as f64 + 0.5) * bin_width, *value, 0))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_binning() {
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(100.2, 10.0, 0),
+            CentroidPeak::new(100.7, 30.0, 0),
+            CentroidPeak::new(102.5, 5.0, 0),
+            CentroidPeak::new(110.0, 8.0, 0),
+        ]);
+        let range = CoordinateRange::new(Some(100.0), Some(105.0));
+        assert_eq!(
+            bin_peaks(&peaks, 1.0, range),
+            vec![40.0, 0.0, 5.0, 0.0, 0.0, 0.0]
+        );
+
+        let max = BinnedSpectrum::new(&peaks, 1.0, range, BinAggregation::Max);
+        assert_eq!(max.values()[0], 30.0);
+        let mean = BinnedSpectrum::new(&peaks, 1.0, range, BinAggregation::Mean);
+        assert_eq!(mean.values()[0], 20.0);
+        assert_eq!(mean.bin_of(102.9), Some(2));
+        assert_eq!(mean.bin_of(99.0), None);
+        assert_eq!(max.dot(&mean), Some(30.0 * 20.0 + 25.0));
+
+        let open =
+            BinnedSpectrum::new(&peaks, 1.0, CoordinateRange::default(), BinAggregation::Sum);
+        assert_eq!(open.len(), 111);
+        assert_eq!(open.values()[110], 8.0);
+        assert_eq!(open.dot(&max), None);
+
+        let centroids = max.unbin();
+        assert_eq!(centroids.len(), 2);
+        assert_eq!(centroids[0].mz, 100.5);
+        assert_eq!(centroids[1].intensity, 5.0);
+
+        assert!(bin_peaks(&PeakSet::empty(), 1.0, CoordinateRange::<MZ>::default()).is_empty());
+    }
+}
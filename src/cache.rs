@@ -0,0 +1,188 @@
+//! Memoize repeated searches against the same peak collection.
+//!
+//! Scoring engines often query one spectrum for the same theoretical coordinates many
+//! times over, once for each candidate which shares a fragment. [`CachedSearcher`] wraps
+//! a borrowed [`PeakCollection`] and remembers the results of
+//! [`PeakCollection::all_peaks_for`] for the most recently used queries, evicting the
+//! least recently used entry when it is full.
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+
+use crate::coordinate::CoordinateLike;
+use crate::mass_error::Tolerance;
+use crate::peak_set::PeakCollection;
+
+/// The default number of queries remembered by a [`CachedSearcher`]
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// The default spacing between distinguishable queries, in coordinate units
+pub const DEFAULT_QUERY_QUANTUM: f64 = 1e-6;
+
+/// A query rounded to a multiple of the searcher's quantum, and the kind and
+/// magnitude of its tolerance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct QueryKey {
+    query: i64,
+    tolerance_kind: u8,
+    tolerance_bits: (u64, u64),
+}
+
+/// A least-recently-used cache of search results over a borrowed [`PeakCollection`]
+#[derive(Debug)]
+pub struct CachedSearcher<'a, P: CoordinateLike<C>, C, T: PeakCollection<P, C>>
+where
+    T::Output: CoordinateLike<C>,
+{
+    collection: &'a T,
+    capacity: usize,
+    quantum: f64,
+    entries: HashMap<QueryKey, (&'a [P], u64)>,
+    recency: BTreeMap<u64, QueryKey>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    _c: PhantomData<C>,
+}
+
+impl<'a, P: CoordinateLike<C>, C, T: PeakCollection<P, C>> CachedSearcher<'a, P, C, T>
+where
+    T::Output: CoordinateLike<C>,
+{
+    /// Create a cache over `collection` remembering up to [`DEFAULT_CACHE_CAPACITY`] queries,
+    /// treating queries within [`DEFAULT_QUERY_QUANTUM`] of each other as the same
+    pub fn new(collection: &'a T) -> Self {
+        Self::with_capacity(collection, DEFAULT_CACHE_CAPACITY, DEFAULT_QUERY_QUANTUM)
+    }
+
+    /// Create a cache over `collection` remembering up to `capacity` queries, rounding each
+    /// query to the nearest multiple of `quantum` to decide if it has been seen before.
+    ///
+    /// # Panics
+    /// If `capacity` is zero or `quantum` is not positive
+    pub fn with_capacity(collection: &'a T, capacity: usize, quantum: f64) -> Self {
+        assert!(capacity > 0, "The cache capacity must be positive");
+        assert!(
+            quantum > 0.0,
+            "The query quantum must be positive, got {quantum}"
+        );
+        Self {
+            collection,
+            capacity,
+            quantum,
+            entries: HashMap::with_capacity(capacity),
+            recency: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+            _c: PhantomData,
+        }
+    }
+
+    fn key(&self, query: f64, error_tolerance: Tolerance) -> QueryKey {
+        let (tolerance_kind, tolerance_bits) = match error_tolerance {
+            Tolerance::PPM(tol) => (0, (tol.to_bits(), 0)),
+            Tolerance::Da(tol) => (1, (tol.to_bits(), 0)),
+            Tolerance::Milli(tol) => (2, (tol.to_bits(), 0)),
+            Tolerance::Hybrid(ppm, da) => (3, (ppm.to_bits(), da.to_bits())),
+        };
+        QueryKey {
+            query: (query / self.quantum).round() as i64,
+            tolerance_kind,
+            tolerance_bits,
+        }
+    }
+
+    /// Find all peaks which could match `query` within `error_tolerance`, as
+    /// [`PeakCollection::all_peaks_for`] would, re-using a previous result if the same
+    /// query was made recently.
+    pub fn all_peaks_for(&mut self, query: f64, error_tolerance: Tolerance) -> &'a [P] {
+        let key = self.key(query, error_tolerance);
+        self.tick += 1;
+        if let Some((peaks, last_used)) = self.entries.get_mut(&key) {
+            self.recency.remove(last_used);
+            *last_used = self.tick;
+            self.recency.insert(self.tick, key);
+            self.hits += 1;
+            return peaks;
+        }
+
+        self.misses += 1;
+        let peaks = self.collection.all_peaks_for(query, error_tolerance);
+        if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (peaks, self.tick));
+        self.recency.insert(self.tick, key);
+        peaks
+    }
+
+    /// The wrapped collection
+    pub fn collection(&self) -> &'a T {
+        self.collection
+    }
+
+    /// The number of queries currently remembered
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of queries answered from the cache and the number which
+    /// required a search, as `(hits, misses)`
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+
+    /// Forget all remembered queries and reset the hit and miss counts
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_data;
+
+    #[test]
+    fn test_cached_searcher() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let mut cache = CachedSearcher::with_capacity(&peaks, 2, 1e-6);
+
+        let expected = peaks.all_peaks_for(773.4414, Tolerance::Da(1.0));
+        assert_eq!(cache.all_peaks_for(773.4414, Tolerance::Da(1.0)), expected);
+        assert_eq!(cache.all_peaks_for(773.4414, Tolerance::Da(1.0)), expected);
+        assert_eq!(cache.stats(), (1, 1));
+
+        // Same query, different tolerance
+        cache.all_peaks_for(773.4414, Tolerance::PPM(1000.0));
+        assert_eq!(cache.stats(), (1, 2));
+        assert_eq!(cache.len(), 2);
+
+        // Refresh the first entry, then evict the second
+        cache.all_peaks_for(773.4414, Tolerance::Da(1.0));
+        cache.all_peaks_for(500.0, Tolerance::Da(1.0));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats(), (2, 3));
+        cache.all_peaks_for(773.4414, Tolerance::Da(1.0));
+        assert_eq!(cache.stats(), (3, 3));
+        cache.all_peaks_for(773.4414, Tolerance::PPM(1000.0));
+        assert_eq!(cache.stats(), (3, 4));
+
+        cache.clear();
+        assert!(cache.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,627 @@
+//! Utilities for reasoning about the charge states of peaks.
+//!
+//! When the same species is observed in several places, e.g. in different isolation windows or
+//! as members of an isotopic or adduct group, the charge states assigned to each observation may
+//! disagree. [`consensus_charge`] settles on a single charge state by intensity-weighted voting and
+//! [`harmonize_charges`] applies that vote to groups of peaks in a collection.
+//!
+//! Electron-driven dissociation methods such as ETD and UVPD leave behind abundant
+//! charge-reduced precursor species, which [`charge_reduced_species`] predicts so they can be
+//! annotated or removed.
+//!
+//! A [`PolarizedPeakSet`] tags a collection with the [`Polarity`] it was acquired in so that
+//! peaks of the wrong sign can be detected before collections are merged.
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Display;
+use std::ops::{Deref, DerefMut};
+
+use crate::coordinate::{IndexedCoordinate, MZ, PROTON};
+use crate::mass_error::Tolerance;
+use crate::panel::{MZTargetPanel, Target};
+use crate::peak::{DeconvolutedPeak, IntensityMeasurement, KnownCharge, KnownChargeMut};
+use crate::peak_set::{PeakCollection, PeakCollectionMut, PeakSetVec};
+
+/// The mass of an electron, in Daltons
+pub const ELECTRON_MASS: f64 = 0.000548579909;
+
+/// How to choose between charge states which received the same total intensity
+/// in [`consensus_charge`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChargeTieBreak {
+    /// Choose the charge state with the smallest absolute value
+    Lowest,
+    /// Choose the charge state with the largest absolute value
+    Highest,
+    /// Choose the charge state of the single most intense peak among the tied candidates
+    #[default]
+    MostIntensePeak,
+    /// Do not choose, report no consensus
+    Unresolved,
+}
+
+/// Find the charge state that the members of `group` agree on, weighting each member's vote
+/// by its intensity.
+///
+/// Members with a charge of zero are treated as having an unknown charge and do not vote. Returns
+/// `None` if no member votes or if a tie could not be resolved under `tie_break`.
+pub fn consensus_charge<P: KnownCharge + IntensityMeasurement>(
+    group: &[P],
+    tie_break: ChargeTieBreak,
+) -> Option<i32> {
+    let mut votes: HashMap<i32, (f64, f32)> = HashMap::new();
+    for member in group {
+        let z = member.charge();
+        if z == 0 {
+            continue;
+        }
+        let entry = votes.entry(z).or_insert((0.0, 0.0));
+        entry.0 += member.intensity() as f64;
+        entry.1 = entry.1.max(member.intensity());
+    }
+
+    let best_total = votes
+        .values()
+        .map(|(total, _)| *total)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let mut tied: Vec<(i32, f32)> = votes
+        .into_iter()
+        .filter(|(_, (total, _))| (best_total - *total).abs() <= best_total.abs() * 1e-9)
+        .map(|(z, (_, most_intense))| (z, most_intense))
+        .collect();
+
+    match tied.len() {
+        0 => None,
+        1 => Some(tied[0].0),
+        _ => {
+            // Order by magnitude, then prefer positive charges for a deterministic result
+            tied.sort_by_key(|(z, _)| (z.unsigned_abs(), -z));
+            match tie_break {
+                ChargeTieBreak::Lowest => tied.first().map(|(z, _)| *z),
+                ChargeTieBreak::Highest => tied.last().map(|(z, _)| *z),
+                ChargeTieBreak::MostIntensePeak => {
+                    let most_intense = tied.iter().map(|(_, i)| *i).fold(f32::MIN, f32::max);
+                    let mut it = tied.iter().filter(|(_, i)| *i == most_intense);
+                    match (it.next(), it.next()) {
+                        (Some((z, _)), None) => Some(*z),
+                        _ => None,
+                    }
+                }
+                ChargeTieBreak::Unresolved => None,
+            }
+        }
+    }
+}
+
+/// For each group of indices into `peaks`, compute the [`consensus_charge`] of the group's
+/// members and assign it to all of them.
+///
+/// Returns the number of peaks whose charge state was changed. Groups without a consensus are
+/// left untouched.
+///
+/// # Panics
+/// If any index in `groups` is out of bounds for `peaks`
+pub fn harmonize_charges<P: KnownChargeMut + IntensityMeasurement, G: AsRef<[usize]>>(
+    peaks: &mut [P],
+    groups: &[G],
+    tie_break: ChargeTieBreak,
+) -> usize {
+    let mut changed = 0;
+    for group in groups {
+        let group = group.as_ref();
+        let members: Vec<&P> = group.iter().map(|i| &peaks[*i]).collect();
+        if let Some(z) = consensus_charge(&members, tie_break) {
+            for i in group {
+                let charge = peaks[*i].charge_mut();
+                if *charge != z {
+                    *charge = z;
+                    changed += 1;
+                }
+            }
+        }
+    }
+    changed
+}
+
+impl<P: IndexedCoordinate<C> + KnownChargeMut + IntensityMeasurement, C> PeakSetVec<P, C> {
+    /// Assign each group of peaks, given by their positions in this collection,
+    /// their [`consensus_charge`], restoring the collection's ordering afterwards.
+    ///
+    /// See [`harmonize_charges`]
+    pub fn harmonize_charges<G: AsRef<[usize]>>(
+        &mut self,
+        groups: &[G],
+        tie_break: ChargeTieBreak,
+    ) -> usize {
+        let changed = harmonize_charges(self.as_mut_slice(), groups, tie_break);
+        if changed > 0 {
+            self.sort();
+        }
+        changed
+    }
+}
+
+/// A precursor ion after it has captured or received `electrons_transferred` electrons
+/// without dissociating
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChargeReducedSpecies {
+    /// The charge state of the species
+    pub charge: i32,
+    /// The number of electrons transferred to the precursor
+    pub electrons_transferred: i32,
+    /// The expected m/z of the species
+    pub mz: f64,
+}
+
+/// Predict the m/z of each charge-reduced species of a precursor of `neutral_mass` and
+/// positive charge `charge`, from the intact precursor at `charge` down to charge 1.
+///
+/// The charge-reduced species keep all of the precursor's protons and gain the mass of
+/// the transferred electrons. Returns an empty list if `charge` is not positive.
+pub fn charge_reduced_species(neutral_mass: f64, charge: i32) -> Vec<ChargeReducedSpecies> {
+    let precursor_mass = neutral_mass + PROTON * charge as f64;
+    (0..charge.max(0))
+        .map(|electrons_transferred| {
+            let z = charge - electrons_transferred;
+            let mass = precursor_mass + ELECTRON_MASS * electrons_transferred as f64;
+            ChargeReducedSpecies {
+                charge: z,
+                electrons_transferred,
+                mz: mass / z as f64,
+            }
+        })
+        .collect()
+}
+
+impl DeconvolutedPeak {
+    /// Predict the charge-reduced species of this peak as a precursor ion.
+    ///
+    /// See [`charge_reduced_species`]
+    pub fn charge_reduced_species(&self) -> Vec<ChargeReducedSpecies> {
+        charge_reduced_species(self.neutral_mass, self.charge)
+    }
+
+    /// Build a [`MZTargetPanel`] of this peak's charge-reduced species, named by their
+    /// charge state as `"z=<charge>"`, each to be matched within `tolerance`.
+    pub fn charge_reduced_targets(&self, tolerance: Tolerance) -> MZTargetPanel {
+        self.charge_reduced_species()
+            .into_iter()
+            .map(|species| Target::new(format!("z={}", species.charge), species.mz, tolerance))
+            .collect()
+    }
+}
+
+impl<P: IndexedCoordinate<MZ>> PeakSetVec<P, MZ> {
+    /// Remove every peak within `tolerance` of a charge-reduced species of `precursor`,
+    /// including the intact precursor, and return the number of peaks removed.
+    pub fn remove_charge_reduced_species(
+        &mut self,
+        precursor: &DeconvolutedPeak,
+        tolerance: Tolerance,
+    ) -> usize {
+        let species = precursor.charge_reduced_species();
+        let n = self.len();
+        self.peaks.retain(|p| {
+            let mz = p.coordinate();
+            !species.iter().any(|s| tolerance.test(mz, s.mz))
+        });
+        let removed = n - self.len();
+        if removed > 0 {
+            self.sort();
+        }
+        removed
+    }
+}
+
+/// The minimum number of consecutive charge states reported as a [`ChargeLadder`]
+pub const MIN_CHARGE_LADDER_LENGTH: usize = 3;
+
+/// A series of peaks consistent with one species observed at consecutive charge states
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChargeLadder {
+    /// The intensity-weighted mean neutral mass implied by the members
+    pub neutral_mass: f64,
+    /// The position of each member peak and the charge assigned to it, in increasing
+    /// charge order
+    pub members: Vec<(usize, i32)>,
+    /// The summed intensity of the members
+    pub intensity: f32,
+}
+
+impl ChargeLadder {
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// The lowest and highest charge states in the ladder
+    pub fn charge_range(&self) -> Option<(i32, i32)> {
+        Some((self.members.first()?.1, self.members.last()?.1))
+    }
+}
+
+impl<P: IndexedCoordinate<MZ> + IntensityMeasurement> PeakSetVec<P, MZ> {
+    /// Find series of at least [`MIN_CHARGE_LADDER_LENGTH`] peaks whose m/z values are
+    /// consistent with the same protonated species at consecutive charge states up to
+    /// `max_charge`, matching each predicted m/z within `tolerance`.
+    ///
+    /// A ladder for a species also fits a species of half its mass at half the charges
+    /// where those are consecutive, so each peak is assigned to at most one ladder,
+    /// preferring the longest ladders. Ladders are returned in decreasing order of total
+    /// intensity.
+    pub fn detect_charge_ladders(
+        &self,
+        tolerance: Tolerance,
+        max_charge: i32,
+    ) -> Vec<ChargeLadder> {
+        let mut candidates = Vec::new();
+        for (seed, peak) in self.peaks.iter().enumerate() {
+            for z in 1..max_charge {
+                let neutral_mass = (peak.coordinate() - PROTON) * z as f64;
+                let predict = |charge: i32| neutral_mass / charge as f64 + PROTON;
+                // Only start a ladder at its lowest charge state
+                if z > 1 && self.search(predict(z - 1), tolerance).is_some() {
+                    continue;
+                }
+                let mut members = vec![(seed, z)];
+                for charge in (z + 1)..=max_charge {
+                    match self.search(predict(charge), tolerance) {
+                        Some(i) => members.push((i, charge)),
+                        None => break,
+                    }
+                }
+                if members.len() < MIN_CHARGE_LADDER_LENGTH {
+                    continue;
+                }
+                let (acc, intensity) =
+                    members
+                        .iter()
+                        .fold((0.0, 0.0f32), |(acc, intensity), (i, charge)| {
+                            let member = &self[*i];
+                            let mass = (member.coordinate() - PROTON) * *charge as f64;
+                            (
+                                acc + mass * member.intensity() as f64,
+                                intensity + member.intensity(),
+                            )
+                        });
+                candidates.push(ChargeLadder {
+                    neutral_mass: if intensity > 0.0 {
+                        acc / intensity as f64
+                    } else {
+                        neutral_mass
+                    },
+                    members,
+                    intensity,
+                });
+            }
+        }
+
+        candidates.sort_by(|a, b| {
+            b.len()
+                .cmp(&a.len())
+                .then(b.intensity.total_cmp(&a.intensity))
+        });
+        let mut assigned = HashSet::new();
+        let mut ladders: Vec<ChargeLadder> = candidates
+            .into_iter()
+            .filter(|ladder| {
+                if ladder.members.iter().any(|(i, _)| assigned.contains(i)) {
+                    return false;
+                }
+                assigned.extend(ladder.members.iter().map(|(i, _)| *i));
+                true
+            })
+            .collect();
+        ladders.sort_by(|a, b| b.intensity.total_cmp(&a.intensity));
+        ladders
+    }
+}
+
+/// The sign of the charges carried by ions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Polarity {
+    Positive,
+    Negative,
+}
+
+impl Polarity {
+    /// The polarity of a charge state, or `None` if `charge` is zero, which is
+    /// taken to mean the charge is unknown
+    pub fn from_charge(charge: i32) -> Option<Self> {
+        match charge.signum() {
+            1 => Some(Self::Positive),
+            -1 => Some(Self::Negative),
+            _ => None,
+        }
+    }
+
+    /// Check if `charge` has this polarity. A charge of zero matches either polarity.
+    pub fn admits(&self, charge: i32) -> bool {
+        Self::from_charge(charge).is_none_or(|p| p == *self)
+    }
+}
+
+impl Display for Polarity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Positive => f.write_str("positive"),
+            Self::Negative => f.write_str("negative"),
+        }
+    }
+}
+
+/// A peak whose charge disagrees with the polarity of its collection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolarityError {
+    /// The polarity the collection was expected to have
+    pub expected: Polarity,
+    /// The position of the first offending peak
+    pub index: usize,
+    /// The charge of the first offending peak
+    pub charge: i32,
+}
+
+impl Display for PolarityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Expected {} polarity, but the peak at {} has charge {}",
+            self.expected, self.index, self.charge
+        )
+    }
+}
+
+impl Error for PolarityError {}
+
+/// Check that every charged peak in `peaks` has the same sign as `expected`, or as the
+/// first charged peak if `expected` is `None`. Peaks with a charge of zero are ignored.
+///
+/// Returns the polarity of the peaks, or `None` if none of them are charged.
+pub fn validate_polarity<'a, P: KnownCharge + 'a, I: IntoIterator<Item = &'a P>>(
+    peaks: I,
+    expected: Option<Polarity>,
+) -> Result<Option<Polarity>, PolarityError> {
+    let mut polarity = expected;
+    for (index, peak) in peaks.into_iter().enumerate() {
+        let charge = peak.charge();
+        match (polarity, Polarity::from_charge(charge)) {
+            (_, None) => {}
+            (None, observed) => polarity = observed,
+            (Some(expected), Some(observed)) if expected != observed => {
+                return Err(PolarityError {
+                    expected,
+                    index,
+                    charge,
+                })
+            }
+            _ => {}
+        }
+    }
+    Ok(polarity)
+}
+
+/// A [`PeakSetVec`] tagged with the [`Polarity`] its peaks were acquired in
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "P: serde::Serialize",
+        deserialize = "P: serde::Deserialize<'de>"
+    ))
+)]
+pub struct PolarizedPeakSet<P: IndexedCoordinate<C>, C> {
+    pub peaks: PeakSetVec<P, C>,
+    /// The polarity of the peaks, if known
+    pub polarity: Option<Polarity>,
+}
+
+impl<P: IndexedCoordinate<C> + KnownCharge, C> PolarizedPeakSet<P, C> {
+    pub fn new(peaks: PeakSetVec<P, C>, polarity: Option<Polarity>) -> Self {
+        Self { peaks, polarity }
+    }
+
+    /// Tag `peaks` with the polarity shared by all of their charges
+    pub fn infer(peaks: PeakSetVec<P, C>) -> Result<Self, PolarityError> {
+        let polarity = validate_polarity(peaks.iter(), None)?;
+        Ok(Self::new(peaks, polarity))
+    }
+
+    /// Check that every charged peak agrees with [`PolarizedPeakSet::polarity`], or with
+    /// each other if the polarity is not known.
+    pub fn validate_polarity(&self) -> Result<Option<Polarity>, PolarityError> {
+        validate_polarity(self.peaks.iter(), self.polarity)
+    }
+
+    /// Iterate over the peaks whose charge has `polarity`, including those with a
+    /// charge of zero
+    pub fn iter_polarity(&self, polarity: Polarity) -> impl Iterator<Item = &P> + '_ {
+        self.peaks
+            .iter()
+            .filter(move |p| polarity.admits(p.charge()))
+    }
+
+    /// Add the peaks of `other` to this collection, provided both collections are
+    /// valid and share a polarity. Otherwise neither collection is changed.
+    pub fn merge(&mut self, other: Self) -> Result<(), PolarityError> {
+        let polarity = self.validate_polarity()?;
+        let polarity = validate_polarity(other.peaks.iter(), polarity.or(other.polarity))?;
+        self.polarity = polarity;
+        self.peaks.extend(other.peaks.peaks);
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> PeakSetVec<P, C> {
+        self.peaks
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> Deref for PolarizedPeakSet<P, C> {
+    type Target = PeakSetVec<P, C>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.peaks
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> DerefMut for PolarizedPeakSet<P, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.peaks
+    }
+}
+
+impl<P: IndexedCoordinate<C> + KnownCharge, C> PeakSetVec<P, C> {
+    /// Tag this collection with `polarity`
+    pub fn with_polarity(self, polarity: Polarity) -> PolarizedPeakSet<P, C> {
+        PolarizedPeakSet::new(self, Some(polarity))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CentroidPeak, DeconvolutedPeakSet, PeakSet};
+
+    #[test]
+    fn test_consensus_charge() {
+        let group = vec![
+            DeconvolutedPeak::new(1000.0, 500.0, 2, 0),
+            DeconvolutedPeak::new(1000.0, 200.0, 3, 1),
+            DeconvolutedPeak::new(1000.0, 200.0, 3, 2),
+            DeconvolutedPeak::new(1000.0, 900.0, 0, 3),
+        ];
+        assert_eq!(
+            consensus_charge(&group, ChargeTieBreak::Unresolved),
+            Some(2)
+        );
+
+        let group = vec![
+            DeconvolutedPeak::new(1000.0, 300.0, 2, 0),
+            DeconvolutedPeak::new(1000.0, 200.0, 3, 1),
+            DeconvolutedPeak::new(1000.0, 100.0, 3, 2),
+        ];
+        assert_eq!(consensus_charge(&group, ChargeTieBreak::Unresolved), None);
+        assert_eq!(consensus_charge(&group, ChargeTieBreak::Lowest), Some(2));
+        assert_eq!(consensus_charge(&group, ChargeTieBreak::Highest), Some(3));
+        assert_eq!(
+            consensus_charge(&group, ChargeTieBreak::MostIntensePeak),
+            Some(2)
+        );
+
+        let empty: Vec<DeconvolutedPeak> = Vec::new();
+        assert_eq!(consensus_charge(&empty, ChargeTieBreak::Lowest), None);
+    }
+
+    #[test]
+    fn test_charge_reduced_species() {
+        let precursor = DeconvolutedPeak::new(2000.0, 1e5, 3, 0);
+        let species = precursor.charge_reduced_species();
+        assert_eq!(species.len(), 3);
+        assert_eq!(species[0].charge, 3);
+        assert!((species[0].mz - precursor.mz()).abs() < 1e-9);
+        assert_eq!(species[2].charge, 1);
+        assert_eq!(species[2].electrons_transferred, 2);
+        let expected = 2000.0 + 3.0 * PROTON + 2.0 * ELECTRON_MASS;
+        assert!((species[2].mz - expected).abs() < 1e-9);
+        assert!(charge_reduced_species(2000.0, 0).is_empty());
+
+        let panel = precursor.charge_reduced_targets(Tolerance::PPM(10.0));
+        assert_eq!(panel.len(), 3);
+        assert!((panel.get("z=2").unwrap().coordinate - species[1].mz).abs() < 1e-9);
+
+        let mut peaks: PeakSetVec<CentroidPeak, MZ> = PeakSetVec::new(vec![
+            CentroidPeak::new(species[1].mz, 500.0, 0),
+            CentroidPeak::new(500.0, 100.0, 0),
+            CentroidPeak::new(species[0].mz + 1e-4, 800.0, 0),
+        ]);
+        let removed = peaks.remove_charge_reduced_species(&precursor, Tolerance::PPM(10.0));
+        assert_eq!(removed, 2);
+        assert_eq!(peaks.len(), 1);
+        assert_eq!(peaks[0].mz, 500.0);
+    }
+
+    #[test]
+    fn test_harmonize_charges() {
+        let mut peaks = DeconvolutedPeakSet::new(vec![
+            DeconvolutedPeak::new(1000.0, 500.0, 2, 0),
+            DeconvolutedPeak::new(1001.0, 200.0, 3, 1),
+            DeconvolutedPeak::new(1002.0, 100.0, 2, 2),
+            DeconvolutedPeak::new(1500.0, 100.0, 4, 3),
+        ]);
+        let changed = peaks.harmonize_charges(&[vec![0, 1, 2], vec![3]], ChargeTieBreak::default());
+        assert_eq!(changed, 1);
+        assert!(peaks.iter().take(3).all(|p| p.charge == 2));
+        assert_eq!(peaks[3].charge, 4);
+    }
+
+    #[test]
+    fn test_polarity() {
+        let peaks = DeconvolutedPeakSet::new(vec![
+            DeconvolutedPeak::new(1000.0, 500.0, 2, 0),
+            DeconvolutedPeak::new(1001.0, 200.0, 0, 1),
+            DeconvolutedPeak::new(1002.0, 100.0, 3, 2),
+        ]);
+        let mut positive = PolarizedPeakSet::infer(peaks).unwrap();
+        assert_eq!(positive.polarity, Some(Polarity::Positive));
+
+        let negative = DeconvolutedPeakSet::new(vec![
+            DeconvolutedPeak::new(900.0, 500.0, -2, 0),
+            DeconvolutedPeak::new(1100.0, 100.0, -1, 1),
+        ])
+        .with_polarity(Polarity::Negative);
+        assert!(negative.validate_polarity().is_ok());
+
+        let err = positive.merge(negative.clone()).unwrap_err();
+        assert_eq!(err.index, 0);
+        assert_eq!(err.charge, -2);
+        assert_eq!(positive.len(), 3);
+
+        let mut mixed = positive.clone();
+        mixed.peaks.extend(negative.peaks.peaks);
+        assert!(mixed.validate_polarity().is_err());
+        assert_eq!(mixed.iter_polarity(Polarity::Negative).count(), 3);
+        assert_eq!(mixed.iter_polarity(Polarity::Positive).count(), 3);
+        assert_eq!(Polarity::from_charge(0), None);
+    }
+
+    #[test]
+    fn test_detect_charge_ladders() {
+        let neutral_mass = 14305.14;
+        let mut peaks: Vec<CentroidPeak> = (10..=15)
+            .map(|z| {
+                let mz = neutral_mass / z as f64 + PROTON;
+                CentroidPeak::new(mz, 1000.0 * z as f32, 0)
+            })
+            .collect();
+        peaks.push(CentroidPeak::new(1111.11, 50.0, 0));
+        peaks.push(CentroidPeak::new(1500.5, 50.0, 0));
+        let peaks = PeakSet::new(peaks);
+
+        let ladders = peaks.detect_charge_ladders(Tolerance::PPM(10.0), 30);
+        assert_eq!(ladders.len(), 1);
+        let ladder = &ladders[0];
+        assert_eq!(ladder.len(), 6);
+        assert_eq!(ladder.charge_range(), Some((10, 15)));
+        assert!((ladder.neutral_mass - neutral_mass).abs() < 1e-6);
+
+        assert!(peaks
+            .detect_charge_ladders(Tolerance::PPM(10.0), 4)
+            .is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() -> serde_json::Result<()> {
+        let peaks = DeconvolutedPeakSet::new(vec![DeconvolutedPeak::new(900.0, 500.0, -2, 0)])
+            .with_polarity(Polarity::Negative);
+        let text = serde_json::to_string(&peaks)?;
+        let dup: PolarizedPeakSet<DeconvolutedPeak, crate::Mass> = serde_json::from_str(&text)?;
+        assert_eq!(dup.polarity, peaks.polarity);
+        assert_eq!(dup.peaks, peaks.peaks);
+        Ok(())
+    }
+}
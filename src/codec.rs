@@ -0,0 +1,310 @@
+//! Lossy compression of m/z and intensity arrays with bounded error.
+//!
+//! Archived peak lists rarely need full `f64` precision. [`MZCodec`] quantizes m/z values
+//! on a logarithmic grid whose spacing guarantees a maximum error in parts-per-million,
+//! then stores the differences between consecutive grid positions as variable-length
+//! integers, which are small for sorted m/z arrays. [`IntensityCodec`] does the same
+//! for intensities with a maximum relative error.
+//!
+//! Each encoded buffer records the grid spacing it was encoded with, so it can be decoded
+//! without knowing the original error bound.
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::peak::{CentroidLike, CentroidPeak};
+use crate::peak_set::PeakSet;
+
+/// A failure to encode or decode an array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError {
+    /// The value at this position can't be represented, e.g. a non-positive or non-finite m/z
+    InvalidValue(usize),
+    /// The buffer ended in the middle of a value
+    UnexpectedEnd,
+    /// A variable-length integer was too long to fit in 64 bits
+    Overflow,
+    /// The grid spacing stored in the buffer is not a positive, finite number
+    InvalidStep,
+    /// The m/z and intensity arrays have different lengths
+    LengthMismatch,
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for CodecError {}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn read_varint(buffer: &[u8], offset: &mut usize) -> Result<u64, CodecError> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *buffer.get(*offset).ok_or(CodecError::UnexpectedEnd)?;
+        *offset += 1;
+        if shift >= 64 {
+            return Err(CodecError::Overflow);
+        }
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+#[inline]
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+#[inline]
+fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Write the grid spacing, the number of values and the zig-zag encoded deltas between
+/// consecutive grid positions
+fn encode_grid<I: Iterator<Item = i64>>(step: f64, n: usize, positions: I) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(n * 2 + 10);
+    buffer.extend_from_slice(&step.to_le_bytes());
+    write_varint(&mut buffer, n as u64);
+    let mut last = 0i64;
+    for q in positions {
+        write_varint(&mut buffer, zigzag_encode(q.wrapping_sub(last)));
+        last = q;
+    }
+    buffer
+}
+
+fn decode_grid(buffer: &[u8]) -> Result<(f64, Vec<i64>), CodecError> {
+    let step = buffer
+        .get(..8)
+        .ok_or(CodecError::UnexpectedEnd)?
+        .try_into()
+        .map(f64::from_le_bytes)
+        .unwrap();
+    if !(step.is_finite() && step > 0.0) {
+        return Err(CodecError::InvalidStep);
+    }
+    let mut offset = 8;
+    let n = read_varint(buffer, &mut offset)? as usize;
+    let mut positions = Vec::with_capacity(n.min(buffer.len()));
+    let mut last = 0i64;
+    for _ in 0..n {
+        last = last.wrapping_add(zigzag_decode(read_varint(buffer, &mut offset)?));
+        positions.push(last);
+    }
+    Ok((step, positions))
+}
+
+/// Compress m/z arrays with a guaranteed maximum error in parts-per-million
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MZCodec {
+    pub max_ppm_error: f64,
+}
+
+impl MZCodec {
+    /// # Panics
+    /// If `max_ppm_error` is not positive
+    pub fn new(max_ppm_error: f64) -> Self {
+        assert!(
+            max_ppm_error > 0.0,
+            "The maximum error must be positive, got {max_ppm_error}"
+        );
+        Self { max_ppm_error }
+    }
+
+    /// The spacing of the logarithmic grid. Rounding to the nearest grid point moves
+    /// `ln(mz)` by at most half a step, a relative error of at most `max_ppm_error`.
+    fn step(&self) -> f64 {
+        2.0 * (self.max_ppm_error / 1e6).ln_1p()
+    }
+
+    /// Encode `mzs`, which compress best when sorted. Every value must be positive and finite.
+    pub fn encode(&self, mzs: &[f64]) -> Result<Vec<u8>, CodecError> {
+        let step = self.step();
+        let positions = mzs
+            .iter()
+            .enumerate()
+            .map(|(i, mz)| {
+                if mz.is_finite() && *mz > 0.0 {
+                    Ok((mz.ln() / step).round() as i64)
+                } else {
+                    Err(CodecError::InvalidValue(i))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(encode_grid(step, positions.len(), positions.into_iter()))
+    }
+
+    /// Decode m/z values written by [`MZCodec::encode`]
+    pub fn decode(buffer: &[u8]) -> Result<Vec<f64>, CodecError> {
+        let (step, positions) = decode_grid(buffer)?;
+        Ok(positions
+            .into_iter()
+            .map(|q| (q as f64 * step).exp())
+            .collect())
+    }
+}
+
+/// Compress intensity arrays with a guaranteed maximum relative error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntensityCodec {
+    /// The largest tolerated value of `|decoded - original| / original`
+    pub max_relative_error: f64,
+}
+
+impl IntensityCodec {
+    /// # Panics
+    /// If `max_relative_error` is not in `(0, 1)`
+    pub fn new(max_relative_error: f64) -> Self {
+        assert!(
+            max_relative_error > 0.0 && max_relative_error < 1.0,
+            "The maximum relative error must be in (0, 1), got {max_relative_error}"
+        );
+        Self { max_relative_error }
+    }
+
+    fn step(&self) -> f64 {
+        2.0 * self.max_relative_error.ln_1p()
+    }
+
+    /// Encode `intensities`. Every value must be finite and not negative. Zero is
+    /// stored exactly.
+    pub fn encode(&self, intensities: &[f32]) -> Result<Vec<u8>, CodecError> {
+        let step = self.step();
+        let positions = intensities
+            .iter()
+            .enumerate()
+            .map(|(i, intensity)| {
+                if !intensity.is_finite() || *intensity < 0.0 {
+                    Err(CodecError::InvalidValue(i))
+                } else if *intensity == 0.0 {
+                    // Reserve the lowest grid position for zero
+                    Ok(i64::MIN / 2)
+                } else {
+                    Ok(((*intensity as f64).ln() / step).round() as i64)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(encode_grid(step, positions.len(), positions.into_iter()))
+    }
+
+    /// Decode intensities written by [`IntensityCodec::encode`]
+    pub fn decode(buffer: &[u8]) -> Result<Vec<f32>, CodecError> {
+        let (step, positions) = decode_grid(buffer)?;
+        Ok(positions
+            .into_iter()
+            .map(|q| {
+                if q == i64::MIN / 2 {
+                    0.0
+                } else {
+                    (q as f64 * step).exp() as f32
+                }
+            })
+            .collect())
+    }
+}
+
+/// A peak list compressed with [`MZCodec`] and [`IntensityCodec`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedPeaks {
+    pub mz_buffer: Vec<u8>,
+    pub intensity_buffer: Vec<u8>,
+}
+
+impl CompressedPeaks {
+    pub fn encode<'a, P: CentroidLike + 'a, I: IntoIterator<Item = &'a P>>(
+        peaks: I,
+        mz_codec: MZCodec,
+        intensity_codec: IntensityCodec,
+    ) -> Result<Self, CodecError> {
+        let (mzs, intensities): (Vec<f64>, Vec<f32>) = peaks
+            .into_iter()
+            .map(|p| (p.coordinate(), p.intensity()))
+            .unzip();
+        Ok(Self {
+            mz_buffer: mz_codec.encode(&mzs)?,
+            intensity_buffer: intensity_codec.encode(&intensities)?,
+        })
+    }
+
+    /// Decode the peaks into a [`PeakSet`]
+    pub fn decode(&self) -> Result<PeakSet, CodecError> {
+        let mzs = MZCodec::decode(&self.mz_buffer)?;
+        let intensities = IntensityCodec::decode(&self.intensity_buffer)?;
+        if mzs.len() != intensities.len() {
+            return Err(CodecError::LengthMismatch);
+        }
+        Ok(mzs
+            .into_iter()
+            .zip(intensities)
+            .map(|(mz, intensity)| CentroidPeak::new(mz, intensity, 0))
+            .collect())
+    }
+
+    /// The total size of the encoded buffers in bytes
+    pub fn size_in_bytes(&self) -> usize {
+        self.mz_buffer.len() + self.intensity_buffer.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_data;
+
+    #[test]
+    fn test_varint() {
+        let mut buffer = Vec::new();
+        for v in [0, 1, 127, 128, 300, u64::MAX] {
+            write_varint(&mut buffer, v);
+        }
+        let mut offset = 0;
+        for v in [0, 1, 127, 128, 300, u64::MAX] {
+            assert_eq!(read_varint(&buffer, &mut offset).unwrap(), v);
+        }
+        assert_eq!(
+            read_varint(&buffer, &mut offset),
+            Err(CodecError::UnexpectedEnd)
+        );
+        for v in [0, -1, 1, i64::MIN, i64::MAX] {
+            assert_eq!(zigzag_decode(zigzag_encode(v)), v);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let compressed =
+            CompressedPeaks::encode(peaks.iter(), MZCodec::new(1.0), IntensityCodec::new(0.01))
+                .unwrap();
+        assert!(compressed.size_in_bytes() < peaks.len() * 12 / 2);
+
+        let decoded = compressed.decode().unwrap();
+        assert_eq!(decoded.len(), peaks.len());
+        for (a, b) in peaks.iter().zip(decoded.iter()) {
+            assert!(((a.mz - b.mz) / a.mz * 1e6).abs() <= 1.0);
+            assert!(((a.intensity - b.intensity) / a.intensity).abs() <= 0.0101);
+        }
+
+        assert_eq!(
+            MZCodec::new(5.0).encode(&[100.0, -1.0]),
+            Err(CodecError::InvalidValue(1))
+        );
+        let zeros = IntensityCodec::new(0.01).encode(&[0.0, 5.0]).unwrap();
+        assert_eq!(IntensityCodec::decode(&zeros).unwrap()[0], 0.0);
+        assert_eq!(MZCodec::decode(&[0, 1]), Err(CodecError::UnexpectedEnd));
+        Ok(())
+    }
+}
@@ -0,0 +1,561 @@
+//! Search sorted coordinate columns without building peak structs.
+//!
+//! Columnar formats store a spectrum as parallel arrays, e.g. an m/z column and an intensity
+//! column. The functions here answer the same queries as [`PeakCollection`](crate::PeakCollection)
+//! with the same [`Tolerance`] semantics, but operate on borrowed `&[f64]` coordinate slices
+//! directly, so a column can be searched in place without copying it into peaks.
+//!
+//! Apache Arrow's `Float64Array::values()` and `Float32Array::values()` dereference to such
+//! slices, so a DataFusion UDF can pass an Arrow column's values straight to these functions.
+//! This crate does not depend on Arrow itself; null handling is left to the caller.
+//!
+//! [`PeakColumns`] converts a whole peak list to and from that column layout, ready to be
+//! copied into the arrays of an Arrow `RecordBatch` or a dataframe.
+//!
+//! Values kept outside of a peak list but aligned with its positions, e.g. annotations or
+//! scores, fall out of step when the list is sorted or filtered. A [`ParallelColumn`] holds
+//! such values and follows the [`Permutation`] returned by
+//! [`PeakSetVec::sort_with_permutation`] and [`PeakSetVec::retain_with_permutation`].
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Index, Range};
+
+use crate::coordinate::{IndexType, IndexedCoordinate, Mass, MZ};
+use crate::error::MzPeaksError;
+use crate::mass_error::Tolerance;
+use crate::peak::{CentroidPeak, DeconvolutedPeak, IntensityMeasurement};
+use crate::peak_set::{DeconvolutedPeakSet, PeakSet, PeakSetVec};
+use crate::table::TableRow;
+
+/// The positions in `coordinates`, which must be sorted in ascending order, of every value
+/// within `error_tolerance` of `query`
+pub fn range_for(coordinates: &[f64], query: f64, error_tolerance: Tolerance) -> Range<usize> {
+    let (lower, upper) = error_tolerance.bounds(query);
+    let start = coordinates.partition_point(|x| *x < lower);
+    let end = start + coordinates[start..].partition_point(|x| *x <= upper);
+    start..end
+}
+
+/// The position of the value in `coordinates`, which must be sorted in ascending order,
+/// closest to `query` within `error_tolerance`
+pub fn search(coordinates: &[f64], query: f64, error_tolerance: Tolerance) -> Option<usize> {
+    range_for(coordinates, query, error_tolerance).min_by(|a, b| {
+        (coordinates[*a] - query)
+            .abs()
+            .total_cmp(&(coordinates[*b] - query).abs())
+    })
+}
+
+/// The position of the most intense value in `coordinates`, which must be sorted in
+/// ascending order, within `error_tolerance` of `query`, where `intensities` is the
+/// parallel intensity column
+///
+/// # Panics
+/// If `coordinates` and `intensities` have different lengths
+pub fn most_intense_within(
+    coordinates: &[f64],
+    intensities: &[f32],
+    query: f64,
+    error_tolerance: Tolerance,
+) -> Option<usize> {
+    assert_eq!(
+        coordinates.len(),
+        intensities.len(),
+        "The coordinate and intensity columns must have the same length"
+    );
+    range_for(coordinates, query, error_tolerance)
+        .max_by(|a, b| intensities[*a].total_cmp(&intensities[*b]))
+}
+
+/// The summed intensity of every value in `coordinates`, which must be sorted in
+/// ascending order, within `error_tolerance` of `query`, where `intensities` is the
+/// parallel intensity column
+///
+/// # Panics
+/// If `coordinates` and `intensities` have different lengths
+pub fn intensity_within(
+    coordinates: &[f64],
+    intensities: &[f32],
+    query: f64,
+    error_tolerance: Tolerance,
+) -> f32 {
+    assert_eq!(
+        coordinates.len(),
+        intensities.len(),
+        "The coordinate and intensity columns must have the same length"
+    );
+    intensities[range_for(coordinates, query, error_tolerance)]
+        .iter()
+        .sum()
+}
+
+/// A peak list stored as parallel columns, one entry per peak, with the coordinate column
+/// in coordinate system `C`
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeakColumns<C> {
+    pub coordinate: Vec<f64>,
+    pub intensity: Vec<f32>,
+    pub index: Vec<IndexType>,
+    /// The charge of each peak, or `None` for peaks without a charge
+    pub charge: Option<Vec<i32>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _c: PhantomData<C>,
+}
+
+impl<C> PeakColumns<C> {
+    pub fn new(
+        coordinate: Vec<f64>,
+        intensity: Vec<f32>,
+        index: Vec<IndexType>,
+        charge: Option<Vec<i32>>,
+    ) -> Self {
+        Self {
+            coordinate,
+            intensity,
+            index,
+            charge,
+            _c: PhantomData,
+        }
+    }
+
+    /// The number of peaks, the length of the coordinate column
+    pub fn len(&self) -> usize {
+        self.coordinate.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coordinate.is_empty()
+    }
+
+    /// Check that every column has one entry per peak, naming the first column that does not
+    pub fn validate(&self) -> Result<(), MzPeaksError> {
+        let expected = self.len();
+        let lengths = [
+            ("intensity", self.intensity.len()),
+            ("index", self.index.len()),
+            ("charge", self.charge.as_ref().map_or(expected, |c| c.len())),
+        ];
+        match lengths.into_iter().find(|(_, found)| *found != expected) {
+            Some((column, found)) => Err(MzPeaksError::ColumnLength {
+                column,
+                expected,
+                found,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<P: IndexedCoordinate<C> + IntensityMeasurement + TableRow, C> PeakSetVec<P, C> {
+    /// Copy the peaks into a [`PeakColumns`]. The charge column is present if the peak
+    /// type has a charge.
+    pub fn to_columns(&self) -> PeakColumns<C> {
+        let charge = P::HAS_CHARGE.then(|| {
+            self.iter()
+                .map(|p| p.charge_cell().unwrap_or_default())
+                .collect()
+        });
+        PeakColumns::new(
+            self.iter().map(|p| p.coordinate()).collect(),
+            self.iter().map(|p| p.intensity()).collect(),
+            self.iter().map(|p| p.get_index()).collect(),
+            charge,
+        )
+    }
+}
+
+/// The peaks are sorted and re-indexed, so the index column need not be consistent
+impl TryFrom<PeakColumns<MZ>> for PeakSet {
+    type Error = MzPeaksError;
+
+    fn try_from(value: PeakColumns<MZ>) -> Result<Self, Self::Error> {
+        value.validate()?;
+        let peaks = value
+            .coordinate
+            .iter()
+            .zip(value.intensity.iter())
+            .zip(value.index.iter())
+            .map(|((mz, intensity), index)| CentroidPeak::new(*mz, *intensity, *index))
+            .collect();
+        PeakSet::try_new(peaks)
+    }
+}
+
+/// The peaks are sorted and re-indexed, so the index column need not be consistent. The
+/// charge column is required.
+impl TryFrom<PeakColumns<Mass>> for DeconvolutedPeakSet {
+    type Error = MzPeaksError;
+
+    fn try_from(value: PeakColumns<Mass>) -> Result<Self, Self::Error> {
+        value.validate()?;
+        let Some(charge) = value.charge.as_ref() else {
+            return Err(MzPeaksError::ColumnLength {
+                column: "charge",
+                expected: value.len(),
+                found: 0,
+            });
+        };
+        let peaks = value
+            .coordinate
+            .iter()
+            .zip(value.intensity.iter())
+            .zip(value.index.iter())
+            .zip(charge.iter())
+            .map(|(((mass, intensity), index), z)| {
+                DeconvolutedPeak::new(*mass, *intensity, *z, *index)
+            })
+            .collect();
+        DeconvolutedPeakSet::try_new(peaks)
+    }
+}
+
+/// Check that `values` are in ascending order without NaNs, naming the first position that
+/// is not
+pub(crate) fn check_sorted(values: &[f64]) -> Result<(), MzPeaksError> {
+    if let Some(index) = values.iter().position(|x| x.is_nan()) {
+        return Err(MzPeaksError::NanCoordinate { index });
+    }
+    match values.windows(2).position(|pair| pair[0] > pair[1]) {
+        Some(i) => Err(MzPeaksError::Unsorted { index: Some(i + 1) }),
+        None => Ok(()),
+    }
+}
+
+impl<P: IndexedCoordinate<C> + IntensityMeasurement, C> PeakSetVec<P, C> {
+    /// Copy the coordinates and intensities of the peaks into two contiguous arrays
+    pub fn to_arrays(&self) -> (Vec<f64>, Vec<f32>) {
+        (
+            self.iter().map(|p| p.coordinate()).collect(),
+            self.iter().map(|p| p.intensity()).collect(),
+        )
+    }
+}
+
+impl PeakSet {
+    /// Create a [`PeakSet`] from parallel m/z and intensity arrays, which must already be
+    /// sorted by m/z. Peaks are indexed by their position.
+    pub fn from_arrays(mzs: &[f64], intensities: &[f32]) -> Result<Self, MzPeaksError> {
+        if mzs.len() != intensities.len() {
+            return Err(MzPeaksError::ColumnLength {
+                column: "intensity",
+                expected: mzs.len(),
+                found: intensities.len(),
+            });
+        }
+        if mzs.len() > IndexType::MAX as usize + 1 {
+            return Err(MzPeaksError::IndexOverflow { length: mzs.len() });
+        }
+        check_sorted(mzs)?;
+        Ok(PeakSet::wrap(
+            mzs.iter()
+                .zip(intensities.iter())
+                .enumerate()
+                .map(|(i, (mz, intensity))| CentroidPeak::new(*mz, *intensity, i as IndexType))
+                .collect(),
+        ))
+    }
+}
+
+/// How the positions of a collection changed when it was sorted or filtered: the new
+/// position `i` holds the item previously at `source(i)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permutation {
+    sources: Vec<usize>,
+    source_len: usize,
+}
+
+impl Permutation {
+    /// The positions the items were moved from, in their new order
+    pub fn sources(&self) -> &[usize] {
+        &self.sources
+    }
+
+    pub fn source(&self, i: usize) -> usize {
+        self.sources[i]
+    }
+
+    /// The length of the collection after the change
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// The length of the collection before the change
+    pub fn source_len(&self) -> usize {
+        self.source_len
+    }
+
+    /// Whether every item stayed in place
+    pub fn is_identity(&self) -> bool {
+        self.len() == self.source_len && self.sources.iter().enumerate().all(|(i, j)| i == *j)
+    }
+
+    /// Rearrange `values`, which must have one entry per item of the collection before the
+    /// change, to follow the items
+    pub fn apply<T>(&self, values: Vec<T>) -> Result<Vec<T>, MzPeaksError> {
+        if values.len() != self.source_len {
+            return Err(MzPeaksError::ColumnLength {
+                column: "parallel",
+                expected: self.source_len,
+                found: values.len(),
+            });
+        }
+        let mut slots: Vec<Option<T>> = values.into_iter().map(Some).collect();
+        Ok(self
+            .sources
+            .iter()
+            .map(|j| {
+                slots[*j]
+                    .take()
+                    .expect("A permutation moves each item at most once")
+            })
+            .collect())
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> PeakSetVec<P, C> {
+    /// Sort the collection like [`PeakCollectionMut::sort`](crate::peak_set::PeakCollectionMut::sort),
+    /// returning how the peaks moved
+    pub fn sort_with_permutation(&mut self) -> Permutation {
+        let mut sources: Vec<usize> = (0..self.peaks.len()).collect();
+        sources.sort_by(|a, b| self.peaks[*a].partial_cmp(&self.peaks[*b]).unwrap());
+        let permutation = Permutation {
+            source_len: sources.len(),
+            sources,
+        };
+        self.peaks = permutation
+            .apply(mem::take(&mut self.peaks))
+            .expect("The permutation was built from this collection");
+        for (i, p) in self.peaks.iter_mut().enumerate() {
+            p.set_index(i as IndexType);
+        }
+        permutation
+    }
+
+    /// Keep only the peaks for which `keep` returns `true`, re-indexing the remaining peaks,
+    /// and return how the peaks moved
+    pub fn retain_with_permutation<F: FnMut(&P) -> bool>(&mut self, mut keep: F) -> Permutation {
+        let source_len = self.peaks.len();
+        let sources: Vec<usize> = (0..source_len).filter(|i| keep(&self.peaks[*i])).collect();
+        let permutation = Permutation {
+            sources,
+            source_len,
+        };
+        self.peaks = permutation
+            .apply(mem::take(&mut self.peaks))
+            .expect("The permutation was built from this collection");
+        for (i, p) in self.peaks.iter_mut().enumerate() {
+            p.set_index(i as IndexType);
+        }
+        permutation
+    }
+}
+
+/// Values aligned with the positions of a peak collection, which can follow the peaks as
+/// the collection is sorted or filtered
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ParallelColumn<T> {
+    values: Vec<T>,
+}
+
+impl<T> ParallelColumn<T> {
+    /// Create a column aligned with `peaks`, which must have one peak per value
+    pub fn new<P: IndexedCoordinate<C>, C>(
+        values: Vec<T>,
+        peaks: &PeakSetVec<P, C>,
+    ) -> Result<Self, MzPeaksError> {
+        if values.len() != peaks.len() {
+            return Err(MzPeaksError::ColumnLength {
+                column: "parallel",
+                expected: peaks.len(),
+                found: values.len(),
+            });
+        }
+        Ok(Self { values })
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Whether this column still has one value per peak of `peaks`
+    pub fn is_aligned_with<P: IndexedCoordinate<C>, C>(&self, peaks: &PeakSetVec<P, C>) -> bool {
+        self.values.len() == peaks.len()
+    }
+
+    /// Rearrange the values to follow the peaks moved by `permutation`
+    pub fn apply(&mut self, permutation: &Permutation) -> Result<(), MzPeaksError> {
+        self.values = permutation.apply(mem::take(&mut self.values))?;
+        Ok(())
+    }
+
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.values.get(i)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.values.iter()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.values
+    }
+}
+
+impl<T> Index<usize> for ParallelColumn<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.values[index]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_data, PeakCollection};
+
+    #[test]
+    fn test_columnar_search() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let mzs: Vec<f64> = peaks.iter().map(|p| p.mz).collect();
+        let intensities: Vec<f32> = peaks.iter().map(|p| p.intensity()).collect();
+
+        let tol = Tolerance::PPM(10.0);
+        assert_eq!(search(&mzs, 773.4414, tol), peaks.search(773.4414, tol));
+        let expected = peaks.all_peaks_for(773.4414, Tolerance::Da(2.0));
+        let range = range_for(&mzs, 773.4414, Tolerance::Da(2.0));
+        assert_eq!(range.len(), expected.len());
+        assert_eq!(mzs[range.start], expected[0].mz);
+
+        let i = most_intense_within(&mzs, &intensities, 773.4414, Tolerance::Da(2.0)).unwrap();
+        assert!(expected.iter().all(|p| p.intensity <= intensities[i]));
+        let total: f32 = expected.iter().map(|p| p.intensity).sum();
+        assert_eq!(
+            intensity_within(&mzs, &intensities, 773.4414, Tolerance::Da(2.0)),
+            total
+        );
+        assert_eq!(search(&mzs, 10.0, tol), None);
+        assert!(range_for(&[], 10.0, tol).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_peak_columns() -> Result<(), MzPeaksError> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt").unwrap();
+        let columns = peaks.to_columns();
+        assert_eq!(columns.len(), peaks.len());
+        assert!(columns.charge.is_none());
+        assert_eq!(columns.coordinate[10], peaks[10].mz);
+        let dup = PeakSet::try_from(columns.clone())?;
+        assert_eq!(dup, peaks);
+
+        let mut truncated = columns;
+        truncated.intensity.pop();
+        assert!(matches!(
+            PeakSet::try_from(truncated),
+            Err(MzPeaksError::ColumnLength {
+                column: "intensity",
+                expected: 485,
+                found: 484
+            })
+        ));
+
+        let masses = DeconvolutedPeakSet::new(vec![
+            DeconvolutedPeak::new(1200.0, 10.0, 3, 0),
+            DeconvolutedPeak::new(1000.0, 20.0, 2, 1),
+        ]);
+        let columns = masses.to_columns();
+        assert_eq!(columns.charge, Some(vec![2, 3]));
+        assert_eq!(DeconvolutedPeakSet::try_from(columns.clone())?, masses);
+        let uncharged =
+            PeakColumns::<Mass>::new(columns.coordinate, columns.intensity, columns.index, None);
+        assert!(DeconvolutedPeakSet::try_from(uncharged).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_peak_columns() -> Result<(), MzPeaksError> {
+        let columns = DeconvolutedPeakSet::empty().to_columns();
+        assert_eq!(columns.charge, Some(vec![]));
+        assert!(DeconvolutedPeakSet::try_from(columns)?.is_empty());
+
+        let columns = PeakSet::empty().to_columns();
+        assert!(columns.charge.is_none());
+        assert!(PeakSet::try_from(columns)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_column() -> Result<(), MzPeaksError> {
+        let mut peaks = PeakSet::wrap(vec![
+            CentroidPeak::new(300.0, 1.0, 0),
+            CentroidPeak::new(100.0, 2.0, 1),
+            CentroidPeak::new(200.0, 3.0, 2),
+        ]);
+        let mut labels = ParallelColumn::new(vec!["c", "a", "b"], &peaks)?;
+        assert!(ParallelColumn::new(vec!["a"], &peaks).is_err());
+
+        let permutation = peaks.sort_with_permutation();
+        assert_eq!(permutation.sources(), [1, 2, 0]);
+        assert!(!permutation.is_identity());
+        labels.apply(&permutation)?;
+        assert_eq!(labels.as_slice(), ["a", "b", "c"]);
+        assert!(peaks.is_sorted_and_indexed());
+
+        let permutation = peaks.retain_with_permutation(|p| p.intensity > 1.5);
+        assert_eq!(permutation.len(), 2);
+        assert_eq!(permutation.source_len(), 3);
+        labels.apply(&permutation)?;
+        assert!(labels.is_aligned_with(&peaks));
+        assert_eq!(labels[1], "b");
+        assert_eq!(peaks[1].index, 1);
+
+        // A stale permutation is rejected rather than misaligning the column
+        assert!(matches!(
+            labels.apply(&permutation),
+            Err(MzPeaksError::ColumnLength {
+                expected: 3,
+                found: 2,
+                ..
+            })
+        ));
+        assert!(peaks.sort_with_permutation().is_identity());
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrays() -> Result<(), MzPeaksError> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt").unwrap();
+        let (mzs, intensities) = peaks.to_arrays();
+        assert_eq!(mzs.len(), peaks.len());
+        assert_eq!(intensities[10], peaks[10].intensity);
+        assert_eq!(PeakSet::from_arrays(&mzs, &intensities)?, peaks);
+
+        assert!(matches!(
+            PeakSet::from_arrays(&[100.0, 300.0, 200.0], &[1.0, 1.0, 1.0]),
+            Err(MzPeaksError::Unsorted { index: Some(2) })
+        ));
+        assert!(matches!(
+            PeakSet::from_arrays(&[100.0, f64::NAN], &[1.0, 1.0]),
+            Err(MzPeaksError::NanCoordinate { index: 1 })
+        ));
+        assert!(matches!(
+            PeakSet::from_arrays(&[100.0], &[]),
+            Err(MzPeaksError::ColumnLength { .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+//! Maintain a consensus spectrum over a rolling window of recent scans.
+//!
+//! Real-time displays want a spectrum with the noise of any single scan suppressed, which
+//! means averaging the most recent scans as each new one arrives. Re-matching the whole
+//! window on every scan is too slow, so an [`OnlineConsensus`] keeps the matched peaks
+//! between scans: each new scan is merged into the current consensus in a single pass, and
+//! the contributions of the scan leaving the window are subtracted back out.
+//!
+//! Each consensus peak records its occupancy, the number of scans in the window it was
+//! observed in, so that peaks seen only sporadically can be dismissed as noise.
+use std::collections::VecDeque;
+
+use crate::coordinate::MZLocated;
+use crate::mass_error::Tolerance;
+use crate::peak::{CentroidLike, CentroidPeak};
+use crate::peak_set::PeakSet;
+
+/// A peak of the consensus spectrum
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConsensusPeak {
+    /// The intensity-weighted mean m/z of the peaks matched to this consensus peak
+    pub mz: f64,
+    /// The intensity matched to this peak, averaged over every scan in the window
+    pub intensity: f32,
+    /// The number of scans in the window in which this peak was observed
+    pub occupancy: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Slot {
+    /// The m/z of the peak that created the slot, which peaks are matched against
+    anchor: f64,
+    weighted_mz: f64,
+    intensity: f64,
+    peaks: usize,
+    occupancy: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Contribution {
+    anchor: f64,
+    mz: f64,
+    intensity: f64,
+    /// Whether this was the first peak of its scan matched to the slot, and so counted
+    /// towards its occupancy
+    occupies: bool,
+}
+
+/// A consensus of the last `window` scans, updated incrementally as each scan arrives
+#[derive(Debug, Clone)]
+pub struct OnlineConsensus {
+    window: usize,
+    tolerance: Tolerance,
+    scans: VecDeque<Vec<Contribution>>,
+    /// Ordered by anchor
+    slots: Vec<Slot>,
+}
+
+impl OnlineConsensus {
+    /// Create an empty consensus over the last `window` scans, matching peaks within
+    /// `tolerance`. A `window` of zero is treated as one.
+    pub fn new(window: usize, tolerance: Tolerance) -> Self {
+        Self {
+            window: window.max(1),
+            tolerance,
+            scans: VecDeque::new(),
+            slots: Vec::new(),
+        }
+    }
+
+    /// The maximum number of scans held in the window
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    pub fn tolerance(&self) -> Tolerance {
+        self.tolerance
+    }
+
+    /// The number of scans currently in the window
+    pub fn len(&self) -> usize {
+        self.scans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scans.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.scans.clear();
+        self.slots.clear();
+    }
+
+    /// Add a scan, evicting the oldest scan if the window is full.
+    ///
+    /// The peaks of `scan` must be sorted by m/z. They are matched to the consensus slots
+    /// in a single pass, and a slot is only counted once per scan if the peaks matching it
+    /// are adjacent, so peaks out of order are silently matched to the wrong slots or
+    /// counted twice.
+    pub fn push<P: CentroidLike>(&mut self, scan: &[P]) {
+        if self.scans.len() == self.window {
+            self.evict();
+        }
+        let mut contributions = Vec::with_capacity(scan.len());
+        let mut created: Vec<Slot> = Vec::new();
+        let mut last_slot: Option<usize> = None;
+        let mut j = 0;
+        for peak in scan {
+            let mz = peak.mz();
+            let intensity = peak.intensity() as f64;
+            let (lower, upper) = self.tolerance.bounds(mz);
+            while j < self.slots.len() && self.slots[j].anchor < lower {
+                j += 1;
+            }
+            let mut best: Option<usize> = None;
+            let mut k = j;
+            while k < self.slots.len() && self.slots[k].anchor <= upper {
+                if best.is_none_or(|b| {
+                    (self.slots[k].anchor - mz).abs() < (self.slots[b].anchor - mz).abs()
+                }) {
+                    best = Some(k);
+                }
+                k += 1;
+            }
+            if let Some(k) = best {
+                let occupies = last_slot != Some(k);
+                let slot = &mut self.slots[k];
+                slot.weighted_mz += mz * intensity;
+                slot.intensity += intensity;
+                slot.peaks += 1;
+                if occupies {
+                    slot.occupancy += 1;
+                }
+                last_slot = Some(k);
+                contributions.push(Contribution {
+                    anchor: slot.anchor,
+                    mz,
+                    intensity,
+                    occupies,
+                });
+                continue;
+            }
+            last_slot = None;
+            match created.last_mut() {
+                Some(slot) if self.tolerance.test(mz, slot.anchor) => {
+                    slot.weighted_mz += mz * intensity;
+                    slot.intensity += intensity;
+                    slot.peaks += 1;
+                    contributions.push(Contribution {
+                        anchor: slot.anchor,
+                        mz,
+                        intensity,
+                        occupies: false,
+                    });
+                }
+                _ => {
+                    created.push(Slot {
+                        anchor: mz,
+                        weighted_mz: mz * intensity,
+                        intensity,
+                        peaks: 1,
+                        occupancy: 1,
+                    });
+                    contributions.push(Contribution {
+                        anchor: mz,
+                        mz,
+                        intensity,
+                        occupies: true,
+                    });
+                }
+            }
+        }
+        if !created.is_empty() {
+            // Both lists are sorted by anchor, so one merge pass keeps the slots in order
+            let existing = std::mem::take(&mut self.slots);
+            self.slots = Vec::with_capacity(existing.len() + created.len());
+            let mut existing = existing.into_iter().peekable();
+            let mut created = created.into_iter().peekable();
+            while let (Some(a), Some(b)) = (existing.peek(), created.peek()) {
+                let next = if a.anchor.total_cmp(&b.anchor).is_le() {
+                    existing.next()
+                } else {
+                    created.next()
+                };
+                self.slots.extend(next);
+            }
+            self.slots.extend(existing);
+            self.slots.extend(created);
+        }
+        self.scans.push_back(contributions);
+    }
+
+    fn evict(&mut self) {
+        let Some(contributions) = self.scans.pop_front() else {
+            return;
+        };
+        for c in contributions {
+            let k = self
+                .slots
+                .partition_point(|s| s.anchor.total_cmp(&c.anchor).is_lt());
+            if let Some(slot) = self.slots.get_mut(k) {
+                slot.weighted_mz -= c.mz * c.intensity;
+                slot.intensity -= c.intensity;
+                slot.peaks -= 1;
+                if c.occupies {
+                    slot.occupancy -= 1;
+                }
+            }
+        }
+        self.slots.retain(|s| s.peaks > 0);
+    }
+
+    /// Iterate over the consensus peaks in m/z order
+    pub fn iter(&self) -> impl Iterator<Item = ConsensusPeak> + '_ {
+        let n = self.scans.len().max(1) as f64;
+        self.slots.iter().map(move |slot| ConsensusPeak {
+            mz: if slot.intensity > 0.0 {
+                slot.weighted_mz / slot.intensity
+            } else {
+                slot.anchor
+            },
+            intensity: (slot.intensity / n) as f32,
+            occupancy: slot.occupancy,
+        })
+    }
+
+    /// The consensus spectrum of the peaks observed in at least `min_occupancy` scans of the
+    /// window
+    pub fn consensus(&self, min_occupancy: usize) -> PeakSet {
+        PeakSet::new(
+            self.iter()
+                .filter(|p| p.occupancy >= min_occupancy)
+                .map(|p| CentroidPeak::new(p.mz, p.intensity, 0))
+                .collect(),
+        )
+    }
+}
+
+impl<P: CentroidLike> Extend<P> for OnlineConsensus {
+    /// Add a single scan made of the peaks of `iter`, sorting them by m/z first
+    fn extend<T: IntoIterator<Item = P>>(&mut self, iter: T) {
+        let mut scan: Vec<P> = iter.into_iter().collect();
+        scan.sort_by(|a, b| a.mz().total_cmp(&b.mz()));
+        self.push(&scan);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_online_consensus() {
+        let mut consensus = OnlineConsensus::new(3, Tolerance::PPM(20.0));
+        let scans = [
+            vec![
+                CentroidPeak::new(300.0, 10.0, 0),
+                CentroidPeak::new(500.0, 100.0, 1),
+            ],
+            vec![
+                CentroidPeak::new(400.0, 5.0, 0),
+                CentroidPeak::new(500.004, 100.0, 1),
+            ],
+            vec![CentroidPeak::new(499.998, 200.0, 0)],
+        ];
+        for scan in scans.iter() {
+            consensus.push(scan);
+        }
+        assert_eq!(consensus.len(), 3);
+        let peaks: Vec<_> = consensus.iter().collect();
+        assert_eq!(peaks.len(), 3);
+        assert_eq!(peaks[2].occupancy, 3);
+        assert!((peaks[2].intensity - 400.0 / 3.0).abs() < 1e-3);
+        assert!((peaks[2].mz - 500.0).abs() < 1e-6);
+
+        let stable = consensus.consensus(2);
+        assert_eq!(stable.len(), 1);
+        assert!((stable[0].mz - 500.0).abs() < 1e-6);
+
+        // The first scan leaves the window, taking its 300 m/z peak with it
+        consensus.push(&[CentroidPeak::new(500.0, 100.0, 0)]);
+        assert_eq!(consensus.len(), 3);
+        let peaks: Vec<_> = consensus.iter().collect();
+        assert_eq!(peaks.len(), 2);
+        assert_eq!(peaks[0].mz, 400.0);
+        assert_eq!(peaks[1].occupancy, 3);
+        assert!((peaks[1].mz - 500.0).abs() < 1e-6);
+
+        // Two peaks of one scan matched to the same consensus peak occupy it once
+        consensus.extend([
+            CentroidPeak::new(500.001, 50.0, 0),
+            CentroidPeak::new(500.0, 50.0, 1),
+        ]);
+        assert_eq!(consensus.consensus(3).len(), 1);
+        assert_eq!(consensus.iter().last().unwrap().occupancy, 3);
+
+        // New peaks on either side of the existing ones are merged into m/z order
+        consensus.push(&[
+            CentroidPeak::new(200.0, 1.0, 0),
+            CentroidPeak::new(450.0, 1.0, 1),
+            CentroidPeak::new(600.0, 1.0, 2),
+        ]);
+        let mzs: Vec<_> = consensus.iter().map(|p| p.mz).collect();
+        assert!(mzs.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!((mzs[0], mzs[mzs.len() - 1]), (200.0, 600.0));
+
+        consensus.clear();
+        assert!(consensus.is_empty());
+        assert!(consensus.consensus(0).is_empty());
+    }
+}
@@ -9,6 +9,9 @@ use std::{
     str::FromStr,
 };
 
+use crate::charge::ELECTRON_MASS;
+use crate::queryable::PeakQueryable;
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, PartialOrd)]
 /// The Mass To Charge Ratio (m/z) coordinate system
 pub struct MZ();
@@ -55,7 +58,22 @@ impl IonMobility {
     }
 }
 
-pub trait CoordinateSystem : Sized {
+#[derive(Default, Debug, Clone, Copy, PartialEq, PartialOrd)]
+/// The Collisional Cross Section (CCS) coordinate system, the calibrated counterpart
+/// of [`IonMobility`]
+pub struct CCS();
+impl CCS {
+    /// Access the collisional cross section of the coordinate type
+    #[inline]
+    pub fn coordinate<T: CoordinateLike<CCS>>(inst: &T) -> f64 {
+        CoordinateLike::<CCS>::coordinate(inst)
+    }
+}
+
+pub trait CoordinateSystem: Sized {
+    /// The unit coordinates in this system are measured in, or `None` if it varies, e.g.
+    /// with the instrument
+    const UNIT: Option<&'static str> = None;
 
     #[inline]
     fn coordinate<T: CoordinateLike<Self>>(&self, inst: &T) -> f64 {
@@ -67,10 +85,17 @@ pub trait CoordinateSystem : Sized {
     }
 }
 
-impl CoordinateSystem for MZ {}
-impl CoordinateSystem for Mass {}
+impl CoordinateSystem for MZ {
+    const UNIT: Option<&'static str> = Some("Th");
+}
+impl CoordinateSystem for Mass {
+    const UNIT: Option<&'static str> = Some("Da");
+}
 impl CoordinateSystem for Time {}
 impl CoordinateSystem for IonMobility {}
+impl CoordinateSystem for CCS {
+    const UNIT: Option<&'static str> = Some("Å²");
+}
 
 /// Denote a type has a coordinate value on coordinate system `T`
 pub trait CoordinateLike<T>: PartialOrd {
@@ -155,8 +180,187 @@ impl<T: IndexedCoordinate<C>, C> IndexedCoordinate<C> for &T {
     fn set_index(&mut self, _index: IndexType) {}
 }
 
+/// Keep the indices of a collection of [`IndexedCoordinate`] items in step with their
+/// positions, e.g. after filtering or merging a plain `Vec` of peaks.
+pub trait Reindex<C> {
+    /// Sort the items by coordinate and set each item's index to its new position
+    fn reindex(&mut self);
+
+    /// Check that the items are sorted by coordinate and that each item's index matches
+    /// its position. A NaN coordinate is never considered sorted.
+    fn is_index_consistent(&self) -> bool;
+}
+
+/// NaN coordinates are sorted after all others
+impl<T: IndexedCoordinate<C>, C> Reindex<C> for [T] {
+    fn reindex(&mut self) {
+        self.sort_by(|a, b| {
+            let (a, b) = (a.coordinate(), b.coordinate());
+            match (a.is_nan(), b.is_nan()) {
+                (true, true) => std::cmp::Ordering::Equal,
+                (true, false) => std::cmp::Ordering::Greater,
+                (false, true) => std::cmp::Ordering::Less,
+                (false, false) => a.total_cmp(&b),
+            }
+        });
+        for (i, item) in self.iter_mut().enumerate() {
+            item.set_index(i as IndexType);
+        }
+    }
+
+    fn is_index_consistent(&self) -> bool {
+        self.iter()
+            .enumerate()
+            .all(|(i, item)| item.get_index() as usize == i && !item.coordinate().is_nan())
+            && self
+                .windows(2)
+                .all(|w| w[0].coordinate() <= w[1].coordinate())
+    }
+}
+
+/// The mass of a proton, the default charge carrier, in Daltons
+pub const PROTON: f64 = 1.007276;
+
+/// The ion which carries each unit of charge when converting between neutral mass and m/z
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChargeCarrier {
+    /// A proton, as in `[M+zH]z+`, or its loss as in `[M-zH]z-`
+    #[default]
+    Proton,
+    /// A sodium cation, as in `[M+Na]+`
+    Sodium,
+    /// An ammonium cation, as in `[M+NH4]+`
+    Ammonium,
+    /// No adduct, the charge coming from the loss of electrons as in a radical cation `M+.`,
+    /// or their gain in negative mode
+    Electron,
+    /// Any other carrier of the given mass, in Daltons
+    Custom(f64),
+}
+
+impl ChargeCarrier {
+    /// The mass added to the neutral mass for each unit of positive charge
+    pub fn mass(&self) -> f64 {
+        match self {
+            Self::Proton => PROTON,
+            Self::Sodium => 22.989218,
+            Self::Ammonium => 18.033823,
+            Self::Electron => -ELECTRON_MASS,
+            Self::Custom(mass) => *mass,
+        }
+    }
+}
+
+/// A conversion of a coordinate in system `From` into system `To`
+pub trait CoordinateTransform<From, To> {
+    fn transform(&self, x: f64) -> f64;
+}
+
+/// Convert a neutral mass into the m/z of the ion carrying `charge` charge carriers of
+/// `carrier_mass` each, a [`ChargeCarrier::Proton`] unless set otherwise. A negative charge
+/// removes carriers instead of adding them, as deprotonation does in negative mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MassToMZ {
+    pub charge: i32,
+    pub carrier_mass: f64,
+}
+
+impl MassToMZ {
+    /// Convert for ions charged by protons
+    pub fn new(charge: i32) -> Self {
+        Self {
+            charge,
+            carrier_mass: ChargeCarrier::Proton.mass(),
+        }
+    }
+
+    pub fn with_carrier(self, carrier: ChargeCarrier) -> Self {
+        self.with_carrier_mass(carrier.mass())
+    }
+
+    pub fn with_carrier_mass(mut self, carrier_mass: f64) -> Self {
+        self.carrier_mass = carrier_mass;
+        self
+    }
+
+    /// The transform which undoes this one
+    pub fn inverse(&self) -> MZToMass {
+        MZToMass {
+            charge: self.charge,
+            carrier_mass: self.carrier_mass,
+        }
+    }
+}
+
+impl CoordinateTransform<Mass, MZ> for MassToMZ {
+    #[inline]
+    fn transform(&self, x: f64) -> f64 {
+        let charge = self.charge as f64;
+        (x + self.carrier_mass * charge) / charge.abs()
+    }
+}
+
+/// Convert the m/z of an ion carrying `charge` charge carriers of `carrier_mass` each into
+/// its neutral mass, the inverse of [`MassToMZ`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MZToMass {
+    pub charge: i32,
+    pub carrier_mass: f64,
+}
+
+impl MZToMass {
+    /// Convert for ions charged by protons
+    pub fn new(charge: i32) -> Self {
+        Self {
+            charge,
+            carrier_mass: ChargeCarrier::Proton.mass(),
+        }
+    }
+
+    pub fn with_carrier(self, carrier: ChargeCarrier) -> Self {
+        self.with_carrier_mass(carrier.mass())
+    }
+
+    pub fn with_carrier_mass(mut self, carrier_mass: f64) -> Self {
+        self.carrier_mass = carrier_mass;
+        self
+    }
+
+    /// The transform which undoes this one
+    pub fn inverse(&self) -> MassToMZ {
+        MassToMZ {
+            charge: self.charge,
+            carrier_mass: self.carrier_mass,
+        }
+    }
+}
+
+impl CoordinateTransform<MZ, Mass> for MZToMass {
+    #[inline]
+    fn transform(&self, x: f64) -> f64 {
+        let charge = self.charge as f64;
+        x * charge.abs() - self.carrier_mass * charge
+    }
+}
+
+/// Apply `transform` to the coordinate of every peak of `peaks`, in order
+pub fn transmute_coordinates<From, To, A, T>(peaks: &A, transform: &T) -> Vec<f64>
+where
+    A: PeakQueryable<From> + ?Sized,
+    T: CoordinateTransform<From, To>,
+{
+    (0..peaks.len())
+        .filter_map(|i| peaks.get(i))
+        .map(|p| transform.transform(p.coordinate()))
+        .collect()
+}
+
 /// An interval within a single dimension
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CoordinateRange<C> {
     pub start: Option<f64>,
     pub end: Option<f64>,
@@ -196,6 +400,230 @@ impl<C> CoordinateRange<C> {
         self.end.unwrap_or(f64::INFINITY) >= interval_start
             && interval_end >= self.start.unwrap_or(0.0)
     }
+
+    fn bounds(&self) -> (f64, f64) {
+        (self.start.unwrap_or(0.0), self.end.unwrap_or(f64::INFINITY))
+    }
+
+    /// The distance between the nearest edges of this range and `other`,
+    /// or `0.0` if they overlap.
+    pub fn distance_to(&self, other: &CoordinateRange<C>) -> f64 {
+        let (start, end) = self.bounds();
+        let (other_start, other_end) = other.bounds();
+        (other_start - end).max(start - other_end).max(0.0)
+    }
+
+    /// The interval lying strictly between this range and `other`, or `None`
+    /// if they overlap or touch.
+    pub fn gap_between(&self, other: &CoordinateRange<C>) -> Option<CoordinateRange<C>> {
+        let (start, end) = self.bounds();
+        let (other_start, other_end) = other.bounds();
+        if end < other_start {
+            Some(Self::new(Some(end), Some(other_start)))
+        } else if other_end < start {
+            Some(Self::new(Some(other_end), Some(start)))
+        } else {
+            None
+        }
+    }
+
+    /// The length of the intersection of this range and `other` divided by the
+    /// length of their union, ranging from `0.0` for disjoint ranges to `1.0` for
+    /// identical ones.
+    ///
+    /// When both ranges have no end, the intersection and union are both infinite
+    /// and the ratio is taken to be `1.0`.
+    pub fn jaccard_overlap(&self, other: &CoordinateRange<C>) -> f64 {
+        let (start, end) = self.bounds();
+        let (other_start, other_end) = other.bounds();
+        let intersection = end.min(other_end) - start.max(other_start);
+        if intersection < 0.0 {
+            return 0.0;
+        }
+        let union = end.max(other_end) - start.min(other_start);
+        if intersection.is_infinite() || union == 0.0 {
+            1.0
+        } else {
+            intersection / union
+        }
+    }
+
+    /// The distance from the start to the end of this range, infinite if it has no end
+    pub fn width(&self) -> f64 {
+        let (start, end) = self.bounds();
+        end - start
+    }
+
+    /// Whether this range starts after it ends, so that it contains no coordinates
+    pub fn is_empty(&self) -> bool {
+        let (start, end) = self.bounds();
+        start > end
+    }
+
+    /// The coordinates covered by both this range and `other`, or `None` if they do not
+    /// overlap. A missing bound is unbounded, so it yields to the other range's bound.
+    pub fn intersection(&self, other: &CoordinateRange<C>) -> Option<CoordinateRange<C>> {
+        let start = match (self.start, other.start) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        let end = match (self.end, other.end) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let range = Self::new(start, end);
+        (!range.is_empty()).then_some(range)
+    }
+
+    /// The smallest range covering both this range and `other`, including any gap between
+    /// them. A missing bound is unbounded, so it is kept.
+    pub fn union(&self, other: &CoordinateRange<C>) -> CoordinateRange<C> {
+        let start = match (self.start, other.start) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            _ => None,
+        };
+        let end = match (self.end, other.end) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            _ => None,
+        };
+        Self::new(start, end)
+    }
+
+    /// This range widened by `delta` on both sides, or narrowed if `delta` is negative
+    pub fn expand_by(&self, delta: f64) -> CoordinateRange<C> {
+        Self::new(self.start.map(|x| x - delta), self.end.map(|x| x + delta))
+    }
+
+    /// This range moved by `delta`
+    pub fn shift(&self, delta: f64) -> CoordinateRange<C> {
+        Self::new(self.start.map(|x| x + delta), self.end.map(|x| x + delta))
+    }
+
+    /// Cover this range with sub-ranges of `width` units, each sharing `overlap`
+    /// units with the previous one. The last tile is truncated to the end of this
+    /// range.
+    ///
+    /// If this range has no end, the iterator never terminates.
+    ///
+    /// # Panics
+    /// If `width` is not positive or `overlap` is not smaller than `width`.
+    pub fn tile(&self, width: f64, overlap: f64) -> CoordinateRangeTiles<C> {
+        CoordinateRangeTiles::new(
+            self.start.unwrap_or(0.0),
+            self.end.unwrap_or(f64::INFINITY),
+            width,
+            overlap,
+        )
+    }
+}
+
+/// Something which spans an interval of coordinate system `C`, rather than lying at
+/// a single point in it.
+pub trait Span1D<C> {
+    /// The lowest coordinate covered
+    fn start(&self) -> f64;
+
+    /// The highest coordinate covered
+    fn end(&self) -> f64;
+
+    /// The distance covered, from [`Span1D::start`] to [`Span1D::end`]
+    fn width(&self) -> f64 {
+        self.end() - self.start()
+    }
+
+    fn as_range(&self) -> CoordinateRange<C> {
+        CoordinateRange::new(Some(self.start()), Some(self.end()))
+    }
+
+    /// Check if `x` lies within this span, inclusive of both ends
+    fn contains_coordinate(&self, x: f64) -> bool {
+        self.start() <= x && x <= self.end()
+    }
+
+    /// Check if this span shares any coordinates with `other`
+    fn overlaps_span<S: Span1D<C> + ?Sized>(&self, other: &S) -> bool {
+        self.start() <= other.end() && other.start() <= self.end()
+    }
+
+    /// Check if `other` lies entirely within this span
+    fn contains_span<S: Span1D<C> + ?Sized>(&self, other: &S) -> bool {
+        self.start() <= other.start() && other.end() <= self.end()
+    }
+}
+
+impl<C, T: Span1D<C>> Span1D<C> for &T {
+    fn start(&self) -> f64 {
+        (*self).start()
+    }
+
+    fn end(&self) -> f64 {
+        (*self).end()
+    }
+}
+
+/// A missing `start` is treated as `0.0` and a missing `end` as infinity, as
+/// in [`CoordinateRange::overlaps`]
+impl<C> Span1D<C> for CoordinateRange<C> {
+    fn start(&self) -> f64 {
+        self.bounds().0
+    }
+
+    fn end(&self) -> f64 {
+        self.bounds().1
+    }
+
+    fn as_range(&self) -> CoordinateRange<C> {
+        Self::new(self.start, self.end)
+    }
+}
+
+/// An iterator over the sub-ranges produced by [`CoordinateRange::tile`]
+#[derive(Debug, Clone)]
+pub struct CoordinateRangeTiles<C> {
+    start: f64,
+    end: f64,
+    width: f64,
+    step: f64,
+    i: usize,
+    done: bool,
+    coord: PhantomData<C>,
+}
+
+impl<C> CoordinateRangeTiles<C> {
+    fn new(start: f64, end: f64, width: f64, overlap: f64) -> Self {
+        assert!(width > 0.0, "Tile width must be positive, got {width}");
+        assert!(
+            overlap < width && overlap >= 0.0,
+            "Tile overlap must be in [0, {width}), got {overlap}"
+        );
+        Self {
+            start,
+            end,
+            width,
+            step: width - overlap,
+            i: 0,
+            done: start > end,
+            coord: PhantomData,
+        }
+    }
+}
+
+impl<C> Iterator for CoordinateRangeTiles<C> {
+    type Item = CoordinateRange<C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        // Compute from the origin rather than accumulating to avoid drift
+        let tile_start = self.start + self.step * self.i as f64;
+        let tile_end = (tile_start + self.width).min(self.end);
+        self.i += 1;
+        if tile_end >= self.end {
+            self.done = true;
+        }
+        Some(CoordinateRange::new(Some(tile_start), Some(tile_end)))
+    }
 }
 
 impl<C> Default for CoordinateRange<C> {
@@ -323,6 +751,167 @@ impl<C> From<CoordinateRange<C>> for Range<f64> {
     }
 }
 
+/// The m/z window isolated around a precursor for fragmentation, described as instruments
+/// report it: a target m/z and how far the window extends below and above it
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IsolationWindow {
+    pub target: f64,
+    pub lower_offset: f64,
+    pub upper_offset: f64,
+}
+
+impl IsolationWindow {
+    pub fn new(target: f64, lower_offset: f64, upper_offset: f64) -> Self {
+        Self {
+            target,
+            lower_offset,
+            upper_offset,
+        }
+    }
+
+    /// A window extending `half_width` either side of `target`
+    pub fn symmetric(target: f64, half_width: f64) -> Self {
+        Self::new(target, half_width, half_width)
+    }
+
+    /// Check if the m/z of `peak` lies within the window, inclusive of both ends
+    pub fn contains_peak<T: CoordinateLike<MZ>>(&self, peak: &T) -> bool {
+        self.contains_coordinate(peak.coordinate())
+    }
+
+    /// Check if `range` lies entirely within the window. A missing `start` is treated as `0.0`
+    /// and a missing `end` as infinity, as in [`Span1D`].
+    pub fn spans(&self, range: &CoordinateRange<MZ>) -> bool {
+        self.contains_span(range)
+    }
+}
+
+impl Span1D<MZ> for IsolationWindow {
+    fn start(&self) -> f64 {
+        self.target - self.lower_offset
+    }
+
+    fn end(&self) -> f64 {
+        self.target + self.upper_offset
+    }
+}
+
+impl From<IsolationWindow> for CoordinateRange<MZ> {
+    fn from(value: IsolationWindow) -> Self {
+        value.as_range()
+    }
+}
+
+/// The set of isolation windows cycled through by a multiplexed or data-independent
+/// acquisition, which may overlap
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowScheme {
+    windows: Vec<IsolationWindow>,
+}
+
+impl WindowScheme {
+    pub fn new(windows: Vec<IsolationWindow>) -> Self {
+        Self { windows }
+    }
+
+    /// Cover `range` with windows `width` m/z wide, each sharing `overlap` m/z with the
+    /// previous one, as in [`CoordinateRange::tile`]
+    ///
+    /// # Panics
+    /// If `range` has no end, `width` is not positive or `overlap` is not smaller than
+    /// `width`.
+    pub fn tiled(range: CoordinateRange<MZ>, width: f64, overlap: f64) -> Self {
+        assert!(
+            range.end.is_some(),
+            "A window scheme cannot tile an unbounded range"
+        );
+        Self::new(
+            range
+                .tile(width, overlap)
+                .map(|tile| {
+                    let (start, end) = (tile.start(), tile.end());
+                    let target = (start + end) / 2.0;
+                    IsolationWindow::new(target, target - start, end - target)
+                })
+                .collect(),
+        )
+    }
+
+    pub fn windows(&self) -> &[IsolationWindow] {
+        &self.windows
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// The position of the window `mz` is assigned to, or `None` if no window contains it.
+    /// Where windows overlap, `mz` is assigned to the window whose target is closest,
+    /// preferring the earlier window on a tie, so every m/z belongs to at most one window.
+    pub fn assign(&self, mz: f64) -> Option<usize> {
+        self.windows
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| w.contains_coordinate(mz))
+            .min_by(|(_, a), (_, b)| (a.target - mz).abs().total_cmp(&(b.target - mz).abs()))
+            .map(|(i, _)| i)
+    }
+}
+
+/// The shape of the taper which attenuates intensities near the edges of a range, as in
+/// [`PeakSetVec::taper_edges`](crate::peak_set::PeakSetVec::taper_edges)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TaperWindow {
+    /// Rise in a straight line
+    Linear,
+    /// Rise as `sin²`, the edge of a Hann window
+    #[default]
+    Hann,
+    /// Rise as `sin`, whose squares rather than values add up to 1 across an overlap
+    Cosine,
+}
+
+impl TaperWindow {
+    /// The weight `t` of the way through the taper, rising from 0 at `t = 0` to 1 at
+    /// `t = 1`. For [`TaperWindow::Linear`] and [`TaperWindow::Hann`], the weights of a
+    /// rising and a falling taper over the same interval add up to 1, so ranges which
+    /// overlap by exactly the taper width reconstruct the original intensities when summed.
+    pub fn weight(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        let quarter_turn = t * std::f64::consts::FRAC_PI_2;
+        match self {
+            Self::Linear => t,
+            Self::Hann => quarter_turn.sin().powi(2),
+            Self::Cosine => quarter_turn.sin(),
+        }
+    }
+
+    /// The weight of coordinate `x` within `range`, rising over the first `taper_width`
+    /// of the range and falling over the last, and 0 outside of it. An open end is not
+    /// tapered.
+    pub fn edge_weight<C>(&self, x: f64, range: &CoordinateRange<C>, taper_width: f64) -> f64 {
+        if !range.contains_raw(&x) {
+            return 0.0;
+        }
+        if taper_width <= 0.0 {
+            return 1.0;
+        }
+        let rising = range
+            .start
+            .map_or(1.0, |start| self.weight((x - start) / taper_width));
+        let falling = range
+            .end
+            .map_or(1.0, |end| self.weight((end - x) / taper_width));
+        rising * falling
+    }
+}
 
 #[cfg(test)]
 mod test {
@@ -345,4 +934,278 @@ mod test {
         *Mass().coordinate_mut(&mut peak) = 9001.0;
     }
 
-}
\ No newline at end of file
+    crate::define_coordinate_system!(
+        /// The fraction of a fractionated sample an observation was collected in
+        FractionNumber,
+        FractionLocated::fraction_number,
+        unit = "fraction",
+    );
+
+    #[derive(Debug, PartialEq, PartialOrd)]
+    struct Spot(f64);
+
+    impl CoordinateLike<FractionNumber> for Spot {
+        fn coordinate(&self) -> f64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_define_coordinate_system() {
+        let spot = Spot(4.0);
+        assert_eq!(spot.fraction_number(), 4.0);
+        assert_eq!(FractionNumber::coordinate(&spot), 4.0);
+        assert_eq!(check_coord(&spot, &FractionNumber()), 4.0);
+        assert_eq!(FractionNumber::UNIT, Some("fraction"));
+        assert_eq!(Time::UNIT, None);
+        assert_eq!(Mass::UNIT, Some("Da"));
+    }
+
+    #[test]
+    fn test_coordinate_transform() {
+        let peak = DeconvolutedPeak::new(1000.0, 1.0, 2, 0);
+        let to_mz = MassToMZ::new(2);
+        let mz = to_mz.transform(peak.neutral_mass);
+        assert_eq!(mz, peak.mz());
+        assert!((to_mz.inverse().transform(mz) - 1000.0).abs() < 1e-9);
+
+        let sodiated = MZToMass::new(1).with_carrier(ChargeCarrier::Sodium);
+        assert!((sodiated.transform(1022.989218) - 1000.0).abs() < 1e-9);
+        let negative = MassToMZ::new(-2);
+        assert!((negative.transform(1000.0) - (500.0 - PROTON)).abs() < 1e-9);
+        assert!((negative.inverse().transform(500.0 - PROTON) - 1000.0).abs() < 1e-9);
+
+        let radical = MassToMZ::new(1).with_carrier(ChargeCarrier::Electron);
+        assert!((radical.transform(1000.0) - (1000.0 - ELECTRON_MASS)).abs() < 1e-12);
+        assert_eq!(ChargeCarrier::default(), ChargeCarrier::Proton);
+        assert_eq!(ChargeCarrier::Custom(2.0).mass(), 2.0);
+
+        let peaks =
+            crate::DeconvolutedPeakSet::new(vec![peak, DeconvolutedPeak::new(500.0, 1.0, 1, 1)]);
+        let mzs = transmute_coordinates(&peaks, &MassToMZ::new(1));
+        assert_eq!(mzs, vec![500.0 + PROTON, 1000.0 + PROTON]);
+    }
+
+    #[test]
+    fn test_charge_carrier() {
+        let neutral_mass = 1000.0;
+        let carriers = [
+            (ChargeCarrier::Proton, PROTON),
+            (ChargeCarrier::Sodium, 22.989218),
+            (ChargeCarrier::Ammonium, 18.033823),
+            (ChargeCarrier::Electron, -ELECTRON_MASS),
+            (ChargeCarrier::Custom(38.963158), 38.963158),
+        ];
+        for (carrier, carrier_mass) in carriers {
+            assert_eq!(carrier.mass(), carrier_mass);
+            for charge in [1, 2, 3, -1, -2] {
+                let to_mz = MassToMZ::new(charge).with_carrier(carrier);
+                let to_mass = MZToMass::new(charge).with_carrier(carrier);
+                assert_eq!(to_mz.inverse(), to_mass);
+
+                let expected = (neutral_mass + carrier_mass * charge as f64) / charge.abs() as f64;
+                let mz = to_mz.transform(neutral_mass);
+                assert!(
+                    (mz - expected).abs() < 1e-9,
+                    "{carrier:?} at charge {charge}: {mz} != {expected}"
+                );
+                assert!((to_mass.transform(mz) - neutral_mass).abs() < 1e-9);
+
+                let peak = DeconvolutedPeak::new(neutral_mass, 1.0, charge, 0);
+                assert!((peak.mz_with_carrier(carrier) - expected).abs() < 1e-9);
+            }
+        }
+
+        // A doubly sodiated ion read back as if protonated gives the wrong neutral mass
+        let sodiated_mz = MassToMZ::new(2)
+            .with_carrier(ChargeCarrier::Sodium)
+            .transform(neutral_mass);
+        assert!((sodiated_mz - 522.989218).abs() < 1e-9);
+        let as_protonated = MZToMass::new(2).transform(sodiated_mz);
+        assert!((as_protonated - (neutral_mass + 2.0 * (22.989218 - PROTON))).abs() < 1e-9);
+        let round_trip = MZToMass::new(2)
+            .with_carrier(ChargeCarrier::Sodium)
+            .transform(sodiated_mz);
+        assert!((round_trip - neutral_mass).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_taper_window() {
+        let range = CoordinateRange::<MZ>::new(Some(100.0), Some(200.0));
+        let hann = TaperWindow::Hann;
+        assert_eq!(hann.edge_weight(150.0, &range, 10.0), 1.0);
+        assert_eq!(hann.edge_weight(100.0, &range, 10.0), 0.0);
+        assert!((hann.edge_weight(105.0, &range, 10.0) - 0.5).abs() < 1e-12);
+        assert!((TaperWindow::Linear.edge_weight(197.5, &range, 10.0) - 0.25).abs() < 1e-12);
+        assert_eq!(hann.edge_weight(250.0, &range, 10.0), 0.0);
+        assert_eq!(hann.edge_weight(100.0, &range, 0.0), 1.0);
+        let cosine = TaperWindow::Cosine.weight(0.3);
+        assert!((cosine.powi(2) + TaperWindow::Cosine.weight(0.7).powi(2) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_reindex() {
+        let mut peaks = vec![
+            crate::CentroidPeak::new(300.0, 1.0, 0),
+            crate::CentroidPeak::new(100.0, 1.0, 1),
+            crate::CentroidPeak::new(200.0, 1.0, 5),
+        ];
+        assert!(!peaks.is_index_consistent());
+        peaks.reindex();
+        assert!(peaks.is_index_consistent());
+        assert_eq!(peaks[0].mz, 100.0);
+        assert_eq!(peaks[2].index, 2);
+
+        peaks.remove(1);
+        assert!(!peaks.is_index_consistent());
+        peaks[..].reindex();
+        assert_eq!(peaks[1].index, 1);
+
+        let mut masses = vec![
+            DeconvolutedPeak::new(1000.0, 1.0, 2, 1),
+            DeconvolutedPeak::new(900.0, 1.0, 1, 0),
+        ];
+        assert!(!Reindex::<Mass>::is_index_consistent(masses.as_slice()));
+        Reindex::<Mass>::reindex(masses.as_mut_slice());
+        assert_eq!(masses[0].neutral_mass, 900.0);
+        assert!(Reindex::<Mass>::is_index_consistent(masses.as_slice()));
+
+        // NaNs of either sign go last
+        let mut peaks = [
+            crate::CentroidPeak::new(f64::NAN, 1.0, 0),
+            crate::CentroidPeak::new(200.0, 1.0, 1),
+            crate::CentroidPeak::new(-f64::NAN, 1.0, 2),
+            crate::CentroidPeak::new(100.0, 1.0, 3),
+        ];
+        peaks.reindex();
+        assert_eq!(peaks[0].mz, 100.0);
+        assert_eq!(peaks[1].mz, 200.0);
+        assert!(peaks[2].mz.is_nan() && peaks[3].mz.is_nan());
+        assert_eq!(peaks[3].index, 3);
+        assert!(!peaks.is_index_consistent());
+    }
+
+    #[test]
+    fn test_span() {
+        let a = CoordinateRange::<MZ>::new(Some(100.0), Some(200.0));
+        let b = CoordinateRange::<MZ>::new(Some(150.0), None);
+        assert_eq!(a.width(), 100.0);
+        assert!(a.contains_coordinate(200.0));
+        assert!(a.overlaps_span(&b));
+        assert!(b.contains_span(&CoordinateRange::<MZ>::new(Some(300.0), Some(400.0))));
+        assert!(!a.contains_span(&b));
+        assert_eq!(Span1D::as_range(&b), b);
+    }
+
+    #[test]
+    fn test_isolation_window() {
+        let window = IsolationWindow::new(500.0, 1.0, 2.0);
+        assert_eq!(window.start(), 499.0);
+        assert_eq!(window.end(), 502.0);
+        assert_eq!(window.width(), 3.0);
+        assert!(window.contains_peak(&crate::CentroidPeak::new(501.5, 1.0, 0)));
+        assert!(!window.contains_peak(&crate::CentroidPeak::new(498.5, 1.0, 0)));
+        assert!(window.spans(&CoordinateRange::new(Some(499.5), Some(500.5))));
+        assert!(!window.spans(&CoordinateRange::new(Some(499.5), None)));
+
+        let range: CoordinateRange<MZ> = IsolationWindow::symmetric(500.0, 0.5).into();
+        assert_eq!(range, CoordinateRange::new(Some(499.5), Some(500.5)));
+
+        let range = CoordinateRange::new(Some(400.0), Some(440.0));
+        let scheme = WindowScheme::tiled(range, 20.0, 2.0);
+        assert_eq!(scheme.len(), 3);
+        assert_eq!(scheme.windows()[0], IsolationWindow::symmetric(410.0, 10.0));
+        assert_eq!(scheme.windows()[1].start(), 418.0);
+        assert_eq!(scheme.assign(405.0), Some(0));
+        assert_eq!(scheme.assign(419.5), Some(1));
+        assert_eq!(scheme.assign(450.0), None);
+    }
+
+    #[test]
+    fn test_tile() {
+        let range = CoordinateRange::<MZ>::new(Some(400.0), Some(500.0));
+        let tiles: Vec<_> = range.tile(25.0, 5.0).collect();
+        assert_eq!(tiles.len(), 5);
+        assert_eq!(tiles[0].start, Some(400.0));
+        assert_eq!(tiles[0].end, Some(425.0));
+        assert_eq!(tiles[1].start, Some(420.0));
+        assert_eq!(tiles[4].start, Some(480.0));
+        assert_eq!(tiles[4].end, Some(500.0));
+
+        let tiles: Vec<_> = range.tile(50.0, 0.0).collect();
+        assert_eq!(tiles.len(), 2);
+        assert_eq!(tiles[1].end, Some(500.0));
+
+        let open = CoordinateRange::<MZ>::new(Some(0.0), None);
+        assert_eq!(open.tile(10.0, 0.0).take(3).count(), 3);
+    }
+
+    #[test]
+    fn test_range_metrics() {
+        let a = CoordinateRange::<MZ>::new(Some(100.0), Some(200.0));
+        let b = CoordinateRange::<MZ>::new(Some(150.0), Some(250.0));
+        let c = CoordinateRange::<MZ>::new(Some(300.0), Some(400.0));
+
+        assert_eq!(a.distance_to(&b), 0.0);
+        assert_eq!(a.distance_to(&c), 100.0);
+        assert_eq!(c.distance_to(&a), 100.0);
+
+        assert!(a.gap_between(&b).is_none());
+        let gap = c.gap_between(&a).unwrap();
+        assert_eq!(gap.start, Some(200.0));
+        assert_eq!(gap.end, Some(300.0));
+
+        assert!((a.jaccard_overlap(&b) - 50.0 / 150.0).abs() < 1e-9);
+        assert_eq!(a.jaccard_overlap(&c), 0.0);
+        assert_eq!(a.jaccard_overlap(&a), 1.0);
+
+        let open = CoordinateRange::<MZ>::new(Some(100.0), None);
+        assert_eq!(open.jaccard_overlap(&a), 0.0);
+        assert_eq!(open.distance_to(&c), 0.0);
+    }
+
+    #[test]
+    fn test_range_arithmetic() {
+        let a = CoordinateRange::<MZ>::new(Some(100.0), Some(200.0));
+        let b = CoordinateRange::<MZ>::new(Some(150.0), Some(300.0));
+        let c = CoordinateRange::<MZ>::new(Some(400.0), Some(500.0));
+        let open = CoordinateRange::<MZ>::new(Some(180.0), None);
+
+        assert_eq!(
+            a.intersection(&b),
+            Some(CoordinateRange::new(Some(150.0), Some(200.0)))
+        );
+        assert_eq!(a.intersection(&c), None);
+        assert_eq!(
+            a.intersection(&open),
+            Some(CoordinateRange::new(Some(180.0), Some(200.0)))
+        );
+        assert_eq!(a.union(&c), CoordinateRange::new(Some(100.0), Some(500.0)));
+        assert_eq!(a.union(&open), CoordinateRange::new(Some(100.0), None));
+
+        assert_eq!(a.width(), 100.0);
+        assert!(open.width().is_infinite());
+        assert_eq!(
+            a.expand_by(10.0),
+            CoordinateRange::new(Some(90.0), Some(210.0))
+        );
+        assert!(a.expand_by(-60.0).is_empty());
+        assert!(!a.is_empty());
+        assert_eq!(open.shift(-30.0), CoordinateRange::new(Some(150.0), None));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() -> serde_json::Result<()> {
+        let range = CoordinateRange::<MZ>::new(Some(200.0), None);
+        let text = serde_json::to_string(&range)?;
+        let dup: CoordinateRange<MZ> = serde_json::from_str(&text)?;
+        assert_eq!(dup, range);
+
+        let window = IsolationWindow::new(500.0, 1.0, 2.0);
+        let text = serde_json::to_string(&window)?;
+        let dup: IsolationWindow = serde_json::from_str(&text)?;
+        assert_eq!(dup, window);
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+//! Detect systematic coordinate offsets between two peak lists.
+//!
+//! When the same sample is measured in two runs, a calibration difference between them
+//! shifts every peak of one run by roughly the same amount relative to the other.
+//! [`cross_correlate`] bins both peak lists onto a common grid and scores how well their
+//! intensities line up at each lag, so the lag with the highest score estimates the offset.
+use crate::coordinate::CoordinateLike;
+use crate::peak::IntensityMeasurement;
+use crate::queryable::PeakQueryable;
+
+/// The normalized cross-correlation of two peak lists at a series of lags
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrossCorrelation {
+    /// The lags tested, in coordinate units, in ascending order
+    pub lags: Vec<f64>,
+    /// The correlation at each lag, between 0 and 1 for non-negative intensities
+    pub scores: Vec<f64>,
+}
+
+impl CrossCorrelation {
+    pub fn len(&self) -> usize {
+        self.lags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lags.is_empty()
+    }
+
+    /// Iterate over `(lag, score)` pairs
+    pub fn iter(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.lags.iter().copied().zip(self.scores.iter().copied())
+    }
+
+    /// The position of the highest scoring lag, or `None` if no lag scored above zero
+    fn best_index(&self) -> Option<usize> {
+        self.scores
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| **s > 0.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+    }
+
+    /// The highest scoring lag and its score, or `None` if no lag scored above zero
+    pub fn best(&self) -> Option<(f64, f64)> {
+        self.best_index().map(|i| (self.lags[i], self.scores[i]))
+    }
+
+    /// The best lag refined between grid points by fitting a parabola through the
+    /// highest score and its neighbors
+    pub fn best_lag(&self) -> Option<f64> {
+        let i = self.best_index()?;
+        if i == 0 || i + 1 == self.len() {
+            return Some(self.lags[i]);
+        }
+        let (left, mid, right) = (self.scores[i - 1], self.scores[i], self.scores[i + 1]);
+        let denom = left - 2.0 * mid + right;
+        if denom.abs() < f64::EPSILON {
+            return Some(self.lags[i]);
+        }
+        let step = self.lags[i + 1] - self.lags[i];
+        let offset = (0.5 * (left - right) / denom).clamp(-0.5, 0.5);
+        Some(self.lags[i] + offset * step)
+    }
+}
+
+/// Sum the intensities of `peaks` into bins of width `step` starting at `origin`
+fn bin_intensities<C, T>(peaks: &T, origin: f64, step: f64, n_bins: usize) -> Vec<f64>
+where
+    T: PeakQueryable<C> + ?Sized,
+    T::Peak: IntensityMeasurement,
+{
+    let mut bins = vec![0.0; n_bins];
+    for peak in (0..peaks.len()).filter_map(|k| peaks.get(k)) {
+        let i = ((peak.coordinate() - origin) / step).floor() as usize;
+        if let Some(bin) = bins.get_mut(i) {
+            *bin += peak.intensity() as f64;
+        }
+    }
+    bins
+}
+
+/// Compute the normalized cross-correlation of the binned intensities of `a` and `b` at
+/// every multiple of `step` between `-max_lag` and `max_lag`.
+///
+/// A positive lag means the peaks of `b` sit at higher coordinates than those of `a`, so
+/// [`CrossCorrelation::best_lag`] estimates the offset to subtract from `b` to align it
+/// with `a`. The resolution of the estimate is limited by `step`, which should be on the
+/// order of the peak width.
+///
+/// # Panics
+/// If `step` is not positive or `max_lag` is negative
+pub fn cross_correlate<C, A, B>(a: &A, b: &B, max_lag: f64, step: f64) -> CrossCorrelation
+where
+    A: PeakQueryable<C> + ?Sized,
+    A::Peak: IntensityMeasurement,
+    B: PeakQueryable<C> + ?Sized,
+    B::Peak: IntensityMeasurement,
+{
+    assert!(step > 0.0, "The lag step must be positive, got {step}");
+    assert!(
+        max_lag >= 0.0,
+        "The maximum lag must not be negative, got {max_lag}"
+    );
+    let (Some(a_first), Some(b_first)) = (a.get(0), b.get(0)) else {
+        return CrossCorrelation::default();
+    };
+    let (Some(a_last), Some(b_last)) = (a.get(a.len() - 1), b.get(b.len() - 1)) else {
+        return CrossCorrelation::default();
+    };
+    let start = a_first.coordinate().min(b_first.coordinate());
+    let end = a_last.coordinate().max(b_last.coordinate());
+    let n_bins = ((end - start) / step).floor() as usize + 1;
+    let a_bins = bin_intensities(a, start, step, n_bins);
+    let b_bins = bin_intensities(b, start, step, n_bins);
+
+    let norm = a_bins.iter().map(|x| x * x).sum::<f64>().sqrt()
+        * b_bins.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let max_shift = (max_lag / step).round() as isize;
+
+    let mut result = CrossCorrelation::default();
+    for shift in -max_shift..=max_shift {
+        let total: f64 = a_bins
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| {
+                let j = i as isize + shift;
+                if j < 0 {
+                    return None;
+                }
+                b_bins.get(j as usize).map(|y| x * y)
+            })
+            .sum();
+        result.lags.push(shift as f64 * step);
+        result
+            .scores
+            .push(if norm > 0.0 { total / norm } else { 0.0 });
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_data, CentroidPeak, PeakSet};
+
+    #[test]
+    fn test_cross_correlate() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let shifted: PeakSet = peaks
+            .iter()
+            .map(|p| CentroidPeak::new(p.mz + 0.05, p.intensity, 0))
+            .collect();
+
+        let xcorr = cross_correlate(&peaks, &shifted, 0.2, 0.01);
+        assert_eq!(xcorr.len(), 41);
+        let (lag, score) = xcorr.best().unwrap();
+        assert!((lag - 0.05).abs() < 0.011, "{lag}");
+        assert!(score > 0.9);
+        assert!((xcorr.best_lag().unwrap() - 0.05).abs() < 0.011);
+
+        let xcorr = cross_correlate(&shifted, &peaks, 0.2, 0.01);
+        assert!((xcorr.best().unwrap().0 + 0.05).abs() < 0.011);
+
+        assert!(cross_correlate(&peaks, &PeakSet::empty(), 0.2, 0.01).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+//! Record the difference between two versions of a peak collection.
+//!
+//! A [`PeakSetDelta`] lists only the peaks that were inserted, removed or modified
+//! between two versions of a [`PeakSetVec`], so applications which let a user edit a
+//! peak list can keep a history of edits for undo and redo without storing a full copy
+//! of the collection for each step.
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::coordinate::IndexedCoordinate;
+use crate::peak_set::{PeakCollectionMut, PeakSetVec};
+
+/// The peaks that differ between two versions of a [`PeakSetVec`].
+///
+/// Peaks are identified by their exact coordinate, so a peak whose coordinate was
+/// changed is recorded as a removal and an insertion while a peak whose other
+/// attributes changed is recorded as a modification.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PeakSetDelta<P: IndexedCoordinate<C>, C> {
+    /// Peaks present only in the newer version
+    pub inserted: Vec<P>,
+    /// Peaks present only in the older version
+    pub removed: Vec<P>,
+    /// Pairs of (older, newer) versions of the same peak
+    pub modified: Vec<(P, P)>,
+    _c: PhantomData<C>,
+}
+
+impl<P: IndexedCoordinate<C>, C> Default for PeakSetDelta<P, C> {
+    fn default() -> Self {
+        Self {
+            inserted: Vec::new(),
+            removed: Vec::new(),
+            modified: Vec::new(),
+            _c: PhantomData,
+        }
+    }
+}
+
+impl<P: IndexedCoordinate<C> + Clone, C> PeakSetDelta<P, C> {
+    /// Compute the changes needed to turn `before` into `after`
+    pub fn between(before: &PeakSetVec<P, C>, after: &PeakSetVec<P, C>) -> Self {
+        let mut delta = Self::default();
+        let mut i = 0;
+        let mut j = 0;
+        while i < before.len() && j < after.len() {
+            let a = &before[i];
+            let b = &after[j];
+            let ca = a.coordinate();
+            let cb = b.coordinate();
+            if ca < cb {
+                delta.removed.push(a.clone());
+                i += 1;
+            } else if cb < ca {
+                delta.inserted.push(b.clone());
+                j += 1;
+            } else {
+                if a != b {
+                    delta.modified.push((a.clone(), b.clone()));
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+        delta.removed.extend(before[i..].iter().cloned());
+        delta.inserted.extend(after[j..].iter().cloned());
+        delta
+    }
+
+    /// Whether the two versions were identical
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// The number of peaks touched by this delta
+    pub fn len(&self) -> usize {
+        self.inserted.len() + self.removed.len() + self.modified.len()
+    }
+
+    /// Create the delta which undoes this one
+    pub fn inverse(&self) -> Self {
+        Self {
+            inserted: self.removed.clone(),
+            removed: self.inserted.clone(),
+            modified: self
+                .modified
+                .iter()
+                .map(|(a, b)| (b.clone(), a.clone()))
+                .collect(),
+            _c: PhantomData,
+        }
+    }
+
+    /// Update `peaks` from the older version to the newer version.
+    ///
+    /// Peaks the delta expects to find but which are missing from `peaks` are ignored.
+    pub fn apply(&self, peaks: &mut PeakSetVec<P, C>) {
+        Self::patch(
+            peaks,
+            &self.removed,
+            self.modified.iter().map(|(a, b)| (a, b)),
+            &self.inserted,
+        )
+    }
+
+    /// Update `peaks` from the newer version back to the older version.
+    ///
+    /// Peaks the delta expects to find but which are missing from `peaks` are ignored.
+    pub fn revert(&self, peaks: &mut PeakSetVec<P, C>) {
+        Self::patch(
+            peaks,
+            &self.inserted,
+            self.modified.iter().map(|(a, b)| (b, a)),
+            &self.removed,
+        )
+    }
+
+    fn patch<'a, I: Iterator<Item = (&'a P, &'a P)>>(
+        peaks: &mut PeakSetVec<P, C>,
+        remove: &'a [P],
+        replace: I,
+        insert: &'a [P],
+    ) where
+        P: 'a,
+    {
+        let mut removals: HashMap<u64, Vec<&P>> = HashMap::new();
+        for p in remove {
+            removals
+                .entry(p.coordinate().to_bits())
+                .or_default()
+                .push(p);
+        }
+        let mut replacements: HashMap<u64, Vec<(&P, &P)>> = HashMap::new();
+        for (old, new) in replace {
+            replacements
+                .entry(old.coordinate().to_bits())
+                .or_default()
+                .push((old, new));
+        }
+
+        let mut result = Vec::with_capacity(peaks.len() + insert.len());
+        for peak in peaks.peaks.drain(..) {
+            let key = peak.coordinate().to_bits();
+            if let Some(candidates) = removals.get_mut(&key) {
+                if let Some(k) = candidates.iter().position(|r| *r == &peak) {
+                    candidates.swap_remove(k);
+                    continue;
+                }
+            }
+            if let Some(candidates) = replacements.get_mut(&key) {
+                if let Some(k) = candidates.iter().position(|(old, _)| *old == &peak) {
+                    let (_, new) = candidates.swap_remove(k);
+                    result.push(new.clone());
+                    continue;
+                }
+            }
+            result.push(peak);
+        }
+        result.extend(insert.iter().cloned());
+        peaks.peaks = result;
+        peaks.sort();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CentroidPeak, PeakSet};
+
+    #[test]
+    fn test_delta_round_trip() {
+        let before = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 10.0, 0),
+            CentroidPeak::new(200.0, 20.0, 0),
+            CentroidPeak::new(300.0, 30.0, 0),
+        ]);
+        let after = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 10.0, 0),
+            CentroidPeak::new(200.0, 50.0, 0),
+            CentroidPeak::new(250.0, 5.0, 0),
+        ]);
+
+        let delta = PeakSetDelta::between(&before, &after);
+        assert_eq!(delta.inserted.len(), 1);
+        assert_eq!(delta.removed.len(), 1);
+        assert_eq!(delta.modified.len(), 1);
+        assert_eq!(delta.len(), 3);
+
+        let mut peaks = before.clone();
+        delta.apply(&mut peaks);
+        assert_eq!(peaks, after);
+        assert_eq!(peaks[2].index, 2);
+
+        delta.revert(&mut peaks);
+        assert_eq!(peaks, before);
+
+        delta.inverse().revert(&mut peaks);
+        assert_eq!(peaks, after);
+
+        assert!(PeakSetDelta::between(&before, &before).is_empty());
+    }
+}
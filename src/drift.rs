@@ -0,0 +1,329 @@
+//! Track and correct m/z drift over the course of a run using a lock mass.
+//!
+//! Instruments drift as they warm up and as the laboratory's temperature changes. A lock mass
+//! is a compound of known m/z present throughout the run, so how far its observed m/z strays
+//! from the known value measures the drift at each point in time. [`track_lock_mass`] follows
+//! the lock mass across a sequence of scans and produces a [`DriftCurve`], which maps time to
+//! the ppm error to remove from any peak list acquired at that time.
+//!
+//! Without a lock mass, [`estimate_shift`] measures the overall offset between two peak lists
+//! of the same sample, e.g. from two runs, so one can be calibrated against the other.
+use crate::coordinate::{CoordinateLike, CoordinateLikeMut, IndexedCoordinate, MZ};
+use crate::mass_error::Tolerance;
+use crate::peak::IntensityMeasurement;
+use crate::peak_set::{PeakCollection, PeakSetVec};
+
+/// The lock mass as observed in a single scan
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftPoint {
+    pub time: f64,
+    /// The intensity-weighted m/z of the peaks matching the lock mass
+    pub observed_mz: f64,
+    /// The error of `observed_mz` relative to the lock mass, in parts-per-million
+    pub ppm_error: f64,
+    /// The total intensity of the peaks matching the lock mass
+    pub intensity: f32,
+}
+
+/// A piecewise linear mapping from time to m/z error in parts-per-million
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DriftCurve {
+    points: Vec<DriftPoint>,
+}
+
+impl DriftCurve {
+    /// Create a curve from `points`, sorting them by time
+    pub fn new(mut points: Vec<DriftPoint>) -> Self {
+        points.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { points }
+    }
+
+    pub fn points(&self) -> &[DriftPoint] {
+        &self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The ppm error at `time`, interpolated linearly between the nearest observations
+    /// and held constant beyond the first and last observation. Returns `None` if the
+    /// curve has no observations.
+    pub fn ppm_at(&self, time: f64) -> Option<f64> {
+        let first = self.points.first()?;
+        let last = self.points.last()?;
+        if time <= first.time {
+            return Some(first.ppm_error);
+        }
+        if time >= last.time {
+            return Some(last.ppm_error);
+        }
+        let i = self.points.partition_point(|p| p.time <= time);
+        let (a, b) = (&self.points[i - 1], &self.points[i]);
+        let t = (time - a.time) / (b.time - a.time);
+        Some(a.ppm_error + t * (b.ppm_error - a.ppm_error))
+    }
+
+    /// Remove the drift at `time` from an m/z value observed at that time
+    pub fn correct_mz(&self, mz: f64, time: f64) -> f64 {
+        match self.ppm_at(time) {
+            Some(ppm) => mz / (1.0 + ppm / 1e6),
+            None => mz,
+        }
+    }
+
+    /// Replace each observation's ppm error with the intensity-weighted mean over the
+    /// observations within `half_width` time units of it, damping scan-to-scan noise
+    pub fn smooth(&self, half_width: f64) -> Self {
+        let points = self
+            .points
+            .iter()
+            .map(|p| {
+                let start = self
+                    .points
+                    .partition_point(|q| q.time < p.time - half_width);
+                let end = self
+                    .points
+                    .partition_point(|q| q.time <= p.time + half_width);
+                let (acc, weight) =
+                    self.points[start..end]
+                        .iter()
+                        .fold((0.0, 0.0), |(acc, weight), q| {
+                            (
+                                acc + q.ppm_error * q.intensity as f64,
+                                weight + q.intensity as f64,
+                            )
+                        });
+                DriftPoint {
+                    ppm_error: if weight > 0.0 {
+                        acc / weight
+                    } else {
+                        p.ppm_error
+                    },
+                    ..*p
+                }
+            })
+            .collect();
+        Self { points }
+    }
+
+    /// Remove the drift at `time` from every peak in `peaks`, which were acquired at `time`.
+    ///
+    /// The correction scales every m/z by the same factor, so the peaks stay sorted.
+    pub fn recalibrate<P: IndexedCoordinate<MZ> + CoordinateLikeMut<MZ>>(
+        &self,
+        peaks: &mut PeakSetVec<P, MZ>,
+        time: f64,
+    ) {
+        if let Some(ppm) = self.ppm_at(time) {
+            let factor = 1.0 / (1.0 + ppm / 1e6);
+            for peak in peaks.iter_mut() {
+                *peak.coordinate_mut() *= factor;
+            }
+        }
+    }
+}
+
+/// Follow `lock_mass` through `scans`, pairs of acquisition time and peak list, matching
+/// peaks within `tolerance`. Scans without a matching peak are skipped.
+///
+/// When several peaks match in one scan, their intensity-weighted mean m/z is used.
+pub fn track_lock_mass<'a, P, S, I>(lock_mass: f64, tolerance: Tolerance, scans: I) -> DriftCurve
+where
+    P: IndexedCoordinate<MZ> + IntensityMeasurement + 'a,
+    S: PeakCollection<P, MZ> + 'a,
+    S::Output: IndexedCoordinate<MZ>,
+    I: IntoIterator<Item = (f64, &'a S)>,
+{
+    let points = scans
+        .into_iter()
+        .filter_map(|(time, peaks)| {
+            let matches = peaks.all_peaks_for(lock_mass, tolerance);
+            let (weighted, intensity) = matches
+                .iter()
+                .filter(|p| tolerance.test(p.coordinate(), lock_mass))
+                .fold((0.0, 0.0f32), |(acc, total), p| {
+                    (
+                        acc + p.coordinate() * p.intensity() as f64,
+                        total + p.intensity(),
+                    )
+                });
+            if intensity <= 0.0 {
+                return None;
+            }
+            let observed_mz = weighted / intensity as f64;
+            Some(DriftPoint {
+                time,
+                observed_mz,
+                ppm_error: (observed_mz - lock_mass) / lock_mass * 1e6,
+                intensity,
+            })
+        })
+        .collect();
+    DriftCurve::new(points)
+}
+
+/// The systematic coordinate offset between two peak lists, from [`estimate_shift`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShiftEstimate {
+    /// The median error of the matched peaks of the second list relative to the first,
+    /// in the units of `tolerance`
+    pub shift: f64,
+    /// A distribution-free 95% confidence interval for `shift`, from the order statistics
+    /// of the errors
+    pub confidence_interval: (f64, f64),
+    /// The number of matched peak pairs the estimate was computed from
+    pub matches: usize,
+    /// The tolerance peaks were matched with, which sets the units of the estimate
+    pub tolerance: Tolerance,
+}
+
+impl ShiftEstimate {
+    /// Whether the confidence interval excludes zero, i.e. the shift is unlikely to be noise
+    pub fn is_significant(&self) -> bool {
+        let (lower, upper) = self.confidence_interval;
+        lower > 0.0 || upper < 0.0
+    }
+
+    /// Remove the shift from a coordinate from the second peak list
+    pub fn correct(&self, coordinate: f64) -> f64 {
+        match self.tolerance {
+            Tolerance::PPM(_) | Tolerance::Hybrid(_, _) => coordinate / (1.0 + self.shift / 1e6),
+            Tolerance::Da(_) => coordinate - self.shift,
+            Tolerance::Milli(_) => coordinate - self.shift / 1e3,
+        }
+    }
+
+    /// Remove the shift from every peak in `peaks`, drawn from the same source as the second
+    /// peak list. The correction is monotonic, so the peaks stay sorted.
+    pub fn recalibrate<P: IndexedCoordinate<C> + CoordinateLikeMut<C>, C>(
+        &self,
+        peaks: &mut PeakSetVec<P, C>,
+    ) {
+        for peak in peaks.iter_mut() {
+            let corrected = self.correct(CoordinateLike::<C>::coordinate(peak));
+            *peak.coordinate_mut() = corrected;
+        }
+    }
+}
+
+/// Estimate the systematic offset of peak list `b` relative to `a` from the error of
+/// every peak of `a` to its nearest peak in `b` within `tolerance`, expressed in the units
+/// of `tolerance`. Taking the median makes the estimate robust to a minority of false
+/// matches. Returns `None` if no peaks match.
+///
+/// `tolerance` must be wide enough to include the shift being looked for.
+pub fn estimate_shift<P, Q, C, A, B>(a: &A, b: &B, tolerance: Tolerance) -> Option<ShiftEstimate>
+where
+    P: IndexedCoordinate<C>,
+    Q: IndexedCoordinate<C>,
+    A: PeakCollection<P, C>,
+    A::Output: IndexedCoordinate<C>,
+    B: PeakCollection<Q, C>,
+    B::Output: IndexedCoordinate<C>,
+{
+    let mut errors: Vec<f64> = (0..a.len())
+        .filter_map(|i| {
+            let reference = a.get_item(i).coordinate();
+            b.search(reference, tolerance)
+                .map(|j| tolerance.call(b.get_item(j).coordinate(), reference))
+        })
+        .collect();
+    let n = errors.len();
+    if n == 0 {
+        return None;
+    }
+    errors.sort_by(|x, y| x.total_cmp(y));
+    let shift = if n % 2 == 1 {
+        errors[n / 2]
+    } else {
+        (errors[n / 2 - 1] + errors[n / 2]) / 2.0
+    };
+    let half_width = 1.96 * (n as f64).sqrt() / 2.0;
+    let lower = ((n as f64 / 2.0 - half_width).floor().max(0.0)) as usize;
+    let upper = ((n as f64 / 2.0 + half_width).ceil() as usize).min(n - 1);
+    Some(ShiftEstimate {
+        shift,
+        confidence_interval: (errors[lower], errors[upper]),
+        matches: n,
+        tolerance,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::peak_set::PeakCollectionMut;
+    use crate::{CentroidPeak, PeakSet};
+
+    const LOCK_MASS: f64 = 556.2771;
+
+    fn scan(ppm: f64) -> PeakSet {
+        PeakSet::new(vec![
+            CentroidPeak::new(300.0 * (1.0 + ppm / 1e6), 100.0, 0),
+            CentroidPeak::new(LOCK_MASS * (1.0 + ppm / 1e6), 1e4, 0),
+            CentroidPeak::new(900.0, 100.0, 0),
+        ])
+    }
+
+    #[test]
+    fn test_track_lock_mass() {
+        let scans = [scan(2.0), scan(4.0), scan(8.0), PeakSet::empty()];
+        let times = [1.0, 2.0, 3.0, 4.0];
+        let curve = track_lock_mass(
+            LOCK_MASS,
+            Tolerance::PPM(20.0),
+            times.iter().copied().zip(scans.iter()),
+        );
+        assert_eq!(curve.len(), 3);
+        assert!((curve.ppm_at(1.5).unwrap() - 3.0).abs() < 1e-6);
+        assert!((curve.ppm_at(0.0).unwrap() - 2.0).abs() < 1e-6);
+        assert!((curve.ppm_at(10.0).unwrap() - 8.0).abs() < 1e-6);
+
+        let mut peaks = scan(6.0);
+        curve.recalibrate(&mut peaks, 2.5);
+        assert!((peaks[0].mz - 300.0).abs() < 1e-6);
+        assert!((curve.correct_mz(LOCK_MASS * (1.0 + 6e-6), 2.5) - LOCK_MASS).abs() < 1e-6);
+
+        let smooth = curve.smooth(1.0);
+        assert!((smooth.points()[1].ppm_error - 14.0 / 3.0).abs() < 1e-6);
+        assert!(DriftCurve::default().ppm_at(1.0).is_none());
+    }
+
+    #[test]
+    fn test_estimate_shift() {
+        let reference: PeakSet = (0..50)
+            .map(|i| CentroidPeak::new(200.0 + i as f64 * 17.3, 100.0, 0))
+            .collect();
+        let mut shifted: PeakSet = reference
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let jitter = (i % 5) as f64 - 2.0;
+                CentroidPeak::new(p.mz * (1.0 + (5.0 + jitter * 0.1) / 1e6), p.intensity, 0)
+            })
+            .collect();
+        // A contaminant near a reference peak should not move the median
+        shifted.push(CentroidPeak::new(200.0 * (1.0 + 15.0 / 1e6), 10.0, 0));
+
+        let estimate = estimate_shift(&reference, &shifted, Tolerance::PPM(20.0)).unwrap();
+        assert_eq!(estimate.matches, 50);
+        assert!((estimate.shift - 5.0).abs() < 1e-3);
+        let (lower, upper) = estimate.confidence_interval;
+        assert!(lower <= estimate.shift && estimate.shift <= upper);
+        assert!(estimate.is_significant());
+
+        estimate.recalibrate(&mut shifted);
+        let after = estimate_shift(&reference, &shifted, Tolerance::PPM(20.0)).unwrap();
+        assert!(after.shift.abs() < 1e-3);
+        assert!(!after.is_significant());
+
+        let da = estimate_shift(&reference, &reference, Tolerance::Da(0.1)).unwrap();
+        assert_eq!(da.shift, 0.0);
+        assert!(estimate_shift(&reference, &PeakSet::empty(), Tolerance::Da(0.1)).is_none());
+    }
+}
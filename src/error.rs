@@ -0,0 +1,208 @@
+//! A single error type covering every fallible operation in this crate.
+//!
+//! Each module reports failures with its own precise error type, e.g.
+//! [`ToleranceParsingError`] or [`NoPeakInWindow`]. Code which calls into several modules
+//! can convert all of them into [`MzPeaksError`] with `?` instead of defining its own
+//! wrapper.
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::charge::PolarityError;
+#[cfg(feature = "io")]
+use crate::codec::CodecError;
+use crate::coordinate::{CoordinateRangeParseError, IndexType};
+use crate::mass_error::ToleranceParsingError;
+use crate::peak_set::{NoPeakInWindow, ViewConversionError};
+
+/// Any error produced by this crate
+///
+/// Some variants only exist when the feature providing the operation that produces them
+/// is enabled, so the enum is non-exhaustive.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MzPeaksError {
+    /// A [`Tolerance`](crate::Tolerance) could not be parsed
+    ToleranceParse(ToleranceParsingError),
+    /// A [`CoordinateRange`](crate::CoordinateRange) could not be parsed
+    CoordinateRangeParse(CoordinateRangeParseError),
+    /// Peaks which were required to be sorted by coordinate were not, with the position
+    /// of the first out-of-order peak if it is known
+    Unsorted { index: Option<usize> },
+    /// A peak's coordinate was NaN where it could not be ordered
+    NanCoordinate { index: usize },
+    /// A collection held more peaks than can be numbered by [`IndexType`]
+    IndexOverflow { length: usize },
+    /// A selection mask did not have one entry per peak
+    MaskLength { expected: usize, found: usize },
+    /// A position referred past the end of a collection of `length` peaks
+    IndexOutOfBounds { index: usize, length: usize },
+    /// A column of values parallel to a collection, e.g. in a
+    /// [`PeakColumns`](crate::columnar::PeakColumns), did not have one entry per peak
+    ColumnLength {
+        column: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// A peak's charge disagreed with the polarity of its collection
+    Polarity(PolarityError),
+    /// A search found no peak within its tolerance window
+    NoPeakInWindow(NoPeakInWindow),
+    /// An array could not be compressed or decompressed
+    #[cfg(feature = "io")]
+    Codec(CodecError),
+    /// Reading or writing a stream failed
+    #[cfg(feature = "io")]
+    Io(std::io::Error),
+}
+
+impl Display for MzPeaksError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ToleranceParse(e) => write!(f, "Failed to parse tolerance: {e}"),
+            Self::CoordinateRangeParse(e) => write!(f, "Failed to parse coordinate range: {e}"),
+            Self::Unsorted { index: Some(i) } => {
+                write!(f, "Peaks are not sorted, starting at position {i}")
+            }
+            Self::Unsorted { index: None } => write!(f, "Peaks are not sorted"),
+            Self::NanCoordinate { index } => {
+                write!(f, "The peak at position {index} has a NaN coordinate")
+            }
+            Self::IndexOverflow { length } => write!(
+                f,
+                "{length} peaks cannot be indexed, the limit is {}",
+                IndexType::MAX as usize + 1
+            ),
+            Self::MaskLength { expected, found } => write!(
+                f,
+                "The mask has {found} entries but the collection has {expected} peaks"
+            ),
+            Self::IndexOutOfBounds { index, length } => write!(
+                f,
+                "Position {index} is out of bounds for a collection of {length} peaks"
+            ),
+            Self::ColumnLength {
+                column,
+                expected,
+                found,
+            } => write!(
+                f,
+                "The {column} column has {found} entries but there are {expected} peaks"
+            ),
+            Self::Polarity(e) => e.fmt(f),
+            Self::NoPeakInWindow(e) => e.fmt(f),
+            #[cfg(feature = "io")]
+            Self::Codec(e) => write!(f, "Codec error: {e}"),
+            #[cfg(feature = "io")]
+            Self::Io(e) => write!(f, "IO error: {e}"),
+        }
+    }
+}
+
+impl Error for MzPeaksError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::ToleranceParse(e) => Some(e),
+            Self::CoordinateRangeParse(e) => Some(e),
+            Self::Polarity(e) => Some(e),
+            Self::NoPeakInWindow(e) => Some(e),
+            #[cfg(feature = "io")]
+            Self::Codec(e) => Some(e),
+            #[cfg(feature = "io")]
+            Self::Io(e) => Some(e),
+            Self::Unsorted { .. }
+            | Self::NanCoordinate { .. }
+            | Self::IndexOverflow { .. }
+            | Self::MaskLength { .. }
+            | Self::IndexOutOfBounds { .. }
+            | Self::ColumnLength { .. } => None,
+        }
+    }
+}
+
+impl From<ToleranceParsingError> for MzPeaksError {
+    fn from(value: ToleranceParsingError) -> Self {
+        Self::ToleranceParse(value)
+    }
+}
+
+impl From<CoordinateRangeParseError> for MzPeaksError {
+    fn from(value: CoordinateRangeParseError) -> Self {
+        Self::CoordinateRangeParse(value)
+    }
+}
+
+impl From<ViewConversionError> for MzPeaksError {
+    fn from(value: ViewConversionError) -> Self {
+        match value {
+            ViewConversionError::Unsorted => Self::Unsorted { index: None },
+        }
+    }
+}
+
+impl From<PolarityError> for MzPeaksError {
+    fn from(value: PolarityError) -> Self {
+        Self::Polarity(value)
+    }
+}
+
+impl From<NoPeakInWindow> for MzPeaksError {
+    fn from(value: NoPeakInWindow) -> Self {
+        Self::NoPeakInWindow(value)
+    }
+}
+
+#[cfg(feature = "io")]
+impl From<CodecError> for MzPeaksError {
+    fn from(value: CodecError) -> Self {
+        Self::Codec(value)
+    }
+}
+
+#[cfg(feature = "io")]
+impl From<std::io::Error> for MzPeaksError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CentroidPeak, PeakCollection, PeakSet, Tolerance};
+
+    fn parse_and_search(peaks: &PeakSet, tolerance: &str) -> Result<usize, MzPeaksError> {
+        let tolerance: Tolerance = tolerance.parse()?;
+        Ok(peaks.try_search(500.0, tolerance)?)
+    }
+
+    #[test]
+    fn test_conversions() {
+        let peaks = PeakSet::new(vec![CentroidPeak::new(500.0, 1.0, 0)]);
+        assert_eq!(parse_and_search(&peaks, "10ppm").unwrap(), 0);
+        let err = parse_and_search(&peaks, "10furlongs").unwrap_err();
+        assert!(matches!(err, MzPeaksError::ToleranceParse(_)));
+        assert!(err.source().is_some());
+
+        let err = PeakSet::try_new(vec![
+            CentroidPeak::new(500.0, 1.0, 0),
+            CentroidPeak::new(f64::NAN, 1.0, 1),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, MzPeaksError::NanCoordinate { index: 1 }));
+        assert!(err.to_string().contains("NaN"));
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn test_io_conversions() {
+        let err: MzPeaksError = crate::codec::CodecError::UnexpectedEnd.into();
+        assert!(matches!(err, MzPeaksError::Codec(_)));
+        assert!(err.to_string().starts_with("Codec error"));
+        assert!(err.source().is_some());
+
+        let err: MzPeaksError =
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "truncated").into();
+        assert!(matches!(err, MzPeaksError::Io(_)));
+        assert!(err.to_string().contains("truncated"));
+    }
+}
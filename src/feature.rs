@@ -4,15 +4,23 @@
 //!
 
 use core::slice;
-use std::{cmp::Ordering, marker::PhantomData};
+use std::{
+    cmp::Ordering,
+    marker::PhantomData,
+    ops::{self, Bound, RangeBounds},
+};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    coordinate::{CoordinateLike, IonMobility, Mass, Time, MZ},
+    columnar::check_sorted,
+    coordinate::{
+        ChargeCarrier, CoordinateLike, CoordinateTransform, IonMobility, Mass, MassToMZ, Span1D,
+        Time, MZ,
+    },
     CentroidPeak, CoordinateRange, DeconvolutedPeak, IntensityMeasurement, KnownCharge,
-    MassLocated,
+    MassLocated, MzPeaksError,
 };
 
 #[derive(PartialEq)]
@@ -92,7 +100,7 @@ pub trait TimeInterval<T> {
     }
 }
 
-impl<'a, T, U: TimeInterval<T>> TimeInterval<T> for &'a U {
+impl<T, U: TimeInterval<T>> TimeInterval<T> for &U {
     fn start_time(&self) -> Option<f64> {
         (*self).start_time()
     }
@@ -127,7 +135,7 @@ pub trait FeatureLike<X, Y>: IntensityMeasurement + TimeInterval<Y> + Coordinate
     }
 }
 
-impl<'a, X, Y, T: FeatureLike<X, Y> + TimeInterval<Y>> FeatureLike<X, Y> for &'a T {
+impl<X, Y, T: FeatureLike<X, Y> + TimeInterval<Y>> FeatureLike<X, Y> for &T {
     fn len(&self) -> usize {
         (*self).len()
     }
@@ -306,8 +314,11 @@ impl<X, Y> Feature<X, Y> {
         if !self.is_empty() && y == *self.y.last().unwrap() {
             let last_x = self.x.last().unwrap();
             let last_z = self.z.last().unwrap();
-            let new_x = *last_x * (*last_z as f64) + (x * z as f64) / (z + *last_z) as f64;
-            *self.x.last_mut().unwrap() = new_x;
+            // Points without intensity leave the coordinate where it was
+            if z + *last_z > 0.0 {
+                let new_x = (*last_x * (*last_z as f64) + x * z as f64) / (z + *last_z) as f64;
+                *self.x.last_mut().unwrap() = new_x;
+            }
             *self.z.last_mut().unwrap() += z;
         } else {
             self.x.push(x);
@@ -325,7 +336,7 @@ impl<X, Y> Feature<X, Y> {
         if self.is_empty() {
             return (None, y);
         }
-        match self.y.binary_search_by(|yi| y.total_cmp(yi)) {
+        match self.y.binary_search_by(|yi| yi.total_cmp(&y)) {
             Ok(i) => {
                 let low = i.saturating_sub(1);
                 (low..(low + 3).min(self.len()))
@@ -371,6 +382,138 @@ impl<X, Y> Feature<X, Y> {
     pub fn area(&self) -> f32 {
         self.trapezoid_integrate(&self.y, &self.z)
     }
+
+    /// The `(coordinate, time, intensity)` point where the feature achieves its maximum
+    /// abundance, or `None` if the feature is empty
+    pub fn apex(&self) -> Option<(f64, f64, f32)> {
+        self.idxmax(&self.z)
+            .map(|i| (self.x[i], self.y[i], self.z[i]))
+    }
+
+    /// Borrow the parallel `(time, coordinate, intensity)` arrays of the feature, ordered
+    /// by time, e.g. to hand the trace to plotting or curve fitting code.
+    pub fn as_arrays(&self) -> (&[f64], &[f64], &[f32]) {
+        (&self.y, &self.x, &self.z)
+    }
+
+    /// Create a feature from parallel `(time, coordinate, intensity)` arrays, in the order
+    /// returned by [`Feature::as_arrays`], which must already be sorted by time
+    pub fn from_arrays(
+        times: &[f64],
+        coordinates: &[f64],
+        intensities: &[f32],
+    ) -> Result<Self, MzPeaksError> {
+        let lengths = [
+            ("coordinate", coordinates.len()),
+            ("intensity", intensities.len()),
+        ];
+        for (column, found) in lengths {
+            if found != times.len() {
+                return Err(MzPeaksError::ColumnLength {
+                    column,
+                    expected: times.len(),
+                    found,
+                });
+            }
+        }
+        check_sorted(times)?;
+        Ok(Self::new(
+            coordinates.to_vec(),
+            times.to_vec(),
+            intensities.to_vec(),
+        ))
+    }
+
+    /// Keep only the `(coordinate, time, intensity)` points for which `keep` returns `true`,
+    /// returning the number of points removed
+    pub fn retain_points<F: FnMut(f64, f64, f32) -> bool>(&mut self, mut keep: F) -> usize {
+        let n = self.len();
+        let mask: Vec<bool> = (0..n)
+            .map(|i| keep(self.x[i], self.y[i], self.z[i]))
+            .collect();
+        let mut it = mask.iter();
+        self.x.retain(|_| *it.next().unwrap());
+        let mut it = mask.iter();
+        self.y.retain(|_| *it.next().unwrap());
+        let mut it = mask.iter();
+        self.z.retain(|_| *it.next().unwrap());
+        n - self.len()
+    }
+
+    /// The times before and after the apex at which the intensity first falls to
+    /// `height_fraction` of the apex intensity, interpolating linearly between points,
+    /// along with the apex time
+    fn crossing_times(&self, height_fraction: f32) -> Option<(f64, f64, f64)> {
+        let apex = self.idxmax(&self.z)?;
+        let threshold = self.z[apex] * height_fraction;
+        let interpolate = |inside: usize, outside: usize| {
+            let (z0, z1) = (self.z[inside], self.z[outside]);
+            let frac = ((z0 - threshold) / (z0 - z1)) as f64;
+            self.y[inside] + (self.y[outside] - self.y[inside]) * frac
+        };
+        let leading = (0..apex).rev().find(|i| self.z[*i] <= threshold)?;
+        let trailing = (apex + 1..self.len()).find(|i| self.z[*i] <= threshold)?;
+        Some((
+            interpolate(leading + 1, leading),
+            self.y[apex],
+            interpolate(trailing - 1, trailing),
+        ))
+    }
+
+    /// The asymmetry factor of the elution profile, the time from the apex to the trailing
+    /// edge divided by the time from the leading edge to the apex, where each edge is where the
+    /// intensity falls to `height_fraction` of the apex intensity, conventionally `0.1`.
+    ///
+    /// A symmetric peak has an asymmetry factor of 1, and a tailing peak a factor above 1.
+    /// Returns `None` if the intensity does not fall to `height_fraction` on both sides.
+    pub fn asymmetry_factor(&self, height_fraction: f32) -> Option<f64> {
+        let (leading, apex, trailing) = self.crossing_times(height_fraction)?;
+        Some((trailing - apex) / (apex - leading))
+    }
+
+    /// The USP tailing factor of the elution profile, the full width where the intensity falls
+    /// to `height_fraction` of the apex intensity, conventionally `0.05`, divided by twice the
+    /// width of its leading half.
+    ///
+    /// A symmetric peak has a tailing factor of 1, and a tailing peak a factor above 1.
+    /// Returns `None` if the intensity does not fall to `height_fraction` on both sides.
+    pub fn tailing_factor(&self, height_fraction: f32) -> Option<f64> {
+        let (leading, apex, trailing) = self.crossing_times(height_fraction)?;
+        Some((trailing - leading) / (2.0 * (apex - leading)))
+    }
+
+    /// Sample the feature at each time in `time_grid`, which should be in ascending order,
+    /// linearly interpolating the coordinate and intensity between the nearest points.
+    ///
+    /// Times outside of the feature's span have zero intensity and the coordinate of the
+    /// nearest end of the feature. An empty feature resamples to an empty feature.
+    pub fn resample(&self, time_grid: &[f64]) -> Self {
+        if self.is_empty() {
+            return Self::empty();
+        }
+        let n = self.len();
+        let mut x = Vec::with_capacity(time_grid.len());
+        let mut z = Vec::with_capacity(time_grid.len());
+        for t in time_grid.iter().copied() {
+            let i = self.y.partition_point(|yi| *yi < t);
+            if i == n {
+                x.push(self.x[n - 1]);
+                z.push(0.0);
+            } else if self.y[i] == t {
+                x.push(self.x[i]);
+                z.push(self.z[i]);
+            } else if i == 0 {
+                x.push(self.x[0]);
+                z.push(0.0);
+            } else {
+                let (y0, y1) = (self.y[i - 1], self.y[i]);
+                let frac = (t - y0) / (y1 - y0);
+                x.push(self.x[i - 1] + (self.x[i] - self.x[i - 1]) * frac);
+                z.push(self.z[i - 1] + (self.z[i] - self.z[i - 1]) * frac as f32);
+            }
+        }
+        Self::new(x, time_grid.to_vec(), z)
+    }
 }
 
 impl<X, Y, P: CoordinateLike<X> + IntensityMeasurement> Extend<(P, f64)> for Feature<X, Y> {
@@ -722,6 +865,7 @@ impl<X, Y> IntoIterator for Feature<X, Y> {
 }
 
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
 pub struct ChargedFeature<X, Y> {
     pub feature: Feature<X, Y>,
     pub charge: i32,
@@ -852,12 +996,50 @@ impl<X, Y> ChargedFeature<X, Y> {
     pub fn is_empty(&self) -> bool {
         self.feature.is_empty()
     }
+
+    /// See [`Feature::apex`]
+    pub fn apex(&self) -> Option<(f64, f64, f32)> {
+        self.feature.apex()
+    }
+
+    /// See [`Feature::as_arrays`]
+    pub fn as_arrays(&self) -> (&[f64], &[f64], &[f32]) {
+        self.feature.as_arrays()
+    }
+
+    /// See [`Feature::retain_points`]
+    pub fn retain_points<F: FnMut(f64, f64, f32) -> bool>(&mut self, keep: F) -> usize {
+        self.feature.retain_points(keep)
+    }
+
+    /// See [`Feature::resample`]
+    pub fn resample(&self, time_grid: &[f64]) -> Self {
+        Self::new(self.feature.resample(time_grid), self.charge)
+    }
+
+    /// See [`Feature::asymmetry_factor`]
+    pub fn asymmetry_factor(&self, height_fraction: f32) -> Option<f64> {
+        self.feature.asymmetry_factor(height_fraction)
+    }
+
+    /// See [`Feature::tailing_factor`]
+    pub fn tailing_factor(&self, height_fraction: f32) -> Option<f64> {
+        self.feature.tailing_factor(height_fraction)
+    }
 }
 
 impl<Y> ChargedFeature<Mass, Y> {
     pub fn iter_peaks(&self) -> DeconvolutedPeakIter<'_, Y> {
         DeconvolutedPeakIter::new(self)
     }
+
+    /// The m/z of this feature as an ion charged by `carrier`, where its m/z as a
+    /// [`CoordinateLike<MZ>`] assumes protons
+    pub fn mz_with_carrier(&self, carrier: ChargeCarrier) -> f64 {
+        MassToMZ::new(self.charge)
+            .with_carrier(carrier)
+            .transform(self.neutral_mass())
+    }
 }
 
 impl<X, Y> PartialEq for ChargedFeature<X, Y> {
@@ -878,9 +1060,7 @@ impl<X, Y> PartialOrd for ChargedFeature<X, Y> {
 
 impl<Y> CoordinateLike<MZ> for ChargedFeature<Mass, Y> {
     fn coordinate(&self) -> f64 {
-        let charge_carrier: f64 = 1.007276;
-        let charge = self.charge as f64;
-        (self.neutral_mass() + charge_carrier * charge) / charge
+        self.mz_with_carrier(ChargeCarrier::Proton)
     }
 }
 
@@ -902,6 +1082,18 @@ impl<X, Y> AsMut<Feature<X, Y>> for ChargedFeature<X, Y> {
     }
 }
 
+impl<X, Y> From<(Feature<X, Y>, i32)> for ChargedFeature<X, Y> {
+    fn from((feature, charge): (Feature<X, Y>, i32)) -> Self {
+        Self::new(feature, charge)
+    }
+}
+
+impl<X, Y> From<ChargedFeature<X, Y>> for (Feature<X, Y>, i32) {
+    fn from(value: ChargedFeature<X, Y>) -> Self {
+        (value.feature, value.charge)
+    }
+}
+
 impl<X, Y, P: CoordinateLike<X> + IntensityMeasurement + KnownCharge> FromIterator<(P, f64)>
     for ChargedFeature<X, Y>
 {
@@ -970,6 +1162,7 @@ impl<'a, Y> DoubleEndedIterator for DeconvolutedPeakIter<'a, Y> {
 }
 
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SimpleFeature<X, Y> {
     pub label: f64,
     y: Vec<f64>,
@@ -1030,7 +1223,7 @@ impl<X, Y> SimpleFeature<X, Y> {
         if self.is_empty() {
             return (None, y);
         }
-        match self.y.binary_search_by(|yi| y.total_cmp(yi)) {
+        match self.y.binary_search_by(|yi| yi.total_cmp(&y)) {
             Ok(i) => {
                 let low = i.saturating_sub(1);
                 (low..(low + 3).min(self.len()))
@@ -1212,18 +1405,14 @@ impl<'a, X, Y> FeatureView<'a, X, Y> {
     }
 
     pub fn to_owned(&self) -> Feature<X, Y> {
-        Feature::new(
-            self.x.to_owned(),
-            self.y.to_owned(),
-            self.z.to_owned()
-        )
+        Feature::new(self.x.to_owned(), self.y.to_owned(), self.z.to_owned())
     }
 
     fn find_y(&self, y: f64) -> (Option<usize>, f64) {
         if self.is_empty() {
             return (None, y);
         }
-        match self.y.binary_search_by(|yi| y.total_cmp(yi)) {
+        match self.y.binary_search_by(|yi| yi.total_cmp(&y)) {
             Ok(i) => {
                 let low = i.saturating_sub(1);
                 (low..(low + 3).min(self.len()))
@@ -1426,20 +1615,60 @@ impl<'a, X, Y> ChargedFeatureView<'a, X, Y> {
     }
 
     pub fn to_owned(&self) -> ChargedFeature<X, Y> {
-        ChargedFeature::new(
-            self.feature.to_owned(), self.charge
-        )
+        ChargedFeature::new(self.feature.to_owned(), self.charge)
     }
 }
 
-
 /// A trait to split features at a given time point
 pub trait SplittableFeatureLike<'a, X, Y>: FeatureLike<X, Y> {
     type ViewType: FeatureLike<X, Y>;
 
+    /// Split the feature at the point nearest to `point`, which begins the second part
     fn split_at(&'a self, point: f64) -> (Self::ViewType, Self::ViewType);
 }
 
+/// A trait to borrow the points of a feature within a span of time, without copying them.
+///
+/// This is separate from [`SplittableFeatureLike`] so that its implementors need not provide it.
+pub trait SliceableFeatureLike<'a, X, Y>: SplittableFeatureLike<'a, X, Y> {
+    /// Borrow the points whose times lie within `range`
+    fn slice<R: RangeBounds<f64>>(&'a self, range: R) -> Self::ViewType;
+
+    /// Split the feature into the points before `time` and the points at or after it.
+    ///
+    /// Unlike [`SplittableFeatureLike::split_at`], this splits at `time` exactly rather than
+    /// at the nearest point, so segmenting a trace at a valley between two points keeps each
+    /// point on its own side.
+    fn split_at_time(&'a self, time: f64) -> (Self::ViewType, Self::ViewType) {
+        (self.slice(..time), self.slice(time..))
+    }
+
+    /// Borrow the points at or after `time`
+    fn truncate_before(&'a self, time: f64) -> Self::ViewType {
+        self.slice(time..)
+    }
+
+    /// Borrow the points at or before `time`
+    fn truncate_after(&'a self, time: f64) -> Self::ViewType {
+        self.slice(..=time)
+    }
+}
+
+/// The positions of the points of sorted `times` which lie within `range`
+fn time_index_range<R: RangeBounds<f64>>(times: &[f64], range: R) -> ops::Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(t) => times.partition_point(|y| y < t),
+        Bound::Excluded(t) => times.partition_point(|y| y <= t),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(t) => times.partition_point(|y| y <= t),
+        Bound::Excluded(t) => times.partition_point(|y| y < t),
+        Bound::Unbounded => times.len(),
+    };
+    start..end.max(start)
+}
+
 const EMPTY_X: &[f64] = &[];
 const EMPTY_Y: &[f64] = &[];
 const EMPTY_Z: &[f32] = &[];
@@ -1500,13 +1729,78 @@ impl<'a, X, Y> SplittableFeatureLike<'a, X, Y> for ChargedFeatureView<'a, X, Y>
     }
 }
 
+impl<'a, X, Y> SliceableFeatureLike<'a, X, Y> for Feature<X, Y> {
+    fn slice<R: RangeBounds<f64>>(&'a self, range: R) -> Self::ViewType {
+        let i = time_index_range(&self.y, range);
+        Self::ViewType::new(&self.x[i.clone()], &self.y[i.clone()], &self.z[i])
+    }
+}
+
+impl<'a, X, Y> SliceableFeatureLike<'a, X, Y> for FeatureView<'a, X, Y> {
+    fn slice<R: RangeBounds<f64>>(&'a self, range: R) -> Self::ViewType {
+        let i = time_index_range(self.y, range);
+        Self::ViewType::new(&self.x[i.clone()], &self.y[i.clone()], &self.z[i])
+    }
+}
+
+impl<'a, X, Y> SliceableFeatureLike<'a, X, Y> for ChargedFeature<X, Y> {
+    fn slice<R: RangeBounds<f64>>(&'a self, range: R) -> Self::ViewType {
+        Self::ViewType::new(self.feature.slice(range), self.charge)
+    }
+}
 
+impl<'a, X, Y> SliceableFeatureLike<'a, X, Y> for ChargedFeatureView<'a, X, Y> {
+    fn slice<R: RangeBounds<f64>>(&'a self, range: R) -> Self::ViewType {
+        Self::ViewType::new(self.feature.slice(range), self.charge)
+    }
+}
+
+/// Features span the interval between their first and last time points. An empty
+/// feature spans `NaN`, overlapping nothing.
+macro_rules! impl_span_for_feature {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl<'a, X, Y> Span1D<Y> for $t where $t: TimeInterval<Y> {
+                fn start(&self) -> f64 {
+                    self.start_time().unwrap_or(f64::NAN)
+                }
+
+                fn end(&self) -> f64 {
+                    self.end_time().unwrap_or(f64::NAN)
+                }
+            }
+        )*
+    };
+}
+
+impl_span_for_feature!(
+    Feature<X, Y>,
+    ChargedFeature<X, Y>,
+    SimpleFeature<X, Y>,
+    FeatureView<'a, X, Y>,
+    ChargedFeatureView<'a, X, Y>,
+);
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::{CentroidPeak, DeconvolutedPeak, MZLocated};
 
+    #[test]
+    fn test_span() {
+        let a: LCMSFeature = [(500.0, 10.0, 1.0), (500.0, 12.0, 4.0)]
+            .into_iter()
+            .collect();
+        let b: LCMSFeature = [(600.0, 11.5, 1.0), (600.0, 14.0, 4.0)]
+            .into_iter()
+            .collect();
+        assert_eq!(a.width(), 2.0);
+        assert!(a.overlaps_span(&b));
+        assert!(!a.contains_span(&b));
+        assert_eq!(Span1D::as_range(&a).end, TimeInterval::as_range(&a).end);
+        assert!(!LCMSFeature::empty().overlaps_span(&a));
+    }
+
     #[test]
     fn test_build_raw() {
         let mut x = LCMSFeature::empty();
@@ -1526,7 +1820,7 @@ mod test {
         let mz = 204.07545212;
         assert!((x.mz() - mz).abs() < 1e-6);
 
-        let area = 1159.879980;
+        let area = 1159.88;
         assert!((x.area() - area).abs() < 1e-6);
 
         assert_eq!(x.iter().len(), 3);
@@ -1550,7 +1844,127 @@ mod test {
         let (b, a) = x.split_at(0.2);
         assert_eq!(b.len(), 1);
         assert_eq!(a.len(), 2);
+    }
 
+    #[test]
+    fn test_push_raw_without_intensity() {
+        // Two points at the same time without intensity keep the first coordinate
+        let mut x = LCMSFeature::empty();
+        x.push_raw(204.08, 0.1, 0.0);
+        x.push_raw(204.07, 0.1, 0.0);
+        assert_eq!(x.len(), 1);
+        assert_eq!(x.as_arrays().1, &[204.08]);
+        assert_eq!(x.intensity(), 0.0);
+
+        // Once intensity arrives, the coordinate follows it
+        x.push_raw(204.06, 0.1, 10.0);
+        assert_eq!(x.len(), 1);
+        assert!((x.as_arrays().1[0] - 204.06).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_slice() {
+        let x: LCMSFeature = [
+            (204.08, 0.1, 3432.1),
+            (204.07, 0.2, 7251.9),
+            (204.08, 0.3, 5261.7),
+            (204.07, 0.4, 1021.3),
+        ]
+        .into_iter()
+        .collect();
+
+        // Unlike split_at, the split falls exactly at the requested time
+        let (b, a) = x.split_at_time(0.25);
+        assert_eq!(b.len(), 2);
+        assert_eq!(a.len(), 2);
+        assert_eq!(a.start_time(), Some(0.3));
+
+        let view = x.slice(0.2..0.4);
+        assert_eq!(view.len(), 2);
+        assert_eq!(view.start_time(), Some(0.2));
+        assert_eq!(view.end_time(), Some(0.3));
+        assert_eq!(view.slice(0.3..).len(), 1);
+        assert_eq!(x.truncate_before(0.2).len(), 3);
+        assert_eq!(x.truncate_after(0.2).len(), 2);
+        assert!(x.slice(0.5..).is_empty());
+
+        let charged = ChargedFeature::new(x, 2);
+        let (b, a) = charged.split_at_time(0.3);
+        assert_eq!((b.len(), a.len()), (2, 2));
+        assert_eq!(a.charge, 2);
+        assert_eq!(charged.truncate_after(0.1).len(), 1);
+    }
+
+    #[test]
+    fn test_arrays_and_resample() {
+        let x: LCMSFeature = [(500.0, 1.0, 10.0), (500.2, 2.0, 30.0), (500.1, 4.0, 10.0)]
+            .into_iter()
+            .collect();
+        let (times, mzs, intensities) = x.as_arrays();
+        assert_eq!(times, [1.0, 2.0, 4.0]);
+        assert_eq!(mzs, [500.0, 500.2, 500.1]);
+        assert_eq!(intensities, [10.0, 30.0, 10.0]);
+
+        let (times, mzs, intensities) = x.as_arrays();
+        assert_eq!(
+            LCMSFeature::from_arrays(times, mzs, intensities).unwrap(),
+            x
+        );
+        assert!(matches!(
+            LCMSFeature::from_arrays(&[2.0, 1.0], &[500.0, 500.0], &[1.0, 1.0]),
+            Err(MzPeaksError::Unsorted { index: Some(1) })
+        ));
+        assert!(LCMSFeature::from_arrays(&[1.0], &[], &[1.0]).is_err());
+
+        let resampled = x.resample(&[0.0, 1.5, 2.0, 3.0, 5.0]);
+        let (times, mzs, intensities) = resampled.as_arrays();
+        assert_eq!(times, [0.0, 1.5, 2.0, 3.0, 5.0]);
+        assert_eq!(intensities, [0.0, 20.0, 30.0, 20.0, 0.0]);
+        assert_eq!(mzs[0], 500.0);
+        assert!((mzs[1] - 500.1).abs() < 1e-9);
+        assert!((mzs[3] - 500.15).abs() < 1e-9);
+        assert_eq!(mzs[4], 500.1);
+        assert!(LCMSFeature::empty().resample(&[1.0]).is_empty());
+
+        let charged = DeconvolvedLCMSFeature::new(
+            [(1000.0, 1.0, 10.0), (1000.0, 2.0, 30.0)]
+                .into_iter()
+                .collect(),
+            2,
+        );
+        let resampled = charged.resample(&[1.5]);
+        assert_eq!(resampled.charge, 2);
+        assert_eq!(resampled.as_arrays().2, [20.0]);
+    }
+
+    #[test]
+    fn test_peak_symmetry() {
+        let symmetric: LCMSFeature = [0.0, 50.0, 100.0, 50.0, 0.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, z)| (500.0, i as f64, z))
+            .collect();
+        assert_eq!(symmetric.asymmetry_factor(0.1), Some(1.0));
+        assert_eq!(symmetric.tailing_factor(0.05), Some(1.0));
+
+        // Falls to 10% of the apex 0.9 units before it and 2.5 units after it
+        let tailing: LCMSFeature = [0.0, 100.0, 60.0, 20.0, 0.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, z)| (500.0, i as f64, z))
+            .collect();
+        let asymmetry = tailing.asymmetry_factor(0.1).unwrap();
+        assert!((asymmetry - 2.5 / 0.9).abs() < 1e-6);
+        let tailing_factor = tailing.tailing_factor(0.1).unwrap();
+        assert!((tailing_factor - 3.4 / 1.8).abs() < 1e-6);
+
+        let truncated: LCMSFeature = [(500.0, 1.0, 100.0), (500.0, 2.0, 10.0)]
+            .into_iter()
+            .collect();
+        assert_eq!(truncated.asymmetry_factor(0.1), None);
+        assert_eq!(LCMSFeature::empty().tailing_factor(0.05), None);
+        let charged = ChargedFeature::new(symmetric, 2);
+        assert_eq!(charged.asymmetry_factor(0.5), Some(1.0));
     }
 
     #[test]
@@ -1573,7 +1987,7 @@ mod test {
         let mass = 203.07545212;
         assert!((x.neutral_mass() - mass).abs() < 1e-6);
 
-        let area = 1159.879980;
+        let area = 1159.88;
         assert!((x.area() - area).abs() < 1e-6);
 
         assert_eq!(x.iter().len(), 3);
@@ -1586,4 +2000,46 @@ mod test {
 
         assert_eq!(x, points.into_iter().collect());
     }
+
+    #[test]
+    fn test_apex() {
+        let x: LCMSFeature = [
+            (500.0, 1.0, 10.0),
+            (500.2, 2.0, 30.0),
+            (500.1, 3.0, 30.0),
+            (500.0, 4.0, 5.0),
+        ]
+        .into_iter()
+        .collect();
+        // Ties go to the earliest point
+        assert_eq!(x.apex(), Some((500.2, 2.0, 30.0)));
+        assert_eq!(x.apex().map(|(_, time, _)| time), x.apex_y());
+        assert_eq!(LCMSFeature::empty().apex(), None);
+
+        let charged = DeconvolvedLCMSFeature::new(
+            [(1000.0, 1.0, 10.0), (1000.1, 2.0, 5.0)]
+                .into_iter()
+                .collect(),
+            2,
+        );
+        assert_eq!(charged.apex(), Some((1000.0, 1.0, 10.0)));
+        assert_eq!(DeconvolvedLCMSFeature::empty(2).apex(), None);
+    }
+
+    #[test]
+    fn test_charged_feature_conversion() {
+        let feature: Feature<Mass, Time> = [(1000.0, 1.0, 10.0), (1000.0, 2.0, 30.0)]
+            .into_iter()
+            .collect();
+
+        let charged: DeconvolvedLCMSFeature = (feature.clone(), -3).into();
+        assert_eq!(charged.charge(), -3);
+        assert_eq!(charged.len(), 2);
+        assert_eq!(charged.neutral_mass(), 1000.0);
+        assert_eq!(charged.as_ref(), &feature);
+
+        let (back, charge): (Feature<Mass, Time>, i32) = charged.into();
+        assert_eq!(charge, -3);
+        assert_eq!(back, feature);
+    }
 }
@@ -0,0 +1,175 @@
+//! Deterministic keys for identifying features across runs.
+//!
+//! A feature's coordinate and apex time vary slightly from run to run and are floating
+//! point, so they cannot be hashed or compared for equality directly. A [`FeatureKey`]
+//! quantizes them to a [`KeyPrecision`], one bin width per dimension, and pairs them with
+//! the feature's charge, giving a value that can key a [`HashMap`](std::collections::HashMap)
+//! or cache. Distinct features that land in the same bins are reported by
+//! [`find_key_collisions`] so the precision can be tuned.
+use std::collections::HashMap;
+use std::fmt::Display;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::feature::FeatureLike;
+use crate::KnownCharge;
+
+/// The bin width of each dimension of a [`FeatureKey`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyPrecision {
+    /// The bin width along the feature's coordinate, e.g. m/z
+    pub coordinate: f64,
+    /// The bin width along the feature's time dimension
+    pub time: f64,
+}
+
+impl Default for KeyPrecision {
+    fn default() -> Self {
+        Self::new(0.001, 0.01)
+    }
+}
+
+impl KeyPrecision {
+    pub fn new(coordinate: f64, time: f64) -> Self {
+        Self { coordinate, time }
+    }
+
+    fn quantize(value: f64, width: f64) -> i64 {
+        (value / width).round() as i64
+    }
+}
+
+/// A quantized `(coordinate, apex time, charge)` triple identifying a feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FeatureKey {
+    /// The coordinate bin, in units of [`KeyPrecision::coordinate`]
+    pub coordinate: i64,
+    /// The apex time bin, in units of [`KeyPrecision::time`]
+    pub time: i64,
+    pub charge: Option<i32>,
+}
+
+impl FeatureKey {
+    /// Quantize `coordinate` and `time` to `precision`
+    pub fn new(coordinate: f64, time: f64, charge: Option<i32>, precision: KeyPrecision) -> Self {
+        Self {
+            coordinate: KeyPrecision::quantize(coordinate, precision.coordinate),
+            time: KeyPrecision::quantize(time, precision.time),
+            charge,
+        }
+    }
+
+    /// The key of `feature` located at its apex time, or `None` if it is empty
+    pub fn for_feature<X, Y, F: FeatureLike<X, Y>>(
+        feature: &F,
+        charge: Option<i32>,
+        precision: KeyPrecision,
+    ) -> Option<Self> {
+        feature
+            .apex_time()
+            .map(|time| Self::new(feature.coordinate(), time, charge, precision))
+    }
+
+    /// The key of a feature with a known charge, or `None` if it is empty
+    pub fn for_charged_feature<X, Y, F: FeatureLike<X, Y> + KnownCharge>(
+        feature: &F,
+        precision: KeyPrecision,
+    ) -> Option<Self> {
+        Self::for_feature(feature, Some(feature.charge()), precision)
+    }
+
+    /// The center of the coordinate bin
+    pub fn coordinate(&self, precision: KeyPrecision) -> f64 {
+        self.coordinate as f64 * precision.coordinate
+    }
+
+    /// The center of the apex time bin
+    pub fn time(&self, precision: KeyPrecision) -> f64 {
+        self.time as f64 * precision.time
+    }
+}
+
+impl Display for FeatureKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.coordinate, self.time)?;
+        if let Some(z) = self.charge {
+            write!(f, "^{z}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`FeatureKey`] shared by more than one feature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCollision {
+    pub key: FeatureKey,
+    /// The positions of the features sharing `key`, in ascending order
+    pub indices: Vec<usize>,
+}
+
+/// Find every key shared by more than one of `features`, where `key_fn` produces each
+/// feature's key, or `None` to leave it out. Collisions are ordered by key.
+pub fn find_key_collisions<'a, F: 'a>(
+    features: impl IntoIterator<Item = &'a F>,
+    key_fn: impl Fn(&F) -> Option<FeatureKey>,
+) -> Vec<KeyCollision> {
+    let mut groups: HashMap<FeatureKey, Vec<usize>> = HashMap::new();
+    for (i, feature) in features.into_iter().enumerate() {
+        if let Some(key) = key_fn(feature) {
+            groups.entry(key).or_default().push(i);
+        }
+    }
+    let mut collisions: Vec<KeyCollision> = groups
+        .into_iter()
+        .filter(|(_, indices)| indices.len() > 1)
+        .map(|(key, indices)| KeyCollision { key, indices })
+        .collect();
+    collisions.sort_by_key(|c| c.key);
+    collisions
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::feature::{ChargedFeature, LCMSFeature};
+
+    #[test]
+    fn test_feature_key() {
+        let precision = KeyPrecision::new(0.01, 0.1);
+        let a: LCMSFeature = [
+            (500.001, 10.0, 1.0),
+            (500.002, 10.5, 5.0),
+            (500.0, 11.0, 2.0),
+        ]
+        .into_iter()
+        .collect();
+        let b: LCMSFeature = [(500.003, 10.48, 3.0)].into_iter().collect();
+        let c: LCMSFeature = [(500.2, 10.5, 3.0)].into_iter().collect();
+
+        let key_a = FeatureKey::for_feature(&a, None, precision).unwrap();
+        assert_eq!(key_a.coordinate, 50000);
+        assert_eq!(key_a.time, 105);
+        assert!((key_a.coordinate(precision) - 500.0).abs() < 1e-9);
+        assert_eq!(key_a.to_string(), "50000@105");
+        assert!(FeatureKey::for_feature(&LCMSFeature::empty(), None, precision).is_none());
+
+        let charged = ChargedFeature::new(a.clone(), 2);
+        let key = FeatureKey::for_charged_feature(&charged, precision).unwrap();
+        assert_ne!(key, key_a);
+        assert_eq!(key.to_string(), "50000@105^2");
+
+        let features = [a, b, c];
+        let collisions =
+            find_key_collisions(&features, |f| FeatureKey::for_feature(f, None, precision));
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].key, key_a);
+        assert_eq!(collisions[0].indices, vec![0, 1]);
+
+        let fine = KeyPrecision::new(0.001, 0.01);
+        let collisions = find_key_collisions(&features, |f| FeatureKey::for_feature(f, None, fine));
+        assert!(collisions.is_empty());
+    }
+}
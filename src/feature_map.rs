@@ -4,9 +4,16 @@
 //! is given by [`FeatureMap`].
 //!
 
-use crate::{feature::FeatureLike, CoordinateLike, Tolerance};
-use std::{marker::PhantomData, ops};
-
+use crate::{
+    coordinate::{CoordinateRange, Mass, MZ},
+    feature::{ChargedFeature, Feature, FeatureLike, FeatureLikeMut, TimeInterval},
+    CentroidPeak, CoordinateLike, DeconvolutedPeak, DeconvolutedPeakSet, KnownCharge, PeakSet,
+    Tolerance,
+};
+use std::{
+    marker::PhantomData,
+    ops::{self, RangeBounds},
+};
 
 /// A two dimensional feature collection where features are sorted by the `X` dimension
 /// and each feature is internally sorted by the `Y` dimension.
@@ -85,10 +92,8 @@ where
     /// Return the feature nearest to `query` within `error_tolerance` in
     /// this feature collection, or `None`.
     fn has_feature(&self, query: f64, error_tolerance: Tolerance) -> Option<&T> {
-        return match self.search(query, error_tolerance) {
-            Some(j) => Some(self.get_item(j)),
-            None => None,
-        };
+        self.search(query, error_tolerance)
+            .map(|j| self.get_item(j))
     }
 
     #[inline]
@@ -114,11 +119,11 @@ where
         };
 
         if lower_index < n && self[lower_index].coordinate() < lower_bound {
-                lower_index += 1;
+            lower_index += 1;
         }
 
         if upper_index < n && upper_index > 0 && self[upper_index].coordinate() > upper_bound {
-                upper_index -= 1;
+            upper_index -= 1;
         }
 
         if upper_index < n {
@@ -173,15 +178,15 @@ where
             lower_index += 1;
         }
         let c = lower_index..upper_index + 1;
-        return self.get_slice(c);
+        self.get_slice(c)
     }
 }
 
 /// A mutable kind of [`FeatureMapLike`] which new features can be added to.
-pub trait FeatureMapLikeMut<X, Y, T: FeatureLike<X, Y>> : FeatureMapLike<X, Y, T>
+pub trait FeatureMapLikeMut<X, Y, T: FeatureLike<X, Y>>: FeatureMapLike<X, Y, T>
 where
-    <Self as ops::Index<usize>>::Output: CoordinateLike<X> {
-
+    <Self as ops::Index<usize>>::Output: CoordinateLike<X>,
+{
     /// Add `feature` to the collection, maintaining sort order and feature
     /// indexing.
     fn push(&mut self, feature: T);
@@ -190,10 +195,10 @@ where
     fn sort(&mut self);
 }
 
-
 /// Represents a sorted list of mass spectral features that is a concrete implementation
 /// of [`FeatureMapLike`] and [`FeatureMapLikeMut`]
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FeatureMap<X, Y, T: FeatureLike<X, Y>> {
     features: Vec<T>,
     _x: PhantomData<X>,
@@ -213,7 +218,7 @@ impl<'a, X, Y, T: FeatureLike<X, Y>> FeatureMap<X, Y, T> {
         Self {
             features: Vec::new(),
             _x: PhantomData,
-            _y: PhantomData
+            _y: PhantomData,
         }
     }
 
@@ -252,13 +257,26 @@ impl<'a, X, Y, T: FeatureLike<X, Y>> FeatureMap<X, Y, T> {
             .binary_search_by(|feature| feature.coordinate().partial_cmp(&query).unwrap())
     }
 
+    /// Insert `feature` in coordinate order without re-sorting the map, returning the
+    /// position it was inserted at.
+    ///
+    /// The new feature is placed after any features with the same coordinate, so features
+    /// which were already present keep their relative order, and only those after the
+    /// insertion point shift by one position.
+    pub fn insert(&mut self, feature: T) -> usize {
+        let x = feature.coordinate();
+        let i = self.features.partition_point(|f| f.coordinate() <= x);
+        self.features.insert(i, feature);
+        i
+    }
+
     /// Extract a subset of this [`FeatureMap`] that overlap the specified `y` coordinate
-    pub fn spanning(&'a self, y: f64) -> FeatureMap<X, Y, &T> {
+    pub fn spanning(&'a self, y: f64) -> FeatureMap<X, Y, &'a T> {
         let subset: Vec<_> = self.iter().filter(|f| f.spans(y)).collect();
         FeatureMap::wrap(subset)
     }
 
-    pub fn from_iter<I: Iterator<Item=T>>(iter: I, sort: bool) -> Self {
+    pub fn from_iter<I: Iterator<Item = T>>(iter: I, sort: bool) -> Self {
         let features = iter.collect();
         if sort {
             Self::new(features)
@@ -295,7 +313,8 @@ impl<X, Y, T: FeatureLike<X, Y>> FeatureMapLikeMut<X, Y, T> for FeatureMap<X, Y,
         if self.is_empty() {
             self.features.push(feature)
         } else {
-            let is_tail = self.features.last().as_ref().unwrap().coordinate() <= feature.coordinate();
+            let is_tail =
+                self.features.last().as_ref().unwrap().coordinate() <= feature.coordinate();
             self.features.push(feature);
             if !is_tail {
                 self.sort();
@@ -304,9 +323,7 @@ impl<X, Y, T: FeatureLike<X, Y>> FeatureMapLikeMut<X, Y, T> for FeatureMap<X, Y,
     }
 
     fn sort(&mut self) {
-        self.features.sort_by(|x, y| {
-            x.partial_cmp(y).unwrap()
-        })
+        self.features.sort_by(|x, y| x.partial_cmp(y).unwrap())
     }
 }
 
@@ -355,6 +372,24 @@ macro_rules! impl_slicing {
             }
         }
 
+        impl<$($args)+> std::ops::Index<std::ops::RangeInclusive<usize>> for $t {
+            type Output = [<Self as std::ops::Index<usize>>::Output];
+
+            fn index(&self, index: std::ops::RangeInclusive<usize>) -> &Self::Output {
+                let idx = std::ops::Range { start: *index.start(), end: *index.end() + 1 };
+                <Self as std::ops::Index<std::ops::Range<usize>>>::index(self, idx)
+            }
+        }
+
+        impl<$($args)+> std::ops::Index<std::ops::RangeToInclusive<usize>> for $t {
+            type Output = [<Self as std::ops::Index<usize>>::Output];
+
+            fn index(&self, index: std::ops::RangeToInclusive<usize>) -> &Self::Output {
+                let idx = std::ops::Range { start: 0, end: index.end + 1 };
+                <Self as std::ops::Index<std::ops::Range<usize>>>::index(self, idx)
+            }
+        }
+
     };
 }
 
@@ -374,7 +409,6 @@ impl<X, Y, T: FeatureLike<X, Y>> ops::IndexMut<usize> for FeatureMap<X, Y, T> {
 
 impl_slicing!(FeatureMap<X, Y, T>, X, Y, T: FeatureLike<X, Y>);
 
-
 #[derive(Debug, Default, Clone)]
 pub struct FeatureMapView<'a, X, Y, T: FeatureLike<X, Y>> {
     features: &'a [T],
@@ -441,22 +475,288 @@ impl<'a, X, Y, T: FeatureLike<X, Y>> FeatureMapLike<X, Y, T> for FeatureMapView<
     }
 }
 
+/// How to choose the intensity of each peak in a pseudo-spectrum built by
+/// [`FeatureMap::to_pseudo_spectrum`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PseudoSpectrumIntensity {
+    /// The most intense point of the feature within the time window
+    #[default]
+    Apex,
+    /// The area under the feature within the time window
+    Area,
+}
+
+/// The intensity of the part of `feature` lying within `time_range`, or `None` if
+/// the feature has no points within it
+fn windowed_intensity<X, Y, T: FeatureLike<X, Y>, R: RangeBounds<f64>>(
+    feature: &T,
+    time_range: &R,
+    mode: PseudoSpectrumIntensity,
+) -> Option<f32> {
+    let mut points = feature
+        .iter()
+        .filter(|(_, y, _)| time_range.contains(*y))
+        .map(|(_, y, z)| (*y, *z));
+    let (mut last_y, mut last_z) = points.next()?;
+    let mut acc = match mode {
+        PseudoSpectrumIntensity::Apex => last_z,
+        PseudoSpectrumIntensity::Area => 0.0,
+    };
+    for (y, z) in points {
+        match mode {
+            PseudoSpectrumIntensity::Apex => acc = acc.max(z),
+            PseudoSpectrumIntensity::Area => {
+                acc += ((y - last_y) * (z + last_z) as f64 / 2.0) as f32;
+            }
+        }
+        last_y = y;
+        last_z = z;
+    }
+    Some(acc)
+}
+
+impl<Y, T: FeatureLike<MZ, Y>> FeatureMap<MZ, Y, T> {
+    /// Collapse the features with points in `time_range` into a single centroid
+    /// pseudo-spectrum, with one peak per feature at the feature's m/z and an intensity
+    /// chosen by `mode` from the points within `time_range`.
+    pub fn to_pseudo_spectrum<R: RangeBounds<f64>>(
+        &self,
+        time_range: R,
+        mode: PseudoSpectrumIntensity,
+    ) -> PeakSet {
+        self.iter()
+            .filter_map(|f| {
+                windowed_intensity(f, &time_range, mode)
+                    .map(|intensity| CentroidPeak::new(f.coordinate(), intensity, 0))
+            })
+            .collect()
+    }
+}
+
+impl<Y, T: FeatureLike<Mass, Y> + KnownCharge> FeatureMap<Mass, Y, T> {
+    /// Collapse the features with points in `time_range` into a single deconvoluted
+    /// pseudo-spectrum, with one peak per feature at the feature's neutral mass and charge,
+    /// and an intensity chosen by `mode` from the points within `time_range`.
+    pub fn to_pseudo_spectrum<R: RangeBounds<f64>>(
+        &self,
+        time_range: R,
+        mode: PseudoSpectrumIntensity,
+    ) -> DeconvolutedPeakSet {
+        self.iter()
+            .filter_map(|f| {
+                windowed_intensity(f, &time_range, mode).map(|intensity| {
+                    DeconvolutedPeak::new(f.coordinate(), intensity, f.charge(), 0)
+                })
+            })
+            .collect()
+    }
+}
+
+impl<X, Y, T: FeatureLike<X, Y>> FeatureMap<X, Y, T> {
+    /// Apply `truncate` to every feature, then drop the features it left empty and restore
+    /// the sort order, returning the number of features removed
+    fn truncate_features<F: FnMut(&mut T)>(&mut self, truncate: F) -> usize {
+        let n = self.len();
+        self.features.iter_mut().for_each(truncate);
+        self.features.retain(|f| !f.is_empty());
+        self.sort();
+        n - self.len()
+    }
+}
+
+impl<X, Y> FeatureMap<X, Y, Feature<X, Y>> {
+    /// Remove the points of every feature whose coordinate lies outside `range`, e.g. the
+    /// acquisition range of the instrument, dropping features left without any points.
+    /// Returns the number of features removed.
+    ///
+    /// A feature crossing the edge of `range` keeps only its points within it, so its
+    /// coordinate, intensity and area reflect only those points.
+    pub fn clip_to(&mut self, range: CoordinateRange<X>) -> usize {
+        self.truncate_features(|f| {
+            f.retain_points(|x, _, _| range.contains_raw(&x));
+        })
+    }
+}
+
+impl<X, Y> FeatureMap<X, Y, ChargedFeature<X, Y>> {
+    /// See [`FeatureMap::clip_to`]
+    pub fn clip_to(&mut self, range: CoordinateRange<X>) -> usize {
+        self.truncate_features(|f| {
+            f.retain_points(|x, _, _| range.contains_raw(&x));
+        })
+    }
+}
+
+/// How to collapse each feature to a single peak in [`FeatureMap::to_peak_set`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RepresentativePeak {
+    /// The coordinate and intensity of the feature's most intense point
+    #[default]
+    Apex,
+    /// The intensity-weighted mean coordinate of the feature and its total intensity
+    WeightedCentroid,
+    /// The intensity-weighted mean coordinate of the feature and the area under it
+    Area,
+}
+
+/// The coordinate and intensity of the peak representing `feature`, or `None` if the
+/// feature is empty
+fn representative_peak<X, Y, T: FeatureLike<X, Y>>(
+    feature: &T,
+    policy: RepresentativePeak,
+) -> Option<(f64, f32)> {
+    if feature.is_empty() {
+        return None;
+    }
+    match policy {
+        RepresentativePeak::Apex => {
+            feature
+                .iter()
+                .fold(None, |best: Option<(f64, f32)>, (x, _, z)| match best {
+                    Some((_, best_z)) if best_z >= *z => best,
+                    _ => Some((*x, *z)),
+                })
+        }
+        RepresentativePeak::WeightedCentroid => Some((feature.coordinate(), feature.intensity())),
+        RepresentativePeak::Area => Some((feature.coordinate(), feature.area())),
+    }
+}
+
+impl<Y, T: FeatureLike<MZ, Y>> FeatureMap<MZ, Y, T> {
+    /// Collapse each feature to a single centroid peak chosen by `policy`, giving a sorted
+    /// and indexed peak list with one peak per non-empty feature.
+    pub fn to_peak_set(&self, policy: RepresentativePeak) -> PeakSet {
+        PeakSet::new(
+            self.iter()
+                .filter_map(|f| representative_peak(f, policy))
+                .map(|(mz, intensity)| CentroidPeak::new(mz, intensity, 0))
+                .collect(),
+        )
+    }
+}
+
+impl<Y, T: FeatureLike<Mass, Y> + KnownCharge> FeatureMap<Mass, Y, T> {
+    /// Collapse each feature to a single deconvoluted peak with the feature's charge, chosen
+    /// by `policy`, giving a sorted and indexed peak list with one peak per non-empty feature.
+    pub fn to_peak_set(&self, policy: RepresentativePeak) -> DeconvolutedPeakSet {
+        DeconvolutedPeakSet::new(
+            self.iter()
+                .filter_map(|f| {
+                    representative_peak(f, policy).map(|(mass, intensity)| {
+                        DeconvolutedPeak::new(mass, intensity, f.charge(), 0)
+                    })
+                })
+                .collect(),
+        )
+    }
+}
+
+/// The fraction of the shorter of `a` and `b`'s time spans that they share, where
+/// a feature with a single time point overlaps fully if the other feature spans it.
+fn time_overlap_fraction<X, Y, A: FeatureLike<X, Y>, B: FeatureLike<X, Y>>(a: &A, b: &B) -> f64 {
+    let (Some(a_start), Some(a_end), Some(b_start), Some(b_end)) =
+        (a.start_time(), a.end_time(), b.start_time(), b.end_time())
+    else {
+        return 0.0;
+    };
+    let overlap = a_end.min(b_end) - a_start.max(b_start);
+    if overlap < 0.0 {
+        return 0.0;
+    }
+    let shortest = (a_end - a_start).min(b_end - b_start);
+    if shortest <= 0.0 {
+        1.0
+    } else {
+        (overlap / shortest).min(1.0)
+    }
+}
+
+impl<X, Y, T: FeatureLikeMut<X, Y>> FeatureMap<X, Y, T> {
+    /// Combine features whose coordinates match within `mz_tol` and whose time spans
+    /// overlap by at least `min_time_overlap_fraction` of the shorter span, repeating
+    /// until no more features can be combined. Returns the number of features absorbed.
+    ///
+    /// The points of each absorbed feature are added to the feature it is merged into, with
+    /// intensities observed at the same time summed, so the apex and area reflect the
+    /// combined trace. This repairs features which a greedy feature finder split into
+    /// fragments.
+    pub fn merge_overlapping(
+        &mut self,
+        mz_tol: Tolerance,
+        min_time_overlap_fraction: f64,
+    ) -> usize {
+        let mut absorbed = 0;
+        loop {
+            let mut features: Vec<Option<T>> = std::mem::take(&mut self.features)
+                .into_iter()
+                .map(Some)
+                .collect();
+            let mut changed = false;
+            for i in 0..features.len() {
+                let Some(mut target) = features[i].take() else {
+                    continue;
+                };
+                for slot in features[i + 1..].iter_mut() {
+                    let Some(other) = slot.as_ref() else {
+                        continue;
+                    };
+                    let (_, upper) = mz_tol.bounds(target.coordinate());
+                    if other.coordinate() > upper {
+                        break;
+                    }
+                    if !mz_tol.test(other.coordinate(), target.coordinate())
+                        || time_overlap_fraction(&target, other) < min_time_overlap_fraction
+                    {
+                        continue;
+                    }
+                    let other = slot.take().unwrap();
+                    for (x, y, z) in other.iter() {
+                        match target.find_time(*y) {
+                            (Some(k), 0.0) => {
+                                let (tx, _, tz) = target.iter_mut().nth(k).unwrap();
+                                let total = *tz + *z;
+                                if total > 0.0 {
+                                    *tx = (*tx * *tz as f64 + *x * *z as f64) / total as f64;
+                                }
+                                *tz = total;
+                            }
+                            _ => target.push_raw(*x, *y, *z),
+                        }
+                    }
+                    absorbed += 1;
+                    changed = true;
+                }
+                features[i] = Some(target);
+            }
+            self.features = features.into_iter().flatten().collect();
+            self.sort();
+            if !changed {
+                break;
+            }
+        }
+        absorbed
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::feature::LCMSFeature;
-    use crate::test_data;
+    use crate::feature::{DeconvolvedLCMSFeature, LCMSFeature};
     use crate::prelude::*;
+    use crate::test_data;
 
     #[test]
     fn test_sequence_behavior() {
         let source_peaks = test_data::read_peaks_from_file("./test/data/test.txt").unwrap();
 
-        let feature_map: FeatureMap<_, _, LCMSFeature> = source_peaks.iter().map(|p| {
-            let mut feature = LCMSFeature::empty();
-            feature.push(&p, 1.0);
-            feature
-        }).collect();
+        let feature_map: FeatureMap<_, _, LCMSFeature> = source_peaks
+            .iter()
+            .map(|p| {
+                let mut feature = LCMSFeature::empty();
+                feature.push(&p, 1.0);
+                feature
+            })
+            .collect();
 
         assert_eq!(feature_map.len(), 485);
         assert!((feature_map[0].mz() - 231.3888).abs() < 1e-3);
@@ -502,14 +802,11 @@ mod test {
 
         let block = feature_map.between(1313.0, 1316.0, "10.0ppm".parse().unwrap());
         assert_eq!(block.len(), 3);
-
     }
 
     #[test]
     fn test_edgecases() {
-        let features = FeatureMap::new(vec![
-            LCMSFeature::from_iter([(500.0, 2., 1.0)])
-        ]);
+        let features = FeatureMap::new(vec![LCMSFeature::from_iter([(500.0, 2., 1.0)])]);
 
         let p = features.has_feature(500.0, Tolerance::Da(1.0));
         assert!(p.is_some());
@@ -523,6 +820,177 @@ mod test {
         assert!(p.is_none());
 
         let p = features.all_features_for(500.0, Tolerance::Da(1.0));
-        assert!(p.len() == 0);
+        assert!(p.is_empty());
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut features: FeatureMap<_, _, LCMSFeature> = FeatureMap::empty();
+        assert_eq!(
+            features.insert([(600.0, 2.0, 1.0)].into_iter().collect()),
+            0
+        );
+        assert_eq!(
+            features.insert([(500.0, 1.0, 1.0)].into_iter().collect()),
+            0
+        );
+        assert_eq!(
+            features.insert([(500.0, 3.0, 1.0)].into_iter().collect()),
+            1
+        );
+        assert_eq!(
+            features.insert([(700.0, 4.0, 1.0)].into_iter().collect()),
+            3
+        );
+
+        let times: Vec<f64> = features.iter().map(|f| f.apex_time().unwrap()).collect();
+        assert_eq!(times, [1.0, 3.0, 2.0, 4.0]);
+        assert_eq!(
+            features.all_features_for(500.0, Tolerance::Da(0.1)).len(),
+            2
+        );
+        assert_eq!(features.spanning(3.0).len(), 1);
+    }
+
+    #[test]
+    fn test_to_pseudo_spectrum() {
+        let a: LCMSFeature = [(500.0, 10.0, 2.0), (500.0, 11.0, 6.0), (500.0, 12.0, 4.0)]
+            .into_iter()
+            .collect();
+        let b: LCMSFeature = [(600.0, 11.5, 3.0), (600.0, 12.5, 5.0)]
+            .into_iter()
+            .collect();
+        let c: LCMSFeature = [(700.0, 20.0, 1.0), (700.0, 21.0, 1.0)]
+            .into_iter()
+            .collect();
+        let map = FeatureMap::new(vec![c, b, a]);
+
+        let spectrum = map.to_pseudo_spectrum(10.0..=12.0, PseudoSpectrumIntensity::Apex);
+        assert_eq!(spectrum.len(), 2);
+        assert_eq!(spectrum[0].mz, 500.0);
+        assert_eq!(spectrum[0].intensity, 6.0);
+        assert_eq!(spectrum[1].intensity, 3.0);
+
+        let spectrum = map.to_pseudo_spectrum(10.0..=12.0, PseudoSpectrumIntensity::Area);
+        assert_eq!(spectrum[0].intensity, 9.0);
+
+        let charged = FeatureMap::new(vec![DeconvolvedLCMSFeature::new(
+            [(1000.0, 10.0, 2.0), (1000.0, 11.0, 6.0)]
+                .into_iter()
+                .collect(),
+            2,
+        )]);
+        let spectrum = charged.to_pseudo_spectrum(.., PseudoSpectrumIntensity::Apex);
+        assert_eq!(spectrum[0].charge, 2);
+        assert_eq!(spectrum[0].neutral_mass, 1000.0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_to_peak_set() {
+        let a: LCMSFeature = [(500.0, 10.0, 2.0), (500.01, 11.0, 6.0), (500.0, 12.0, 2.0)]
+            .into_iter()
+            .collect();
+        let b: LCMSFeature = [(400.0, 10.0, 4.0)].into_iter().collect();
+        let map = FeatureMap::new(vec![a, LCMSFeature::empty(), b]);
+
+        let peaks = map.to_peak_set(RepresentativePeak::Apex);
+        assert_eq!(peaks.len(), 2);
+        assert_eq!((peaks[0].mz, peaks[0].intensity), (400.0, 4.0));
+        assert_eq!((peaks[1].mz, peaks[1].intensity), (500.01, 6.0));
+        assert_eq!(peaks[1].index, 1);
+
+        let peaks = map.to_peak_set(RepresentativePeak::WeightedCentroid);
+        assert!((peaks[1].mz - 500.006).abs() < 1e-9);
+        assert_eq!(peaks[1].intensity, 10.0);
+        assert_eq!(map.to_peak_set(RepresentativePeak::Area)[1].intensity, 8.0);
+
+        let charged = FeatureMap::new(vec![DeconvolvedLCMSFeature::new(
+            [(1000.0, 10.0, 2.0), (1000.0, 11.0, 6.0)]
+                .into_iter()
+                .collect(),
+            3,
+        )]);
+        let peaks = charged.to_peak_set(RepresentativePeak::Apex);
+        assert_eq!(peaks[0].charge, 3);
+        assert_eq!(peaks[0].intensity, 6.0);
+    }
+
+    #[test]
+    fn test_clip_to() {
+        let a: LCMSFeature = [
+            (199.999, 10.0, 2.0),
+            (200.001, 11.0, 6.0),
+            (200.001, 12.0, 2.0),
+        ]
+        .into_iter()
+        .collect();
+        let b: LCMSFeature = [(500.0, 10.0, 4.0)].into_iter().collect();
+        let c: LCMSFeature = [(1500.0, 10.0, 4.0)].into_iter().collect();
+        let mut map = FeatureMap::new(vec![a, b, c]);
+        assert_eq!(map[0].area(), 8.0);
+
+        let removed = map.clip_to(CoordinateRange::new(Some(200.0), Some(1000.0)));
+        assert_eq!(removed, 1);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[0].len(), 2);
+        assert_eq!(map[0].coordinate(), 200.001);
+        assert_eq!(map[0].area(), 4.0);
+
+        let mut charged = FeatureMap::new(vec![DeconvolvedLCMSFeature::new(
+            [(1000.0, 10.0, 2.0)].into_iter().collect(),
+            2,
+        )]);
+        assert_eq!(charged.clip_to(CoordinateRange::new(None, Some(900.0))), 1);
+        assert!(charged.is_empty());
+    }
+
+    #[test]
+    fn test_merge_overlapping() {
+        let head: LCMSFeature = (1..=5).map(|t| (500.0, t as f64, 10.0)).collect();
+        let tail: LCMSFeature = (3..=7).map(|t| (500.002, t as f64, 30.0)).collect();
+        let later: LCMSFeature = (20..=22).map(|t| (500.0, t as f64, 10.0)).collect();
+        let other: LCMSFeature = (1..=5).map(|t| (600.0, t as f64, 10.0)).collect();
+        let mut map = FeatureMap::new(vec![head, tail, later, other]);
+
+        assert_eq!(map.clone().merge_overlapping(Tolerance::PPM(10.0), 0.75), 0);
+        assert_eq!(map.merge_overlapping(Tolerance::PPM(10.0), 0.5), 1);
+        assert_eq!(map.len(), 3);
+
+        let merged = map.iter().find(|f| f.len() == 7).unwrap();
+        assert_eq!(merged.start_time(), Some(1.0));
+        assert_eq!(merged.end_time(), Some(7.0));
+        let (x, _, z) = merged.iter().nth(3).unwrap();
+        assert_eq!(*z, 40.0);
+        assert!((x - 500.0015).abs() < 1e-9);
+        assert_eq!(merged.area(), 180.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() -> serde_json::Result<()> {
+        use crate::feature::{Feature, SimpleFeature};
+        use crate::{Time, MZ};
+
+        let a: Feature<Mass, Time> = [(1000.0, 10.0, 5.0), (1000.01, 10.5, 8.0)]
+            .into_iter()
+            .collect();
+        let b: Feature<Mass, Time> = [(1200.0, 11.0, 3.0)].into_iter().collect();
+        let features: FeatureMap<_, _, DeconvolvedLCMSFeature> = FeatureMap::new(vec![
+            DeconvolvedLCMSFeature::new(a, 2),
+            DeconvolvedLCMSFeature::new(b, 1),
+        ]);
+        let text = serde_json::to_string(&features)?;
+        let dup: FeatureMap<_, _, DeconvolvedLCMSFeature> = serde_json::from_str(&text)?;
+        assert_eq!(dup.len(), features.len());
+        assert!(dup.iter().zip(features.iter()).all(|(a, b)| a == b));
+
+        let mut feature = SimpleFeature::<MZ, Time>::empty(500.0);
+        feature.push_raw(500.0, 1.0, 4.0);
+        feature.push_raw(500.0, 2.0, 6.0);
+        let simple: FeatureMap<MZ, Time, SimpleFeature<MZ, Time>> = FeatureMap::new(vec![feature]);
+        let text = serde_json::to_string(&simple)?;
+        let dup: FeatureMap<MZ, Time, SimpleFeature<MZ, Time>> = serde_json::from_str(&text)?;
+        assert_eq!(dup[0], simple[0]);
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+//! Build features by linking peaks across a sequence of peak lists.
+//!
+//! An LC-MS run produces one peak list per scan, and an analyte eluting from the column
+//! appears as a peak at nearly the same m/z in each of a run of consecutive scans.
+//! [`FeatureExtracter`] consumes the peak lists in time order and links each peak to the
+//! trace, if any, that it continues within a [`Tolerance`], so that every analyte becomes a
+//! [`LCMSFeature`]. A trace may skip a few scans where its peak was not picked, up to a
+//! configurable number of missing scans, before it is considered finished.
+//!
+//! [`extract_features`] runs the whole process over an iterator of peak lists at once.
+use crate::coordinate::{CoordinateLike, Time, MZ};
+use crate::feature::LCMSFeature;
+use crate::feature_map::FeatureMap;
+use crate::mass_error::Tolerance;
+use crate::peak::IntensityMeasurement;
+use crate::queryable::PeakQueryable;
+
+/// A feature which may still be extended by the next peak list
+#[derive(Debug, Clone)]
+struct Trace {
+    feature: LCMSFeature,
+    /// The sum of intensity-weighted m/z, to maintain the trace's m/z cheaply
+    weighted_mz: f64,
+    total_intensity: f64,
+    /// The number of consecutive peak lists without a matching peak
+    missing: usize,
+}
+
+impl Trace {
+    fn new(mz: f64, time: f64, intensity: f32) -> Self {
+        let mut trace = Self {
+            feature: LCMSFeature::empty(),
+            weighted_mz: 0.0,
+            total_intensity: 0.0,
+            missing: 0,
+        };
+        trace.push(mz, time, intensity);
+        trace
+    }
+
+    fn push(&mut self, mz: f64, time: f64, intensity: f32) {
+        self.feature.push_raw(mz, time, intensity);
+        self.weighted_mz += mz * intensity as f64;
+        self.total_intensity += intensity as f64;
+        self.missing = 0;
+    }
+
+    /// The intensity-weighted m/z of the trace, or the m/z of its last point if it has no
+    /// intensity
+    fn mz(&self) -> f64 {
+        if self.total_intensity > 0.0 {
+            self.weighted_mz / self.total_intensity
+        } else {
+            let (_, mzs, _) = self.feature.as_arrays();
+            mzs.last().copied().unwrap_or_default()
+        }
+    }
+}
+
+/// Link peaks across successive peak lists into [`LCMSFeature`]s.
+///
+/// Each peak list passed to [`FeatureExtracter::push`] is matched against the traces still
+/// being extended. A peak and a trace are linked when the peak's m/z is within the tolerance
+/// of the trace's intensity-weighted m/z, closest pairs first, so each trace takes at most
+/// one peak per peak list and each peak joins at most one trace. Peaks left over start new
+/// traces, and a trace which goes more than `max_missing` peak lists without a peak is
+/// finished.
+#[derive(Debug, Clone)]
+pub struct FeatureExtracter {
+    tolerance: Tolerance,
+    max_missing: usize,
+    min_length: usize,
+    last_time: Option<f64>,
+    active: Vec<Trace>,
+    finished: Vec<LCMSFeature>,
+}
+
+impl FeatureExtracter {
+    /// Create an extracter which links peaks within `tolerance`, finishes a trace as soon as
+    /// a peak list lacks a peak for it, and keeps features of any length
+    pub fn new(tolerance: Tolerance) -> Self {
+        Self {
+            tolerance,
+            max_missing: 0,
+            min_length: 1,
+            last_time: None,
+            active: Vec::new(),
+            finished: Vec::new(),
+        }
+    }
+
+    /// Set the number of consecutive peak lists a trace may lack a peak in and still be
+    /// extended
+    pub fn with_max_missing(mut self, max_missing: usize) -> Self {
+        self.max_missing = max_missing;
+        self
+    }
+
+    /// Set the number of points a finished feature must have to be kept
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    pub fn tolerance(&self) -> Tolerance {
+        self.tolerance
+    }
+
+    pub fn max_missing(&self) -> usize {
+        self.max_missing
+    }
+
+    pub fn min_length(&self) -> usize {
+        self.min_length
+    }
+
+    /// The number of traces which may still be extended
+    pub fn active_len(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Link the peaks of the peak list acquired at `time` to the active traces
+    ///
+    /// # Panics
+    /// If `time` is earlier than the time of the previous peak list
+    pub fn push<A>(&mut self, time: f64, peaks: &A)
+    where
+        A: PeakQueryable<MZ> + ?Sized,
+        A::Peak: IntensityMeasurement,
+    {
+        if let Some(last_time) = self.last_time {
+            assert!(
+                time >= last_time,
+                "Peak lists must be pushed in time order, got {time} after {last_time}"
+            );
+        }
+        self.last_time = Some(time);
+
+        let mut candidates = Vec::new();
+        for (i, trace) in self.active.iter().enumerate() {
+            let mz = trace.mz();
+            for j in peaks.between(mz, mz, self.tolerance) {
+                let Some(peak) = peaks.get(j) else {
+                    continue;
+                };
+                if self.tolerance.test(peak.coordinate(), mz) {
+                    candidates.push((self.tolerance.call(peak.coordinate(), mz).abs(), i, j));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut trace_taken = vec![false; self.active.len()];
+        let mut peak_taken = vec![false; peaks.len()];
+        for (_, i, j) in candidates {
+            if trace_taken[i] || peak_taken[j] {
+                continue;
+            }
+            let Some(peak) = peaks.get(j) else {
+                continue;
+            };
+            self.active[i].push(peak.coordinate(), time, peak.intensity());
+            trace_taken[i] = true;
+            peak_taken[j] = true;
+        }
+
+        for (trace, taken) in self.active.iter_mut().zip(trace_taken) {
+            if !taken {
+                trace.missing += 1;
+            }
+        }
+        let max_missing = self.max_missing;
+        let (active, expired): (Vec<_>, Vec<_>) = std::mem::take(&mut self.active)
+            .into_iter()
+            .partition(|trace| trace.missing <= max_missing);
+        self.active = active;
+        for trace in expired {
+            self.finish_trace(trace);
+        }
+
+        for (j, taken) in peak_taken.into_iter().enumerate() {
+            if taken {
+                continue;
+            }
+            if let Some(peak) = peaks.get(j) {
+                self.active
+                    .push(Trace::new(peak.coordinate(), time, peak.intensity()));
+            }
+        }
+    }
+
+    fn finish_trace(&mut self, trace: Trace) {
+        if trace.feature.len() >= self.min_length {
+            self.finished.push(trace.feature);
+        }
+    }
+
+    /// Take the features finished so far, leaving the active traces to be extended
+    pub fn drain_finished(&mut self) -> Vec<LCMSFeature> {
+        std::mem::take(&mut self.finished)
+    }
+
+    /// Finish every active trace and return all of the features not yet drained
+    pub fn finish(mut self) -> FeatureMap<MZ, Time, LCMSFeature> {
+        for trace in std::mem::take(&mut self.active) {
+            self.finish_trace(trace);
+        }
+        FeatureMap::new(self.finished)
+    }
+}
+
+/// Link the peaks of every `(time, peak list)` pair in `scans` into features, as
+/// [`FeatureExtracter`] does
+pub fn extract_features<'a, A, I>(
+    scans: I,
+    tolerance: Tolerance,
+    max_missing: usize,
+) -> FeatureMap<MZ, Time, LCMSFeature>
+where
+    A: PeakQueryable<MZ> + ?Sized + 'a,
+    A::Peak: IntensityMeasurement,
+    I: IntoIterator<Item = (f64, &'a A)>,
+{
+    let mut extracter = FeatureExtracter::new(tolerance).with_max_missing(max_missing);
+    for (time, peaks) in scans {
+        extracter.push(time, peaks);
+    }
+    extracter.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::feature::TimeInterval;
+    use crate::feature_map::FeatureMapLike;
+    use crate::{CentroidPeak, PeakSet};
+
+    #[test]
+    fn test_feature_extraction() {
+        let scan = |mzs: &[f64]| {
+            PeakSet::new(
+                mzs.iter()
+                    .map(|mz| CentroidPeak::new(*mz, 100.0, 0))
+                    .collect(),
+            )
+        };
+        let scans = [
+            (1.0, scan(&[300.0, 500.0])),
+            (2.0, scan(&[300.001, 500.002, 700.0])),
+            (3.0, scan(&[299.999, 700.001])),
+            (4.0, scan(&[300.0, 500.001])),
+            (5.0, scan(&[300.002])),
+        ];
+        let tol = Tolerance::PPM(10.0);
+
+        let features = extract_features(scans.iter().map(|(t, p)| (*t, p)), tol, 1);
+        assert_eq!(features.len(), 3);
+        assert_eq!(features[0].len(), 5);
+        assert_eq!(features[1].len(), 3);
+        assert_eq!(features[1].start_time(), Some(1.0));
+        assert_eq!(features[1].end_time(), Some(4.0));
+        assert_eq!(features[2].len(), 2);
+
+        // Without allowing gaps the m/z 500 trace is broken in two
+        let mut extracter = FeatureExtracter::new(tol).with_min_length(2);
+        for (time, peaks) in scans.iter() {
+            extracter.push(*time, peaks);
+        }
+        assert_eq!(extracter.active_len(), 1);
+        let drained = extracter.drain_finished();
+        assert_eq!(drained.len(), 2);
+        let features = extracter.finish();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].len(), 5);
+        assert!(features.search(300.0, tol).is_some());
+
+        // A trace without intensity is still followed by the m/z of its peaks
+        let silent = |mz: f64| PeakSet::new(vec![CentroidPeak::new(mz, 0.0, 0)]);
+        let features = extract_features([(1.0, &silent(300.0)), (2.0, &silent(300.001))], tol, 0);
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].len(), 2);
+    }
+}
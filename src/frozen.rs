@@ -0,0 +1,274 @@
+//! An immutable peak list optimized for many random-access searches.
+//!
+//! Database search engines query the same spectrum for thousands of theoretical
+//! fragment coordinates. [`FrozenPeakSet`] gives up the ability to add peaks in exchange
+//! for a bucketed lookup table that narrows each search to the handful of peaks within
+//! one fixed-width bucket, followed by a branch-free binary search over those peaks.
+//! `cargo bench --bench frozen_search` compares it against the plain binary search of a
+//! [`PeakSet`](crate::PeakSet).
+//!
+//! Because it can't be modified, a [`FrozenPeakSet`] can be shared freely between
+//! threads, e.g. behind an [`Arc`](std::sync::Arc).
+use std::marker::PhantomData;
+use std::ops;
+
+use crate::coordinate::IndexedCoordinate;
+use crate::peak_set::{PeakCollection, PeakSetVec, PeakSetView};
+
+/// The default width of each lookup bucket, in coordinate units
+pub const DEFAULT_BUCKET_WIDTH: f64 = 1.0;
+
+/// An immutable, sorted peak list with a bucketed lookup table for fast searching
+#[derive(Debug, Clone)]
+pub struct FrozenPeakSet<P: IndexedCoordinate<C>, C> {
+    peaks: Box<[P]>,
+    /// The coordinate at which the first bucket starts
+    origin: f64,
+    bucket_width: f64,
+    /// `offsets[b]` is the number of peaks which fall in a bucket before bucket `b`,
+    /// as computed by [`FrozenPeakSet::bucket_of`]. The last entry is the number of
+    /// peaks with a non-NaN coordinate.
+    offsets: Box<[u32]>,
+    _c: PhantomData<C>,
+}
+
+impl<P: IndexedCoordinate<C>, C> FrozenPeakSet<P, C> {
+    /// Freeze `peaks` using buckets [`DEFAULT_BUCKET_WIDTH`] units wide
+    pub fn new(peaks: PeakSetVec<P, C>) -> Self {
+        Self::with_bucket_width(peaks, DEFAULT_BUCKET_WIDTH)
+    }
+
+    /// Freeze `peaks` using buckets `bucket_width` units wide. Narrower buckets
+    /// make each search shorter at the cost of a larger lookup table.
+    ///
+    /// # Panics
+    /// If `bucket_width` is not positive, or if there are more than [`u32::MAX`] peaks
+    pub fn with_bucket_width(peaks: PeakSetVec<P, C>, bucket_width: f64) -> Self {
+        assert!(
+            bucket_width > 0.0,
+            "Bucket width must be positive, got {bucket_width}"
+        );
+        assert!(
+            peaks.len() <= u32::MAX as usize,
+            "Cannot freeze more than {} peaks",
+            u32::MAX
+        );
+        let peaks = peaks.peaks.into_boxed_slice();
+        let n_finite = peaks
+            .iter()
+            .rposition(|p| !p.coordinate().is_nan())
+            .map_or(0, |i| i + 1);
+
+        if n_finite == 0 {
+            return Self {
+                peaks,
+                origin: 0.0,
+                bucket_width,
+                offsets: vec![0].into_boxed_slice(),
+                _c: PhantomData,
+            };
+        }
+
+        let origin = (peaks[0].coordinate() / bucket_width).floor() * bucket_width;
+        let bucket_of = |x: f64| Self::bucket_of(x, origin, bucket_width) as usize;
+        let n_buckets = bucket_of(peaks[n_finite - 1].coordinate()) + 1;
+
+        // Assign peaks to buckets with the same expression used at query time, rather than
+        // comparing against bucket start coordinates, so that the two can't round apart at
+        // a bucket edge
+        let mut offsets = Vec::with_capacity(n_buckets + 1);
+        let mut i = 0;
+        for b in 0..n_buckets {
+            while i < n_finite && bucket_of(peaks[i].coordinate()) < b {
+                i += 1;
+            }
+            offsets.push(i as u32);
+        }
+        offsets.push(n_finite as u32);
+
+        Self {
+            peaks,
+            origin,
+            bucket_width,
+            offsets: offsets.into_boxed_slice(),
+            _c: PhantomData,
+        }
+    }
+
+    pub fn bucket_width(&self) -> f64 {
+        self.bucket_width
+    }
+
+    pub fn as_slice(&self) -> &[P] {
+        &self.peaks
+    }
+
+    /// Borrow the peaks as a [`PeakSetView`]
+    pub fn as_view(&self) -> PeakSetView<'_, P, C> {
+        // SAFETY: The peaks came from a `PeakSetVec` and can't have been modified since
+        unsafe { PeakSetView::wrap(&self.peaks) }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, P> {
+        self.peaks.iter()
+    }
+
+    /// Unfreeze the peak list, discarding the lookup table
+    pub fn into_inner(self) -> PeakSetVec<P, C> {
+        PeakSetVec::wrap(self.peaks.into_vec())
+    }
+
+    /// The bucket containing coordinate `x`, which may be negative or past the last bucket
+    #[inline]
+    fn bucket_of(x: f64, origin: f64, bucket_width: f64) -> f64 {
+        ((x - origin) / bucket_width).floor()
+    }
+
+    /// The index of the first peak whose coordinate is not less than `query`
+    #[inline]
+    fn lower_bound(&self, query: f64) -> usize {
+        let n_finite = self.offsets[self.offsets.len() - 1] as usize;
+        if query.is_nan() {
+            return n_finite;
+        }
+        let bucket = Self::bucket_of(query, self.origin, self.bucket_width);
+        if bucket < 0.0 {
+            return 0;
+        }
+        let bucket = bucket as usize;
+        if bucket >= self.offsets.len() - 1 {
+            return n_finite;
+        }
+        let start = self.offsets[bucket] as usize;
+        let end = self.offsets[bucket + 1] as usize;
+        // The standard library's binary search is branch-free, and benchmarks faster here
+        // than a hand-written loop
+        start + self.peaks[start..end].partition_point(|p| p.coordinate() < query)
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> From<PeakSetVec<P, C>> for FrozenPeakSet<P, C> {
+    fn from(value: PeakSetVec<P, C>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> ops::Index<usize> for FrozenPeakSet<P, C> {
+    type Output = P;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.peaks[i]
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> PeakCollection<P, C> for FrozenPeakSet<P, C> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.peaks.len()
+    }
+
+    #[inline]
+    fn get_item(&self, i: usize) -> &P {
+        &self.peaks[i]
+    }
+
+    #[inline]
+    fn get_slice(&self, i: ops::Range<usize>) -> &[P] {
+        &self.peaks[i]
+    }
+
+    #[inline]
+    fn search_by(&self, query: f64) -> Result<usize, usize> {
+        let i = self.lower_bound(query);
+        if i < self.peaks.len() && self.peaks[i].coordinate() == query {
+            Ok(i)
+        } else {
+            Err(i)
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &P> {
+        self.peaks.iter()
+    }
+}
+
+impl<'a, P: IndexedCoordinate<C>, C> IntoIterator for &'a FrozenPeakSet<P, C> {
+    type Item = &'a P;
+
+    type IntoIter = std::slice::Iter<'a, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.peaks.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_data, CentroidPeak, PeakSet, Tolerance};
+
+    #[test]
+    fn test_frozen_search() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        for width in [0.05, 1.0, 25.0] {
+            let frozen = FrozenPeakSet::with_bucket_width(peaks.clone(), width);
+            assert_eq!(frozen.len(), peaks.len());
+            let mut query = 100.0;
+            while query < 2100.0 {
+                let expected = peaks.has_peak(query, Tolerance::PPM(20.0)).map(|p| p.index);
+                let found = frozen
+                    .has_peak(query, Tolerance::PPM(20.0))
+                    .map(|p| p.index);
+                assert_eq!(expected, found, "query {query} with bucket width {width}");
+                assert_eq!(
+                    peaks.all_peaks_for(query, Tolerance::Da(2.0)).len(),
+                    frozen.all_peaks_for(query, Tolerance::Da(2.0)).len()
+                );
+                query += 0.731;
+            }
+            for p in peaks.iter() {
+                assert_eq!(frozen.search_by(p.mz), Ok(p.index as usize));
+            }
+        }
+
+        let frozen = FrozenPeakSet::new(peaks);
+        assert_eq!(frozen.search_by(1e6), Err(frozen.len()));
+        assert_eq!(frozen.search_by(0.0), Err(0));
+        assert_eq!(frozen.into_inner().len(), 485);
+
+        let empty = FrozenPeakSet::new(PeakSet::empty());
+        assert_eq!(empty.search_by(500.0), Err(0));
+
+        let single = FrozenPeakSet::new(PeakSet::new(vec![CentroidPeak::new(500.0, 1.0, 0)]));
+        assert!(single.has_peak(500.0, Tolerance::Da(0.1)).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_frozen_grid_aligned() {
+        // Coordinates on multiples of the bucket width sit on bucket edges, where computing
+        // the bucket start and the bucket of a coordinate can round differently
+        for width in [0.1, 0.3, 0.7] {
+            let peaks = PeakSet::new(
+                (1..5000)
+                    .map(|k| CentroidPeak::new(k as f64 * width, 1.0, 0))
+                    .collect(),
+            );
+            let frozen = FrozenPeakSet::with_bucket_width(peaks.clone(), width);
+            for p in peaks.iter() {
+                assert_eq!(
+                    frozen.search_by(p.mz),
+                    Ok(p.index as usize),
+                    "{} with bucket width {width}",
+                    p.mz
+                );
+                let between = p.mz + width / 2.0;
+                assert_eq!(frozen.search_by(between), Err(p.index as usize + 1));
+            }
+        }
+        assert_eq!(
+            FrozenPeakSet::new(PeakSet::new(vec![CentroidPeak::new(1.0, 1.0, 0)])).search_by(1e300),
+            Err(1)
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Find the spans overlapping a coordinate range in sub-linear time.
+//!
+//! Checking every span with [`Span1D::overlaps_span`] is a linear scan, which becomes the
+//! bottleneck when many queries are made against many spans, e.g. matching features by
+//! their time interval. An [`IntervalTree`] sorts the spans by their start and stores them
+//! in a flat array treated as an implicit balanced binary tree, where each node also
+//! records the largest end coordinate beneath it so whole subtrees can be skipped.
+use std::marker::PhantomData;
+
+use crate::coordinate::Span1D;
+
+/// A static collection of [`Span1D`] items supporting overlap and containment queries
+#[derive(Debug, Clone)]
+pub struct IntervalTree<C, T: Span1D<C>> {
+    items: Vec<T>,
+    starts: Vec<f64>,
+    ends: Vec<f64>,
+    /// The largest end of the subtree rooted at each position
+    max_ends: Vec<f64>,
+    _c: PhantomData<C>,
+}
+
+impl<C, T: Span1D<C>> Default for IntervalTree<C, T> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<C, T: Span1D<C>> IntervalTree<C, T> {
+    /// Build a tree over `items`
+    pub fn new(mut items: Vec<T>) -> Self {
+        items.sort_by(|a, b| a.start().total_cmp(&b.start()));
+        let starts: Vec<f64> = items.iter().map(|i| i.start()).collect();
+        let ends: Vec<f64> = items.iter().map(|i| i.end()).collect();
+        let mut this = Self {
+            max_ends: vec![f64::NEG_INFINITY; items.len()],
+            items,
+            starts,
+            ends,
+            _c: PhantomData,
+        };
+        this.fill_max_ends(0, this.len());
+        this
+    }
+
+    fn fill_max_ends(&mut self, low: usize, high: usize) -> f64 {
+        if low >= high {
+            return f64::NEG_INFINITY;
+        }
+        let mid = low + (high - low) / 2;
+        let max_end = self.ends[mid]
+            .max(self.fill_max_ends(low, mid))
+            .max(self.fill_max_ends(mid + 1, high));
+        self.max_ends[mid] = max_end;
+        max_end
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Iterate over the items in order of their start coordinate
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+    }
+
+    fn visit(&self, start: f64, end: f64, low: usize, high: usize, out: &mut Vec<usize>) {
+        if low >= high {
+            return;
+        }
+        let mid = low + (high - low) / 2;
+        if self.max_ends[mid] < start {
+            return;
+        }
+        self.visit(start, end, low, mid, out);
+        if self.starts[mid] <= end {
+            if self.ends[mid] >= start {
+                out.push(mid);
+            }
+            self.visit(start, end, mid + 1, high, out);
+        }
+    }
+
+    /// The items sharing any coordinate with `start..=end`, in order of their start
+    pub fn overlaps_range(&self, start: f64, end: f64) -> Vec<&T> {
+        let mut positions = Vec::new();
+        self.visit(start, end, 0, self.len(), &mut positions);
+        positions.into_iter().map(|i| &self.items[i]).collect()
+    }
+
+    /// The items sharing any coordinate with `span`, in order of their start
+    pub fn overlaps<S: Span1D<C> + ?Sized>(&self, span: &S) -> Vec<&T> {
+        self.overlaps_range(span.start(), span.end())
+    }
+
+    /// The items whose span includes `point`, in order of their start
+    pub fn contains(&self, point: f64) -> Vec<&T> {
+        self.overlaps_range(point, point)
+    }
+}
+
+impl<C, T: Span1D<C>> FromIterator<T> for IntervalTree<C, T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::coordinate::{CoordinateRange, Time};
+    use crate::feature::LCMSFeature;
+
+    #[test]
+    fn test_interval_tree() {
+        let ranges: Vec<CoordinateRange<Time>> = (0..200)
+            .map(|i| {
+                let start = (i * 37 % 101) as f64;
+                CoordinateRange::new(Some(start), Some(start + (i % 7) as f64))
+            })
+            .collect();
+        let tree: IntervalTree<Time, _> = ranges.iter().cloned().collect();
+        assert_eq!(tree.len(), 200);
+
+        for (start, end) in [(10.0, 12.0), (50.5, 50.6), (-5.0, 0.0), (100.0, 200.0)] {
+            let query = CoordinateRange::<Time>::new(Some(start), Some(end));
+            let expected = ranges.iter().filter(|r| r.overlaps_span(&query)).count();
+            let found = tree.overlaps(&query);
+            assert_eq!(found.len(), expected);
+            assert!(found.windows(2).all(|w| w[0].start() <= w[1].start()));
+        }
+        let expected = ranges
+            .iter()
+            .filter(|r| r.contains_coordinate(42.0))
+            .count();
+        assert_eq!(tree.contains(42.0).len(), expected);
+        assert!(tree.overlaps_range(300.0, 400.0).is_empty());
+
+        let a: LCMSFeature = [(500.0, 10.0, 1.0), (500.0, 12.0, 4.0)]
+            .into_iter()
+            .collect();
+        let b: LCMSFeature = [(600.0, 11.5, 1.0), (600.0, 14.0, 4.0)]
+            .into_iter()
+            .collect();
+        let features: IntervalTree<Time, _> = [a, b].into_iter().collect();
+        assert_eq!(features.contains(11.0).len(), 1);
+        assert_eq!(features.contains(11.75).len(), 2);
+        assert!(IntervalTree::<Time, LCMSFeature>::default()
+            .contains(1.0)
+            .is_empty());
+    }
+}
@@ -0,0 +1,6 @@
+//! Reading and writing peaks and peak lists in simple streaming formats.
+//!
+//! Full-featured spectrum file formats are out of scope for this crate, see
+//! [`mzdata`](https://crates.io/crates/mzdata) for those.
+#[cfg(feature = "jsonl")]
+pub mod jsonl;
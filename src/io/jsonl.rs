@@ -0,0 +1,189 @@
+//! Stream peaks or whole spectra as [JSON Lines](https://jsonlines.org/), one value per line.
+//!
+//! Each line is parsed independently, so only one line needs to be held in memory at a time.
+//! This makes the format suitable for logs and message queues which carry spectra
+//! incrementally and are replayed into collections later.
+//!
+//! Whether a line holds a single peak or a whole spectrum is decided by the type being read
+//! or written: a [`JsonLinesWriter`] of [`CentroidPeak`](crate::CentroidPeak) writes one peak
+//! per line, while a [`JsonLinesWriter`] of [`PeakSet`](crate::PeakSet) writes one spectrum per line.
+use std::io::{self, prelude::*};
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::coordinate::IndexedCoordinate;
+use crate::peak_set::PeakSetVec;
+
+/// Write values of type `T` to a [`Write`] stream, one JSON document per line
+#[derive(Debug)]
+pub struct JsonLinesWriter<W: Write, T: Serialize> {
+    writer: W,
+    _t: PhantomData<T>,
+}
+
+impl<W: Write, T: Serialize> JsonLinesWriter<W, T> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            _t: PhantomData,
+        }
+    }
+
+    /// Write `value` as a single line
+    pub fn write(&mut self, value: &T) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, value)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Write each value of `values` as its own line, returning the number of lines written
+    pub fn write_all<'a, I: IntoIterator<Item = &'a T>>(&mut self, values: I) -> io::Result<usize>
+    where
+        T: 'a,
+    {
+        let mut n = 0;
+        for value in values {
+            self.write(value)?;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Read values of type `T` from a [`BufRead`] stream holding one JSON document per line.
+///
+/// Blank lines are skipped. A line which fails to parse produces an [`io::Error`] of kind
+/// [`io::ErrorKind::InvalidData`] naming the line number, after which reading may continue.
+#[derive(Debug)]
+pub struct JsonLinesReader<R: BufRead, T: DeserializeOwned> {
+    reader: R,
+    buffer: String,
+    line_number: usize,
+    _t: PhantomData<T>,
+}
+
+impl<R: BufRead, T: DeserializeOwned> JsonLinesReader<R, T> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: String::new(),
+            line_number: 0,
+            _t: PhantomData,
+        }
+    }
+
+    /// The number of lines consumed so far
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Read the next value, or `None` if the stream is exhausted
+    pub fn read_next(&mut self) -> Option<io::Result<T>> {
+        loop {
+            self.buffer.clear();
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            self.line_number += 1;
+            let line = self.buffer.trim();
+            if line.is_empty() {
+                continue;
+            }
+            return Some(serde_json::from_str(line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Failed to parse line {}: {}", self.line_number, e),
+                )
+            }));
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead, T: DeserializeOwned> Iterator for JsonLinesReader<R, T> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next()
+    }
+}
+
+/// Write every peak in `peaks` to `writer`, one peak per line
+pub fn write_peaks<W: Write, P: IndexedCoordinate<C> + Serialize, C>(
+    writer: W,
+    peaks: &PeakSetVec<P, C>,
+) -> io::Result<usize> {
+    let mut writer = JsonLinesWriter::new(writer);
+    let n = writer.write_all(peaks.iter())?;
+    writer.flush()?;
+    Ok(n)
+}
+
+/// Read peaks written one per line from `reader` into a sorted [`PeakSetVec`]
+pub fn read_peaks<R: BufRead, P: IndexedCoordinate<C> + DeserializeOwned, C>(
+    reader: R,
+) -> io::Result<PeakSetVec<P, C>> {
+    let peaks = JsonLinesReader::new(reader).collect::<io::Result<Vec<P>>>()?;
+    Ok(PeakSetVec::new(peaks))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_data, CentroidPeak, PeakSet};
+
+    #[test]
+    fn test_peaks_per_line() -> io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let mut buffer = Vec::new();
+        assert_eq!(write_peaks(&mut buffer, &peaks)?, peaks.len());
+        assert_eq!(buffer.iter().filter(|b| **b == b'\n').count(), peaks.len());
+
+        let dup: PeakSet = read_peaks(io::Cursor::new(buffer))?;
+        assert_eq!(dup.len(), peaks.len());
+        assert_eq!(dup[10], peaks[10]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_spectra_per_line() -> io::Result<()> {
+        let spectra = [
+            PeakSet::new(vec![CentroidPeak::new(100.0, 5.0, 0)]),
+            PeakSet::new(vec![
+                CentroidPeak::new(200.0, 5.0, 0),
+                CentroidPeak::new(201.0, 3.0, 1),
+            ]),
+        ];
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        writer.write_all(spectra.iter())?;
+        let mut buffer = writer.into_inner();
+        buffer.extend_from_slice(b"\nnot json\n");
+
+        let mut reader: JsonLinesReader<_, PeakSet> = JsonLinesReader::new(io::Cursor::new(buffer));
+        assert_eq!(reader.next().unwrap()?.len(), 1);
+        assert_eq!(reader.next().unwrap()?.len(), 2);
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(reader.line_number(), 4);
+        assert!(reader.next().is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+//! Theoretical isotopic envelopes to match against observed peaks.
+//!
+//! Matching code which looks for a species' isotopic peaks needs to know where those peaks
+//! should fall. [`IsotopicEnvelopeBuilder`] produces a [`TheoreticalPattern`] for a neutral
+//! mass and charge, with peaks spaced by [`NEUTRON_SHIFT`]` / |z|` and relative abundances
+//! from a simple model. It is not a substitute for a full isotope calculator working from
+//! an elemental composition, but it needs nothing beyond the mass.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::coordinate::{ChargeCarrier, CoordinateTransform, MassToMZ};
+use crate::mass_error::Tolerance;
+use crate::panel::{MZTargetPanel, Target};
+use crate::peak::MZPoint;
+use crate::simulate::{averagine_abundance, NEUTRON_SHIFT};
+
+/// The number of isotopic peaks in an envelope when not otherwise specified
+pub const DEFAULT_ENVELOPE_LENGTH: usize = 4;
+
+/// How to assign relative abundances to the peaks of an envelope
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EnvelopeShape {
+    /// The abundances of a peptide-like molecule, approximated by a Poisson distribution
+    /// whose mean grows linearly with mass
+    #[default]
+    Averagine,
+    /// Every peak is equally abundant, for when only the positions matter
+    Uniform,
+}
+
+/// The expected isotopic peaks of a species, with intensities summing to one
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TheoreticalPattern {
+    pub neutral_mass: f64,
+    pub charge: i32,
+    /// The isotopic peaks in increasing m/z order, starting with the monoisotopic peak
+    pub peaks: Vec<MZPoint>,
+}
+
+impl TheoreticalPattern {
+    pub fn len(&self) -> usize {
+        self.peaks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peaks.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, MZPoint> {
+        self.peaks.iter()
+    }
+
+    pub fn monoisotopic_mz(&self) -> Option<f64> {
+        self.peaks.first().map(|p| p.mz)
+    }
+
+    /// The most abundant peak of the pattern
+    pub fn base_peak(&self) -> Option<&MZPoint> {
+        self.peaks
+            .iter()
+            .max_by(|a, b| a.intensity.total_cmp(&b.intensity))
+    }
+
+    /// A copy of this pattern with intensities scaled to sum to `total_intensity`
+    pub fn scaled(&self, total_intensity: f32) -> Self {
+        let mut dup = self.clone();
+        for peak in dup.peaks.iter_mut() {
+            peak.intensity *= total_intensity;
+        }
+        dup
+    }
+
+    /// Build a [`MZTargetPanel`] with one target per isotopic peak, named `"M+<k>"` for the
+    /// `k`th peak, each to be matched within `tolerance`
+    pub fn to_targets(&self, tolerance: Tolerance) -> MZTargetPanel {
+        self.peaks
+            .iter()
+            .enumerate()
+            .map(|(k, p)| Target::new(format!("M+{k}"), p.mz, tolerance))
+            .collect()
+    }
+}
+
+/// Build [`TheoreticalPattern`]s of a fixed length and shape
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IsotopicEnvelopeBuilder {
+    /// The number of isotopic peaks in each pattern
+    pub length: usize,
+    pub shape: EnvelopeShape,
+    /// The ion carrying each unit of charge, a proton by default
+    pub carrier: ChargeCarrier,
+}
+
+impl Default for IsotopicEnvelopeBuilder {
+    fn default() -> Self {
+        Self::new(DEFAULT_ENVELOPE_LENGTH)
+    }
+}
+
+impl IsotopicEnvelopeBuilder {
+    pub fn new(length: usize) -> Self {
+        Self {
+            length,
+            shape: EnvelopeShape::default(),
+            carrier: ChargeCarrier::default(),
+        }
+    }
+
+    pub fn with_shape(mut self, shape: EnvelopeShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn with_carrier(mut self, carrier: ChargeCarrier) -> Self {
+        self.carrier = carrier;
+        self
+    }
+
+    /// Build the pattern of a species of `neutral_mass` observed at `charge`. A negative
+    /// charge is taken to mean the loss of charge carriers.
+    ///
+    /// # Panics
+    /// If `charge` is zero
+    pub fn build(&self, neutral_mass: f64, charge: i32) -> TheoreticalPattern {
+        assert!(charge != 0, "Cannot place an isotopic envelope at charge 0");
+        let z = charge as f64;
+        let monoisotopic_mz = MassToMZ::new(charge)
+            .with_carrier(self.carrier)
+            .transform(neutral_mass);
+        let spacing = NEUTRON_SHIFT / z.abs();
+        let abundances: Vec<f64> = (0..self.length)
+            .map(|k| match self.shape {
+                EnvelopeShape::Averagine => averagine_abundance(neutral_mass, k),
+                EnvelopeShape::Uniform => 1.0,
+            })
+            .collect();
+        let total: f64 = abundances.iter().sum();
+        let peaks = abundances
+            .into_iter()
+            .enumerate()
+            .map(|(k, a)| {
+                MZPoint::new(
+                    monoisotopic_mz + k as f64 * spacing,
+                    if total > 0.0 { (a / total) as f32 } else { 0.0 },
+                )
+            })
+            .collect();
+        TheoreticalPattern {
+            neutral_mass,
+            charge,
+            peaks,
+        }
+    }
+
+    /// Build the pattern of a species of `neutral_mass` at every charge in `charges`
+    pub fn build_charge_series(
+        &self,
+        neutral_mass: f64,
+        charges: impl IntoIterator<Item = i32>,
+    ) -> Vec<TheoreticalPattern> {
+        charges
+            .into_iter()
+            .map(|z| self.build(neutral_mass, z))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CentroidPeak, PeakSet};
+
+    #[test]
+    fn test_build_envelope() {
+        let builder = IsotopicEnvelopeBuilder::new(5);
+        let pattern = builder.build(1570.6774, 2);
+        assert_eq!(pattern.len(), 5);
+        assert!((pattern.monoisotopic_mz().unwrap() - 786.346).abs() < 1e-3);
+        assert!((pattern.peaks[1].mz - pattern.peaks[0].mz - NEUTRON_SHIFT / 2.0).abs() < 1e-9);
+        let total: f32 = pattern.iter().map(|p| p.intensity).sum();
+        assert!((total - 1.0).abs() < 1e-6);
+        assert_eq!(pattern.base_peak().unwrap().mz, pattern.peaks[0].mz);
+
+        let negative = builder.build(1570.6774, -2);
+        assert!((negative.monoisotopic_mz().unwrap() - 784.331).abs() < 1e-3);
+        let sodiated = builder
+            .with_carrier(ChargeCarrier::Sodium)
+            .build(1570.6774, 2);
+        let expected = 1570.6774 / 2.0 + ChargeCarrier::Sodium.mass();
+        assert!((sodiated.monoisotopic_mz().unwrap() - expected).abs() < 1e-9);
+
+        let uniform = builder
+            .with_shape(EnvelopeShape::Uniform)
+            .build(1570.6774, 2)
+            .scaled(100.0);
+        assert!(uniform.iter().all(|p| (p.intensity - 20.0).abs() < 1e-4));
+
+        let peaks: PeakSet = pattern
+            .scaled(1e4)
+            .iter()
+            .take(3)
+            .map(|p| CentroidPeak::new(p.mz, p.intensity, 0))
+            .collect();
+        let matches = pattern.to_targets(Tolerance::PPM(5.0)).match_panel(&peaks);
+        assert!(matches.get("M+2").unwrap().nearest.is_some());
+        assert!(matches.get("M+3").unwrap().nearest.is_none());
+
+        assert_eq!(builder.build_charge_series(10000.0, 8..=12).len(), 5);
+    }
+}
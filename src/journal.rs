@@ -0,0 +1,273 @@
+//! Record the processing applied to a peak list so it can be audited and repeated.
+//!
+//! A [`JournaledPeakSet`] wraps a [`PeakSetVec`] and performs each processing step
+//! through a method which also appends a [`ProcessingEvent`] describing the step and its
+//! parameters to a [`MutationJournal`]. The journal is plain data, so it can be serialized
+//! alongside results as provenance, and [`MutationJournal::replay_on`] re-applies the same
+//! steps to another peak list.
+//!
+//! Journaling is opt-in: a bare [`PeakSetVec`] records nothing.
+use std::ops::Deref;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::baseline::BaselineMethod;
+use crate::coordinate::{CoordinateLikeMut, IndexedCoordinate};
+use crate::mass_error::Tolerance;
+use crate::peak::IntensityMeasurementMut;
+use crate::peak_set::{MergeStrategy, PeakCollectionMut, PeakSetVec};
+
+/// A single processing step and its parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ProcessingEvent {
+    /// Remove peaks with an intensity below `threshold`
+    FilterIntensity { threshold: f32 },
+    /// Remove peaks with a coordinate outside of `start..=end`
+    FilterCoordinate { start: f64, end: f64 },
+    /// Scale intensities so that the most intense peak has intensity `scale`
+    NormalizeToMax { scale: f32 },
+    /// Scale intensities so that they sum to `total`
+    NormalizeToTotal { total: f32 },
+    /// See [`PeakSetVec::keep_top_tic_fraction`]
+    KeepTopTicFraction { fraction: f32 },
+    /// See [`PeakSetVec::subtract_baseline`]
+    SubtractBaseline {
+        window_width: f64,
+        method: BaselineMethod,
+    },
+    /// Combine runs of neighboring peaks within `tolerance` of each other into a single
+    /// peak at their intensity-weighted mean coordinate with their summed intensity
+    MergeWithin { tolerance: Tolerance },
+}
+
+fn scale_intensities<P: IntensityMeasurementMut>(peaks: &mut [P], factor: f32) {
+    for p in peaks.iter_mut() {
+        *p.intensity_mut() *= factor;
+    }
+}
+
+impl ProcessingEvent {
+    /// Apply this step to `peaks`, returning the number of peaks removed
+    pub fn apply<P, C>(&self, peaks: &mut PeakSetVec<P, C>) -> usize
+    where
+        P: IndexedCoordinate<C> + CoordinateLikeMut<C> + IntensityMeasurementMut,
+    {
+        let n = peaks.len();
+        match *self {
+            Self::FilterIntensity { threshold } => {
+                peaks.peaks.retain(|p| p.intensity() >= threshold);
+            }
+            Self::FilterCoordinate { start, end } => {
+                peaks
+                    .peaks
+                    .retain(|p| (start..=end).contains(&p.coordinate()));
+            }
+            Self::NormalizeToMax { scale } => {
+                let max = peaks.iter().map(|p| p.intensity()).fold(0.0f32, f32::max);
+                if max > 0.0 {
+                    scale_intensities(peaks.as_mut_slice(), scale / max);
+                }
+            }
+            Self::NormalizeToTotal { total } => {
+                let sum: f32 = peaks.iter().map(|p| p.intensity()).sum();
+                if sum > 0.0 {
+                    scale_intensities(peaks.as_mut_slice(), total / sum);
+                }
+            }
+            Self::KeepTopTicFraction { fraction } => {
+                peaks.keep_top_tic_fraction(fraction);
+            }
+            Self::SubtractBaseline {
+                window_width,
+                method,
+            } => {
+                peaks.subtract_baseline(window_width, method);
+            }
+            Self::MergeWithin { tolerance } => {
+                peaks.merge_peaks_within(tolerance, MergeStrategy::WeightedSum);
+            }
+        }
+        if peaks.len() != n {
+            peaks.sort();
+        }
+        n - peaks.len()
+    }
+}
+
+/// An ordered record of [`ProcessingEvent`]s
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MutationJournal {
+    events: Vec<ProcessingEvent>,
+}
+
+impl MutationJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, event: ProcessingEvent) {
+        self.events.push(event)
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ProcessingEvent> {
+        self.events.iter()
+    }
+
+    /// Apply every recorded step to `peaks` in order, returning the total number of
+    /// peaks removed
+    pub fn replay_on<P, C>(&self, peaks: &mut PeakSetVec<P, C>) -> usize
+    where
+        P: IndexedCoordinate<C> + CoordinateLikeMut<C> + IntensityMeasurementMut,
+    {
+        self.events.iter().map(|event| event.apply(peaks)).sum()
+    }
+}
+
+impl FromIterator<ProcessingEvent> for MutationJournal {
+    fn from_iter<T: IntoIterator<Item = ProcessingEvent>>(iter: T) -> Self {
+        Self {
+            events: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// A [`PeakSetVec`] which records every processing step applied through it in a
+/// [`MutationJournal`]
+#[derive(Debug, Clone, Default)]
+pub struct JournaledPeakSet<P: IndexedCoordinate<C>, C> {
+    peaks: PeakSetVec<P, C>,
+    journal: MutationJournal,
+}
+
+impl<P, C> JournaledPeakSet<P, C>
+where
+    P: IndexedCoordinate<C> + CoordinateLikeMut<C> + IntensityMeasurementMut,
+{
+    pub fn new(peaks: PeakSetVec<P, C>) -> Self {
+        Self {
+            peaks,
+            journal: MutationJournal::new(),
+        }
+    }
+
+    pub fn journal(&self) -> &MutationJournal {
+        &self.journal
+    }
+
+    pub fn into_parts(self) -> (PeakSetVec<P, C>, MutationJournal) {
+        (self.peaks, self.journal)
+    }
+
+    /// Apply `event` to the peaks and record it, returning the number of peaks removed
+    pub fn apply(&mut self, event: ProcessingEvent) -> usize {
+        let removed = event.apply(&mut self.peaks);
+        self.journal.push(event);
+        removed
+    }
+
+    pub fn filter_intensity(&mut self, threshold: f32) -> usize {
+        self.apply(ProcessingEvent::FilterIntensity { threshold })
+    }
+
+    pub fn filter_coordinate(&mut self, start: f64, end: f64) -> usize {
+        self.apply(ProcessingEvent::FilterCoordinate { start, end })
+    }
+
+    pub fn normalize_to_max(&mut self, scale: f32) {
+        self.apply(ProcessingEvent::NormalizeToMax { scale });
+    }
+
+    pub fn normalize_to_total(&mut self, total: f32) {
+        self.apply(ProcessingEvent::NormalizeToTotal { total });
+    }
+
+    pub fn keep_top_tic_fraction(&mut self, fraction: f32) -> usize {
+        self.apply(ProcessingEvent::KeepTopTicFraction { fraction })
+    }
+
+    /// # Panics
+    /// If `window_width` is negative or NaN, see
+    /// [`estimate_baseline`](crate::baseline::estimate_baseline). The event is not recorded.
+    pub fn subtract_baseline(&mut self, window_width: f64, method: BaselineMethod) {
+        self.apply(ProcessingEvent::SubtractBaseline {
+            window_width,
+            method,
+        });
+    }
+
+    pub fn merge_within(&mut self, tolerance: Tolerance) -> usize {
+        self.apply(ProcessingEvent::MergeWithin { tolerance })
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> Deref for JournaledPeakSet<P, C> {
+    type Target = PeakSetVec<P, C>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.peaks
+    }
+}
+
+impl<P, C> PeakSetVec<P, C>
+where
+    P: IndexedCoordinate<C> + CoordinateLikeMut<C> + IntensityMeasurementMut,
+{
+    /// Start recording the processing applied to this collection
+    pub fn journaled(self) -> JournaledPeakSet<P, C> {
+        JournaledPeakSet::new(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_data;
+
+    #[test]
+    fn test_replay() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let mut journaled = peaks.clone().journaled();
+        journaled.filter_coordinate(300.0, 1500.0);
+        journaled.merge_within(Tolerance::Da(0.5));
+        journaled.normalize_to_max(100.0);
+        let removed = journaled.filter_intensity(1.0);
+        assert!(removed > 0);
+        assert_eq!(journaled.journal().len(), 4);
+        assert!(journaled.iter().all(|p| p.intensity <= 100.0 + 1e-3));
+
+        let (processed, journal) = journaled.into_parts();
+        let mut replayed = peaks.clone();
+        let total_removed = journal.replay_on(&mut replayed);
+        assert_eq!(replayed, processed);
+        assert_eq!(total_removed, peaks.len() - processed.len());
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() -> serde_json::Result<()> {
+        let journal: MutationJournal = [
+            ProcessingEvent::NormalizeToTotal { total: 1.0 },
+            ProcessingEvent::MergeWithin {
+                tolerance: Tolerance::PPM(10.0),
+            },
+        ]
+        .into_iter()
+        .collect();
+        let text = serde_json::to_string(&journal)?;
+        let dup: MutationJournal = serde_json::from_str(&text)?;
+        assert_eq!(dup, journal);
+        Ok(())
+    }
+}
@@ -2,7 +2,23 @@
 //! in a mass spectrum.
 //!
 //! It's meant to be used as a building block for other tools and does not provide
-//! any I/O machinery for peak lists. For that, consider [`mzdata`](https://crates.io/crates/mzdata)
+//! any I/O machinery for spectrum file formats beyond the simple streaming formats in `io`.
+//! For that, consider [`mzdata`](https://crates.io/crates/mzdata)
+//!
+//! # Features
+//!
+//! The coordinate systems, peak types and peak collections are always available. Larger
+//! subsystems can be left out to reduce compile time and binary size:
+//!
+//! - `algorithms`: signal processing and analysis, e.g. [`baseline`], [`binning`],
+//!   [`consensus`], [`correlate`], [`drift`], [`feature_mapping`], [`isotope`], [`journal`],
+//!   [`matching`], [`quality`], [`shape`], [`similarity`], [`simulate`] and [`spacing`]
+//! - `indexes`: alternative peak list storage and lookup, e.g. [`arena`], [`cache`],
+//!   [`frozen`], [`interval_tree`], [`peak_index`], [`sort_cache`] and [`storage`]
+//! - `io`: array codecs in [`codec`] and streaming formats in [`io`]
+//!
+//! All three are enabled by default. The `parallel` feature adds [`parallel`], which answers
+//! large batches of queries across threads.
 //!
 //! ```rust
 //! use mzpeaks::{CentroidPeak, PeakSet, PeakCollection, Tolerance};
@@ -23,28 +39,103 @@
 //! assert!((peak.mz - 204.07).abs() < 1e-6);
 //!```
 
+#[cfg(feature = "indexes")]
+pub mod arena;
+#[cfg(feature = "algorithms")]
+pub mod baseline;
+#[cfg(feature = "algorithms")]
+pub mod binning;
+#[cfg(feature = "indexes")]
+pub mod cache;
+pub mod charge;
+#[cfg(feature = "io")]
+pub mod codec;
+pub mod columnar;
+#[cfg(feature = "algorithms")]
+pub mod consensus;
 pub mod coordinate;
+#[cfg(feature = "algorithms")]
+pub mod correlate;
+pub mod delta;
+#[cfg(feature = "algorithms")]
+pub mod drift;
+pub mod error;
 #[macro_use]
 pub mod macros;
 pub mod feature;
+pub mod feature_key;
 pub mod feature_map;
+#[cfg(feature = "algorithms")]
+pub mod feature_mapping;
+#[cfg(feature = "indexes")]
+pub mod frozen;
+#[cfg(feature = "indexes")]
+pub mod interval_tree;
+#[cfg(feature = "io")]
+pub mod io;
+#[cfg(feature = "algorithms")]
+pub mod isotope;
+#[cfg(feature = "algorithms")]
+pub mod journal;
 pub mod mass_error;
+#[cfg(feature = "algorithms")]
+pub mod matching;
+pub mod metadata;
+pub mod mobility;
+pub mod overlay;
+pub mod panel;
+#[cfg(feature = "parallel")]
+pub mod parallel;
 pub mod peak;
+#[cfg(feature = "indexes")]
 pub mod peak_index;
 pub mod peak_set;
+pub mod plot;
 pub mod prelude;
+pub mod profile;
+#[cfg(feature = "algorithms")]
+pub mod quality;
+pub mod queryable;
+pub mod search;
+#[cfg(feature = "algorithms")]
+pub mod shape;
+#[cfg(feature = "algorithms")]
+pub mod similarity;
+#[cfg(feature = "algorithms")]
+pub mod simulate;
+#[cfg(feature = "indexes")]
+pub mod sort_cache;
+#[cfg(feature = "algorithms")]
+pub mod spacing;
+#[cfg(feature = "indexes")]
+pub mod storage;
+pub mod summary;
+pub mod table;
 #[cfg(test)]
 mod test_data;
+#[cfg(feature = "test_support")]
+pub mod test_support;
 
 pub use crate::coordinate::{
-    CoordinateLike, CoordinateLikeMut, CoordinateRange, CoordinateRangeParseError, IndexType,
-    IndexedCoordinate, IonMobility, MZLocated, Mass, MassLocated, Time, MZ,
+    ChargeCarrier, CoordinateLike, CoordinateLikeMut, CoordinateRange, CoordinateRangeParseError,
+    CoordinateRangeTiles, CoordinateTransform, IndexType, IndexedCoordinate, IonMobility,
+    IsolationWindow, MZLocated, MZToMass, Mass, MassLocated, MassToMZ, Reindex, Span1D,
+    TaperWindow, Time, WindowScheme, CCS, MZ,
+};
+pub use crate::error::MzPeaksError;
+pub use crate::mass_error::{
+    CascadedTolerance, MassDisplay, MassUnit, TieredMatch, Tolerance, ToleranceParsingError,
 };
-pub use crate::mass_error::{Tolerance, ToleranceParsingError};
 pub use crate::peak::{
-    CentroidLike, CentroidPeak, DeconvolutedCentroidLike, DeconvolutedPeak, IntensityMeasurement,
-    IntensityMeasurementMut, KnownCharge, KnownChargeMut,
+    CentroidLike, CentroidPeak, CentroidPeakF32, ChargeRange, DeconvolutedCentroidLike,
+    DeconvolutedPeak, FittedPeak, IntensityMeasurement, IntensityMeasurementMut,
+    IonMobilityAwareCentroidPeak, KnownCharge, KnownChargeMut, KnownChargeRange,
+    MultiChargedCentroid, SaturableIntensity, SaturableIntensityMut,
 };
+#[cfg(feature = "serde")]
+pub use crate::peak_set::PeakSetSeed;
 pub use crate::peak_set::{
-    DeconvolutedPeakSet, MZPeakSetType, MassPeakSetType, PeakCollection, PeakSet,
+    ContentHashPrecision, DeconvolutedPeakSet, MZPeakSetType, MassPeakSetType, MergeStrategy,
+    NanPolicy, NoPeakInWindow, PeakCollection, PeakSet, PeakSetBuilder, PrecisionLossReport,
+    ReorderedView, SortValidation, TryConvertCollect,
 };
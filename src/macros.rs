@@ -1,5 +1,6 @@
-/// A set of code generation macros to make a type behave as [`CentroidLike`](crate::CentroidLike)
-/// or [`DeconvolutedCentroidLike`](crate::DeconvolutedCentroidLike).
+//! A set of code generation macros to make a type behave as [`CentroidLike`](crate::CentroidLike)
+//! or [`DeconvolutedCentroidLike`](crate::DeconvolutedCentroidLike), and to define new
+//! coordinate systems with [`define_coordinate_system`](crate::define_coordinate_system).
 
 #[macro_export]
 macro_rules! implement_mz_coord {
@@ -7,7 +8,7 @@ macro_rules! implement_mz_coord {
         impl std::hash::Hash for $t {
             #[inline]
             fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-                let mz_val: i64 = self.coordinate().round() as i64;
+                let mz_val: i64 = self.mz.round() as i64;
                 mz_val.hash(state);
             }
         }
@@ -98,7 +99,7 @@ macro_rules! implement_mass_coord {
             fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
                 match self.neutral_mass.total_cmp(&other.coordinate()) {
                     cmp::Ordering::Equal => self.charge.partial_cmp(&other.charge()),
-                    x => Some(x)
+                    x => Some(x),
                 }
             }
         }
@@ -137,7 +138,6 @@ macro_rules! implement_mass_coord {
                 &mut self.intensity
             }
         }
-
     };
 }
 
@@ -155,7 +155,6 @@ macro_rules! implement_centroid_conversion {
                 Self {
                     mz: peak.coordinate(),
                     intensity: peak.intensity(),
-                    ..Self::default()
                 }
             }
         }
@@ -321,3 +320,74 @@ macro_rules! implement_centroidlike {
         $crate::implement_centroidlike_inner!($t, false, true);
     };
 }
+
+#[macro_export]
+/// Define a new coordinate system: a zero-sized marker type which implements
+/// [`CoordinateSystem`](crate::coordinate::CoordinateSystem), and a `*Located` trait with a
+/// named accessor for its coordinate, implemented for every
+/// [`CoordinateLike`](crate::CoordinateLike) type in that system.
+///
+/// Attributes, including doc comments and any extra derives such as serde's, are applied
+/// to the marker type, and an optional `unit` is recorded as
+/// [`CoordinateSystem::UNIT`](crate::coordinate::CoordinateSystem::UNIT).
+///
+/// ```rust
+/// use mzpeaks::{define_coordinate_system, CoordinateLike};
+///
+/// define_coordinate_system!(
+///     /// The fraction of a fractionated sample an observation was collected in
+///     pub FractionNumber,
+///     FractionLocated::fraction_number,
+///     unit = "fraction"
+/// );
+///
+/// #[derive(PartialEq, PartialOrd)]
+/// struct Spot(f64);
+///
+/// impl CoordinateLike<FractionNumber> for Spot {
+///     fn coordinate(&self) -> f64 {
+///         self.0
+///     }
+/// }
+///
+/// assert_eq!(Spot(3.0).fraction_number(), 3.0);
+/// ```
+macro_rules! define_coordinate_system {
+    (
+        $(#[$meta:meta])*
+        $vis:vis $name:ident,
+        $located:ident :: $accessor:ident
+        $(, unit = $unit:literal)?
+        $(,)?
+    ) => {
+        $(#[$meta])*
+        #[derive(Default, Debug, Clone, Copy, PartialEq, PartialOrd)]
+        $vis struct $name();
+
+        impl $name {
+            #[doc = concat!(
+                "Access the coordinate of the type in the [`",
+                stringify!($name),
+                "`] system"
+            )]
+            #[inline]
+            pub fn coordinate<T: $crate::CoordinateLike<$name>>(inst: &T) -> f64 {
+                $crate::CoordinateLike::<$name>::coordinate(inst)
+            }
+        }
+
+        impl $crate::coordinate::CoordinateSystem for $name {
+            $(const UNIT: Option<&'static str> = Some($unit);)?
+        }
+
+        #[doc = concat!("A named coordinate system membership for [`", stringify!($name), "`]")]
+        $vis trait $located: $crate::CoordinateLike<$name> {
+            #[inline]
+            fn $accessor(&self) -> f64 {
+                $crate::CoordinateLike::<$name>::coordinate(self)
+            }
+        }
+
+        impl<T: $crate::CoordinateLike<$name>> $located for T {}
+    };
+}
@@ -1,9 +1,13 @@
 //! Measure accuracy error tolerance ranges
-use std::{ops::{self, RangeInclusive}, fmt::Display, error::Error, str::FromStr};
+use std::{
+    error::Error,
+    fmt::Display,
+    ops::{self, RangeInclusive},
+    str::FromStr,
+};
 
 #[cfg(feature = "serde")]
-use serde::{Serialize, Deserialize};
-
+use serde::{Deserialize, Serialize};
 
 /// A failure to parse a mass error tolerance quantity from a string
 #[derive(Debug, PartialEq, Eq)]
@@ -11,7 +15,9 @@ pub enum ToleranceParsingError {
     /// The unit isn't empty, but not recognized
     UnknownUnit,
     /// The magnitude of the error tolerated couldn't be determined
-    InvalidMagnitude
+    InvalidMagnitude,
+    /// A hybrid tolerance didn't pair one PPM tolerance with one absolute tolerance
+    InvalidHybrid,
 }
 
 impl Display for ToleranceParsingError {
@@ -22,24 +28,27 @@ impl Display for ToleranceParsingError {
 
 impl Error for ToleranceParsingError {}
 
-impl FromStr for Tolerance {
-    type Err = ToleranceParsingError;
-
-    /// Parse a string of the form "<magnitude:f64><unit:da|ppm?>"
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Tolerance {
+    fn parse_single(s: &str) -> Result<Self, ToleranceParsingError> {
+        let s = s.trim();
         let n = s.len();
         if n <= 2 {
-            return Err(ToleranceParsingError::InvalidMagnitude)
+            return Err(ToleranceParsingError::InvalidMagnitude);
         }
-        let s = s.to_lowercase();
         if s.ends_with("da") {
-            if let Ok(magnitude) = s[0..n-2].parse::<f64>() {
+            if let Ok(magnitude) = s[0..n - 2].trim_end().parse::<f64>() {
                 Ok(Self::Da(magnitude))
             } else {
                 Err(ToleranceParsingError::InvalidMagnitude)
             }
+        } else if s.ends_with("mmu") {
+            if let Ok(magnitude) = s[0..n - 3].trim_end().parse::<f64>() {
+                Ok(Self::Milli(magnitude))
+            } else {
+                Err(ToleranceParsingError::InvalidMagnitude)
+            }
         } else if s.ends_with("ppm") {
-            if let Ok(magnitude) = s[0..n-3].parse::<f64>() {
+            if let Ok(magnitude) = s[0..n - 3].trim_end().parse::<f64>() {
                 Ok(Self::PPM(magnitude))
             } else {
                 Err(ToleranceParsingError::InvalidMagnitude)
@@ -50,25 +59,77 @@ impl FromStr for Tolerance {
     }
 }
 
+impl FromStr for Tolerance {
+    type Err = ToleranceParsingError;
+
+    /// Parse a string of the form "<magnitude:f64><unit:da|mmu|ppm?>", case-insensitively
+    /// and optionally with whitespace between the magnitude and unit, or a hybrid tolerance
+    /// of the form "<ppm tolerance>|<da or mmu tolerance>" in either order
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        match s.split_once('|') {
+            None => Self::parse_single(&s),
+            Some((first, second)) => {
+                match (Self::parse_single(first)?, Self::parse_single(second)?) {
+                    (Self::PPM(ppm), Self::Da(da)) | (Self::Da(da), Self::PPM(ppm)) => {
+                        Ok(Self::Hybrid(ppm, da))
+                    }
+                    (Self::PPM(ppm), Self::Milli(mmu)) | (Self::Milli(mmu), Self::PPM(ppm)) => {
+                        Ok(Self::Hybrid(ppm, mmu / 1e3))
+                    }
+                    _ => Err(ToleranceParsingError::InvalidHybrid),
+                }
+            }
+        }
+    }
+}
 
+/// A mass error tolerance.
+///
+/// With the `serde` feature, a tolerance is (de)serialized in the same string form that
+/// [`Display`] writes and [`FromStr`] reads, e.g. `"10PPM"` or `"10PPM|0.01Da"`.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "String", try_from = "String")
+)]
 pub enum Tolerance {
     PPM(f64),
-    Da(f64)
+    Da(f64),
+    /// Milli-mass units, thousandths of a Dalton
+    Milli(f64),
+    /// A PPM tolerance and a Dalton tolerance, accepting whichever interval is wider. Errors
+    /// are measured in PPM.
+    Hybrid(f64, f64),
 }
 
-impl ToString for Tolerance {
-    fn to_string(&self) -> String {
+impl From<Tolerance> for String {
+    fn from(value: Tolerance) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for Tolerance {
+    type Error = ToleranceParsingError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Tolerance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Da(tol) => format!("{}Da", tol),
-            Self::PPM(tol) => format!("{}PPM", tol),
+            Self::Da(tol) => write!(f, "{}Da", tol),
+            Self::PPM(tol) => write!(f, "{}PPM", tol),
+            Self::Milli(tol) => write!(f, "{}mmu", tol),
+            Self::Hybrid(ppm, da) => write!(f, "{}PPM|{}Da", ppm, da),
         }
     }
 }
 
 impl Tolerance {
-
     /// The interval around `query` which is within this `Tolerance`
     /// instance's range.
     pub fn bounds(&self, query: f64) -> (f64, f64) {
@@ -77,8 +138,14 @@ impl Tolerance {
                 let width = query * *tol / 1e6;
                 (query - width, query + width)
             }
-            Tolerance::Da(tol) => {
-                (query - *tol, query + *tol)
+            Tolerance::Da(tol) => (query - *tol, query + *tol),
+            Tolerance::Milli(tol) => {
+                let width = *tol / 1e3;
+                (query - width, query + width)
+            }
+            Tolerance::Hybrid(ppm, da) => {
+                let width = (query * *ppm / 1e6).max(*da);
+                (query - width, query + width)
             }
         }
     }
@@ -86,20 +153,19 @@ impl Tolerance {
     /// Compute the error between the two masses, in the appropriate units
     pub fn call(&self, query: f64, reference: f64) -> f64 {
         match self {
-            Self::PPM(_tol) => {
-                (query - reference) / reference * 1e6
-            },
-            Self::Da(_tol) => {
-                query - reference
-            }
+            Self::PPM(_) | Self::Hybrid(_, _) => (query - reference) / reference * 1e6,
+            Self::Da(_tol) => query - reference,
+            Self::Milli(_tol) => (query - reference) * 1e3,
         }
     }
 
-    /// Return the numeric value of the error threshold in its units
+    /// Return the numeric value of the error threshold in its units, the PPM component
+    /// for [`Tolerance::Hybrid`]
     pub fn tol(&self) -> f64 {
         match self {
-            Self::PPM(tol) => *tol,
-            Self::Da(tol) => *tol
+            Self::PPM(tol) | Self::Hybrid(tol, _) => *tol,
+            Self::Da(tol) => *tol,
+            Self::Milli(tol) => *tol,
         }
     }
 
@@ -109,17 +175,38 @@ impl Tolerance {
         query >= lower_bound && query <= upper_bound
     }
 
+    /// The error of `query` relative to `reference` as a fraction of the error tolerated
+    /// around `reference`, so that `query` is within tolerance when its magnitude is at
+    /// most 1. A zero tolerance only admits an exact match, anything else being infinitely
+    /// far away.
+    pub fn normalized_error(&self, query: f64, reference: f64) -> f64 {
+        let (lower_bound, upper_bound) = self.bounds(reference);
+        let half_width = (upper_bound - lower_bound) / 2.0;
+        let error = query - reference;
+        if half_width > 0.0 {
+            error / half_width
+        } else if error == 0.0 {
+            0.0
+        } else {
+            error.signum() * f64::INFINITY
+        }
+    }
+
     /// Format the error between two masses with the appropriate units
     pub fn format_error(&self, query: f64, reference: f64) -> String {
         match self {
-            Self::PPM(_tol) => {
+            Self::PPM(_) | Self::Hybrid(_, _) => {
                 let magnitude = (query - reference) / reference * 1e6;
                 format!("{}PPM", magnitude).to_string()
-            },
+            }
             Self::Da(_tol) => {
                 let magnitude = query - reference;
                 format!("{}Da", magnitude).to_string()
             }
+            Self::Milli(_tol) => {
+                let magnitude = (query - reference) * 1e3;
+                format!("{}mmu", magnitude)
+            }
         }
     }
 
@@ -136,13 +223,310 @@ impl ops::Mul<f64> for Tolerance {
     fn mul(self, rhs: f64) -> Self::Output {
         match self {
             Self::Da(val) => Self::Da(rhs * val),
-            Self::PPM(val) => Self::PPM(rhs * val)
+            Self::PPM(val) => Self::PPM(rhs * val),
+            Self::Milli(val) => Self::Milli(rhs * val),
+            Self::Hybrid(ppm, da) => Self::Hybrid(rhs * ppm, rhs * da),
         }
     }
 }
 
+impl ops::Mul<Tolerance> for f64 {
+    type Output = Tolerance;
+
+    fn mul(self, rhs: Tolerance) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl ops::MulAssign<f64> for Tolerance {
+    fn mul_assign(&mut self, rhs: f64) {
+        *self = *self * rhs;
+    }
+}
+
+impl ops::Div<f64> for Tolerance {
+    type Output = Tolerance;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        match self {
+            Self::Da(val) => Self::Da(val / rhs),
+            Self::PPM(val) => Self::PPM(val / rhs),
+            Self::Milli(val) => Self::Milli(val / rhs),
+            Self::Hybrid(ppm, da) => Self::Hybrid(ppm / rhs, da / rhs),
+        }
+    }
+}
+
+impl ops::DivAssign<f64> for Tolerance {
+    fn div_assign(&mut self, rhs: f64) {
+        *self = *self / rhs;
+    }
+}
+
 impl From<f64> for Tolerance {
     fn from(value: f64) -> Self {
         Self::PPM(value)
     }
-}
\ No newline at end of file
+}
+
+/// A value found under one tier of a [`CascadedTolerance`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TieredMatch<T> {
+    pub value: T,
+    /// The position of the tier that matched, where `0` is the first tier tried
+    pub tier: usize,
+    /// The tolerance of the tier that matched
+    pub tolerance: Tolerance,
+}
+
+/// A sequence of tolerances to try in turn, usually from strictest to loosest, so that a
+/// search prefers a high-confidence match but falls back on a looser one. Every match
+/// reports the tier it was found under so that looser matches can be penalized.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CascadedTolerance {
+    tiers: Vec<Tolerance>,
+}
+
+impl CascadedTolerance {
+    pub fn new(tiers: Vec<Tolerance>) -> Self {
+        Self { tiers }
+    }
+
+    /// Add `tolerance` as the next tier to try
+    pub fn then(mut self, tolerance: Tolerance) -> Self {
+        self.tiers.push(tolerance);
+        self
+    }
+
+    pub fn tiers(&self) -> &[Tolerance] {
+        &self.tiers
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiers.is_empty()
+    }
+
+    /// The first tier under which `query` is within the tolerated error interval around
+    /// `reference`, or `None` if it is outside all of them
+    pub fn test(&self, query: f64, reference: f64) -> Option<usize> {
+        self.tiers.iter().position(|tol| tol.test(query, reference))
+    }
+
+    /// Call `f` with each tier's tolerance in turn until it finds something. This adapts
+    /// any search taking a [`Tolerance`], e.g.
+    /// `cascade.find(|tol| peaks.search(query, tol))`.
+    pub fn find<T, F: FnMut(Tolerance) -> Option<T>>(&self, mut f: F) -> Option<TieredMatch<T>> {
+        self.tiers.iter().enumerate().find_map(|(tier, tolerance)| {
+            f(*tolerance).map(|value| TieredMatch {
+                value,
+                tier,
+                tolerance: *tolerance,
+            })
+        })
+    }
+}
+
+impl From<Vec<Tolerance>> for CascadedTolerance {
+    fn from(value: Vec<Tolerance>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl FromIterator<Tolerance> for CascadedTolerance {
+    fn from_iter<T: IntoIterator<Item = Tolerance>>(iter: T) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+/// A unit in which to express a mass
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MassUnit {
+    #[default]
+    Da,
+    /// Thousands of Daltons, common for intact proteins
+    KDa,
+}
+
+impl MassUnit {
+    /// The number of Daltons in one of this unit
+    pub const fn scale(&self) -> f64 {
+        match self {
+            Self::Da => 1.0,
+            Self::KDa => 1e3,
+        }
+    }
+
+    /// Convert `mass` in Daltons to this unit
+    pub fn from_da(&self, mass: f64) -> f64 {
+        mass / self.scale()
+    }
+
+    /// Convert `value` in this unit to Daltons
+    pub fn to_da(&self, value: f64) -> f64 {
+        value * self.scale()
+    }
+
+    /// Wrap `mass` in Daltons so that it displays in this unit
+    pub fn display(&self, mass: f64) -> MassDisplay {
+        MassDisplay::new(mass, *self)
+    }
+}
+
+impl Display for MassUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Da => f.write_str("Da"),
+            Self::KDa => f.write_str("kDa"),
+        }
+    }
+}
+
+/// Display a mass in Daltons converted to a [`MassUnit`] with its unit suffix.
+///
+/// The precision of the formatter is respected, e.g. `format!("{:.2}", MassUnit::KDa.display(25432.1))`
+/// gives `"25.43kDa"`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MassDisplay {
+    /// The mass in Daltons
+    pub mass: f64,
+    pub unit: MassUnit,
+}
+
+impl MassDisplay {
+    pub fn new(mass: f64, unit: MassUnit) -> Self {
+        Self { mass, unit }
+    }
+}
+
+impl Display for MassDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.unit.from_da(self.mass);
+        match f.precision() {
+            Some(precision) => write!(f, "{:.*}{}", precision, value, self.unit),
+            None => write!(f, "{}{}", value, self.unit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_milli_mass_units() {
+        let tol: Tolerance = "5mmu".parse().unwrap();
+        assert_eq!(tol, Tolerance::Milli(5.0));
+        assert_eq!(tol.to_string(), "5mmu");
+        let (low, high) = tol.bounds(1000.0);
+        assert!((low - 999.995).abs() < 1e-9);
+        assert!((high - 1000.005).abs() < 1e-9);
+        assert!(tol.test(1000.004, 1000.0));
+        assert!(!tol.test(1000.006, 1000.0));
+        assert!((tol.call(1000.002, 1000.0) - 2.0).abs() < 1e-6);
+        assert!((tol.normalized_error(999.9975, 1000.0) + 0.5).abs() < 1e-6);
+        assert_eq!(Tolerance::Da(0.0).normalized_error(1.0, 1.0), 0.0);
+        assert_eq!(Tolerance::Da(0.0).normalized_error(1.5, 1.0), f64::INFINITY);
+        assert_eq!(tol * 2.0, Tolerance::Milli(10.0));
+    }
+
+    #[test]
+    fn test_tolerance_arithmetic() {
+        let tol: Tolerance = "0.02da".parse().unwrap();
+        assert_eq!(tol, Tolerance::Da(0.02));
+        assert_eq!(
+            " 10 PPM".parse::<Tolerance>().unwrap(),
+            Tolerance::PPM(10.0)
+        );
+        assert!("ppm".parse::<Tolerance>().is_err());
+
+        let mut tol = Tolerance::PPM(10.0);
+        assert_eq!(2.0 * tol, Tolerance::PPM(20.0));
+        assert_eq!(tol / 4.0, Tolerance::PPM(2.5));
+        tol *= 3.0;
+        assert_eq!(tol, Tolerance::PPM(30.0));
+        tol /= 2.0;
+        assert_eq!(tol, Tolerance::PPM(15.0));
+    }
+
+    #[test]
+    fn test_hybrid_tolerance() {
+        let tol: Tolerance = "10ppm|0.01Da".parse().unwrap();
+        assert_eq!(tol, Tolerance::Hybrid(10.0, 0.01));
+        assert_eq!(
+            "5 mmu | 10 PPM".parse::<Tolerance>().unwrap(),
+            Tolerance::Hybrid(10.0, 0.005)
+        );
+        assert_eq!(
+            "10ppm|20ppm".parse::<Tolerance>(),
+            Err(ToleranceParsingError::InvalidHybrid)
+        );
+        assert_eq!(
+            "10ppm|5 furlongs".parse::<Tolerance>(),
+            Err(ToleranceParsingError::UnknownUnit)
+        );
+
+        // The Dalton tolerance is wider at low m/z, the PPM tolerance at high m/z
+        assert!(tol.test(100.009, 100.0));
+        assert!(!tol.test(100.011, 100.0));
+        assert!(tol.test(2000.019, 2000.0));
+        assert!((tol.call(1000.01, 1000.0) - 10.0).abs() < 1e-6);
+
+        for tol in [
+            tol,
+            Tolerance::PPM(12.5),
+            Tolerance::Da(0.02),
+            Tolerance::Milli(5.0),
+        ] {
+            assert_eq!(tol.to_string().parse::<Tolerance>().unwrap(), tol);
+        }
+    }
+
+    #[test]
+    fn test_cascaded_tolerance() {
+        let cascade = CascadedTolerance::default()
+            .then(Tolerance::PPM(5.0))
+            .then(Tolerance::PPM(20.0))
+            .then(Tolerance::Da(0.1));
+        assert_eq!(cascade.len(), 3);
+        assert_eq!(cascade.test(1000.001, 1000.0), Some(0));
+        assert_eq!(cascade.test(1000.01, 1000.0), Some(1));
+        assert_eq!(cascade.test(1000.05, 1000.0), Some(2));
+        assert_eq!(cascade.test(1001.0, 1000.0), None);
+
+        let references = [500.0, 1000.01];
+        let hit = cascade
+            .find(|tol| references.iter().position(|r| tol.test(1000.0, *r)))
+            .unwrap();
+        assert_eq!(hit.value, 1);
+        assert_eq!(hit.tier, 1);
+        assert_eq!(hit.tolerance, Tolerance::PPM(20.0));
+        assert!(cascade
+            .find(|tol| tol.test(2000.0, 1000.0).then_some(()))
+            .is_none());
+        assert!(CascadedTolerance::from(vec![]).find(|_| Some(())).is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_string_form() -> serde_json::Result<()> {
+        let tol = Tolerance::Hybrid(10.0, 0.01);
+        let text = serde_json::to_string(&tol)?;
+        assert_eq!(text, "\"10PPM|0.01Da\"");
+        assert_eq!(serde_json::from_str::<Tolerance>(&text)?, tol);
+        assert!(serde_json::from_str::<Tolerance>("\"10furlongs\"").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mass_display() {
+        assert_eq!(format!("{:.2}", MassUnit::KDa.display(25432.1)), "25.43kDa");
+        assert_eq!(MassUnit::Da.display(500.5).to_string(), "500.5Da");
+        assert_eq!(MassUnit::KDa.to_da(1.5), 1500.0);
+    }
+}
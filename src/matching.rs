@@ -0,0 +1,302 @@
+//! Pair up the peaks or features of two samples and compare their intensities.
+//!
+//! Comparative quantification starts by matching each signal in a reference sample to the
+//! same signal in a second sample. [`match_peaks`] and [`match_features`] do so one-to-one
+//! within a [`Tolerance`] and produce [`MatchedPairs`], which computes log-ratios (fold
+//! changes), normalizes them against reference pairs expected not to change, and summarizes
+//! their distribution.
+use crate::coordinate::CoordinateLike;
+use crate::feature::FeatureLike;
+use crate::feature_map::FeatureMapLike;
+use crate::mass_error::Tolerance;
+use crate::peak::IntensityMeasurement;
+use crate::queryable::PeakQueryable;
+
+/// A signal in the reference sample matched to a signal in the query sample
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchedPair {
+    /// The position of the signal in the reference collection
+    pub reference: usize,
+    /// The position of the signal in the query collection
+    pub query: usize,
+    pub reference_intensity: f32,
+    pub query_intensity: f32,
+    /// The error of the query coordinate relative to the reference, in the units of the
+    /// tolerance the pair was matched with
+    pub error: f64,
+}
+
+impl MatchedPair {
+    /// `log2(query / reference)`, infinite if either intensity is zero
+    pub fn log2_ratio(&self) -> f64 {
+        (self.query_intensity as f64 / self.reference_intensity as f64).log2()
+    }
+}
+
+/// Summary statistics of a set of log-ratios, ignoring non-finite values
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RatioSummary {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl RatioSummary {
+    /// Summarize the finite values of `values`, or `None` if there are none
+    pub fn from_values(values: &[f64]) -> Option<Self> {
+        let mut finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+        let n = finite.len();
+        if n == 0 {
+            return None;
+        }
+        finite.sort_by(|a, b| a.total_cmp(b));
+        let mean = finite.iter().sum::<f64>() / n as f64;
+        let variance = finite.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n as f64;
+        Some(Self {
+            count: n,
+            mean,
+            median: median_of_sorted(&finite),
+            std_dev: variance.sqrt(),
+            min: finite[0],
+            max: finite[n - 1],
+        })
+    }
+}
+
+fn median_of_sorted(values: &[f64]) -> f64 {
+    let n = values.len();
+    if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        (values[n / 2 - 1] + values[n / 2]) / 2.0
+    }
+}
+
+/// One-to-one matches between a reference and a query collection, ordered by the
+/// position of the reference signal
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchedPairs {
+    pairs: Vec<MatchedPair>,
+}
+
+impl MatchedPairs {
+    /// Create a collection from `pairs`, ordering them by reference position
+    pub fn new(mut pairs: Vec<MatchedPair>) -> Self {
+        pairs.sort_by_key(|p| p.reference);
+        Self { pairs }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, MatchedPair> {
+        self.pairs.iter()
+    }
+
+    pub fn as_slice(&self) -> &[MatchedPair] {
+        &self.pairs
+    }
+
+    /// The pair whose reference signal is at position `reference`, if it was matched
+    pub fn for_reference(&self, reference: usize) -> Option<&MatchedPair> {
+        self.pairs
+            .binary_search_by_key(&reference, |p| p.reference)
+            .ok()
+            .map(|i| &self.pairs[i])
+    }
+
+    /// The `log2(query / reference)` intensity ratio of each pair
+    pub fn log2_ratios(&self) -> Vec<f64> {
+        self.pairs.iter().map(|p| p.log2_ratio()).collect()
+    }
+
+    /// The log-ratios shifted so that the median log-ratio of the pairs whose reference
+    /// positions are listed in `reference_channels` is zero, correcting for a difference in
+    /// overall loading between the samples. Returns `None` if none of `reference_channels`
+    /// were matched with a finite ratio.
+    pub fn normalized_log2_ratios(&self, reference_channels: &[usize]) -> Option<Vec<f64>> {
+        let mut anchors: Vec<f64> = reference_channels
+            .iter()
+            .filter_map(|i| self.for_reference(*i))
+            .map(|p| p.log2_ratio())
+            .filter(|r| r.is_finite())
+            .collect();
+        if anchors.is_empty() {
+            return None;
+        }
+        anchors.sort_by(|a, b| a.total_cmp(b));
+        let offset = median_of_sorted(&anchors);
+        Some(self.pairs.iter().map(|p| p.log2_ratio() - offset).collect())
+    }
+
+    /// The log-ratios shifted so that their median is zero, assuming most signals do not
+    /// change between the samples
+    pub fn median_normalized_log2_ratios(&self) -> Vec<f64> {
+        let ratios = self.log2_ratios();
+        match RatioSummary::from_values(&ratios) {
+            Some(summary) => ratios.into_iter().map(|r| r - summary.median).collect(),
+            None => ratios,
+        }
+    }
+
+    /// Summary statistics of the finite log-ratios, or `None` if there are none
+    pub fn summary(&self) -> Option<RatioSummary> {
+        RatioSummary::from_values(&self.log2_ratios())
+    }
+}
+
+impl<'a> IntoIterator for &'a MatchedPairs {
+    type Item = &'a MatchedPair;
+
+    type IntoIter = std::slice::Iter<'a, MatchedPair>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Match `n_reference` reference signals one-to-one against the query, where `nearest`
+/// finds a candidate in the query for a reference position along with its error
+fn match_one_to_one(
+    n_reference: usize,
+    mut nearest: impl FnMut(usize) -> Option<(usize, f64)>,
+    reference_intensity: impl Fn(usize) -> f32,
+    query_intensity: impl Fn(usize) -> f32,
+) -> MatchedPairs {
+    let mut candidates: Vec<(usize, usize, f64)> = (0..n_reference)
+        .filter_map(|i| nearest(i).map(|(j, err)| (i, j, err)))
+        .collect();
+    candidates.sort_by(|a, b| a.2.abs().total_cmp(&b.2.abs()));
+    let mut used = std::collections::HashSet::new();
+    let pairs = candidates
+        .into_iter()
+        .filter(|(_, j, _)| used.insert(*j))
+        .map(|(i, j, error)| MatchedPair {
+            reference: i,
+            query: j,
+            reference_intensity: reference_intensity(i),
+            query_intensity: query_intensity(j),
+            error,
+        })
+        .collect();
+    MatchedPairs::new(pairs)
+}
+
+/// Match each peak of `reference` to its nearest peak in `query` within `tolerance`. When
+/// several reference peaks share a nearest query peak, only the closest keeps it.
+pub fn match_peaks<C, A, B>(reference: &A, query: &B, tolerance: Tolerance) -> MatchedPairs
+where
+    A: PeakQueryable<C> + ?Sized,
+    A::Peak: IntensityMeasurement,
+    B: PeakQueryable<C> + ?Sized,
+    B::Peak: IntensityMeasurement,
+{
+    match_one_to_one(
+        reference.len(),
+        |i| {
+            let x = reference.get(i)?.coordinate();
+            let j = query.search(x, tolerance)?;
+            Some((j, tolerance.call(query.get(j)?.coordinate(), x)))
+        },
+        |i| reference.get(i).map_or(0.0, |p| p.intensity()),
+        |j| query.get(j).map_or(0.0, |p| p.intensity()),
+    )
+}
+
+/// Match each feature of `reference` to its nearest feature in `query` by coordinate within
+/// `tolerance`, comparing their total intensities. When several reference features share a
+/// nearest query feature, only the closest keeps it.
+pub fn match_features<X, Y, F, G, A, B>(
+    reference: &A,
+    query: &B,
+    tolerance: Tolerance,
+) -> MatchedPairs
+where
+    F: FeatureLike<X, Y>,
+    G: FeatureLike<X, Y>,
+    A: FeatureMapLike<X, Y, F>,
+    A::Output: CoordinateLike<X>,
+    B: FeatureMapLike<X, Y, G>,
+    B::Output: CoordinateLike<X>,
+{
+    match_one_to_one(
+        reference.len(),
+        |i| {
+            let x = reference.get_item(i).coordinate();
+            query
+                .search(x, tolerance)
+                .map(|j| (j, tolerance.call(query.get_item(j).coordinate(), x)))
+        },
+        |i| reference.get_item(i).intensity(),
+        |j| query.get_item(j).intensity(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::feature::LCMSFeature;
+    use crate::feature_map::FeatureMap;
+    use crate::{CentroidPeak, PeakSet};
+
+    #[test]
+    fn test_match_peaks() {
+        let reference = PeakSet::new(vec![
+            CentroidPeak::new(300.0, 100.0, 0),
+            CentroidPeak::new(400.0, 200.0, 0),
+            CentroidPeak::new(400.0015, 50.0, 0),
+            CentroidPeak::new(500.0, 400.0, 0),
+            CentroidPeak::new(600.0, 10.0, 0),
+        ]);
+        let query = PeakSet::new(vec![
+            CentroidPeak::new(300.0, 200.0, 0),
+            CentroidPeak::new(400.0005, 400.0, 0),
+            CentroidPeak::new(500.0, 1600.0, 0),
+        ]);
+        let pairs = match_peaks(&reference, &query, Tolerance::PPM(10.0));
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs.for_reference(1).unwrap().query, 1);
+        assert!(pairs.for_reference(2).is_none());
+        assert_eq!(pairs.log2_ratios(), [1.0, 1.0, 2.0]);
+
+        let normalized = pairs.normalized_log2_ratios(&[0]).unwrap();
+        assert_eq!(normalized, [0.0, 0.0, 1.0]);
+        assert!(pairs.normalized_log2_ratios(&[4]).is_none());
+        assert_eq!(pairs.median_normalized_log2_ratios(), [0.0, 0.0, 1.0]);
+
+        let summary = pairs.summary().unwrap();
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.median, 1.0);
+        assert!((summary.mean - 4.0 / 3.0).abs() < 1e-9);
+        assert_eq!((summary.min, summary.max), (1.0, 2.0));
+    }
+
+    #[test]
+    fn test_match_features() {
+        let reference: FeatureMap<_, _, LCMSFeature> = FeatureMap::new(vec![
+            [(500.0, 1.0, 10.0), (500.0, 2.0, 10.0)]
+                .into_iter()
+                .collect(),
+            [(700.0, 1.0, 5.0)].into_iter().collect(),
+        ]);
+        let query: FeatureMap<_, _, LCMSFeature> =
+            FeatureMap::new(vec![[(500.001, 1.1, 5.0), (500.001, 2.1, 5.0)]
+                .into_iter()
+                .collect()]);
+        let pairs = match_features(&reference, &query, Tolerance::PPM(5.0));
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs.log2_ratios(), [-1.0]);
+        assert!(pairs.as_slice()[0].error > 0.0);
+    }
+}
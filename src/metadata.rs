@@ -0,0 +1,266 @@
+//! A minimal description of where a peak collection came from.
+//!
+//! This crate does not model spectra, but a peak list handed from one crate to another is
+//! hard to interpret without a little context: which MS level it was acquired at, which
+//! precursor it was fragmented from, and its polarity. [`PeakSetMetadata`] carries just
+//! those fields, and [`TaggedPeakSet`] attaches them to a [`PeakSetVec`]. A
+//! [`PrecursorProductPair`] records the other side of that link, from a precursor peak to the
+//! collection of product ions it was fragmented into. Anything richer belongs in a spectrum
+//! model such as [`mzdata`](https://crates.io/crates/mzdata).
+use std::fmt::Display;
+use std::ops::{Deref, DerefMut};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::charge::Polarity;
+use crate::coordinate::{CoordinateLike, CoordinateRange, IndexedCoordinate, MZ};
+use crate::peak_set::PeakSetVec;
+
+/// The context needed to interpret a peak collection. Every field is optional.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PeakSetMetadata {
+    /// The MS level the peaks were acquired at, e.g. 1 for survey scans and 2 for
+    /// fragmentation scans
+    pub ms_level: Option<u8>,
+    /// The position of the precursor collection these peaks were fragmented from, in
+    /// whatever sequence of collections the producer and consumer share
+    pub precursor_index: Option<usize>,
+    pub polarity: Option<Polarity>,
+}
+
+impl PeakSetMetadata {
+    pub fn new(
+        ms_level: Option<u8>,
+        precursor_index: Option<usize>,
+        polarity: Option<Polarity>,
+    ) -> Self {
+        Self {
+            ms_level,
+            precursor_index,
+            polarity,
+        }
+    }
+
+    pub fn with_ms_level(mut self, ms_level: u8) -> Self {
+        self.ms_level = Some(ms_level);
+        self
+    }
+
+    pub fn with_precursor_index(mut self, precursor_index: usize) -> Self {
+        self.precursor_index = Some(precursor_index);
+        self
+    }
+
+    pub fn with_polarity(mut self, polarity: Polarity) -> Self {
+        self.polarity = Some(polarity);
+        self
+    }
+
+    /// Whether no field is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether the peaks were acquired at MS level 1. Collections with an unknown
+    /// MS level are assumed not to be survey scans.
+    pub fn is_survey(&self) -> bool {
+        self.ms_level == Some(1)
+    }
+}
+
+impl Display for PeakSetMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.ms_level {
+            Some(level) => write!(f, "MS{level}")?,
+            None => f.write_str("MS?")?,
+        }
+        if let Some(polarity) = self.polarity {
+            write!(f, " {polarity}")?;
+        }
+        if let Some(index) = self.precursor_index {
+            write!(f, " (precursor {index})")?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`PeakSetVec`] tagged with [`PeakSetMetadata`]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "P: Serialize", deserialize = "P: Deserialize<'de>"))
+)]
+pub struct TaggedPeakSet<P: IndexedCoordinate<C>, C> {
+    pub peaks: PeakSetVec<P, C>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: PeakSetMetadata,
+}
+
+impl<P: IndexedCoordinate<C>, C> TaggedPeakSet<P, C> {
+    pub fn new(peaks: PeakSetVec<P, C>, metadata: PeakSetMetadata) -> Self {
+        Self { peaks, metadata }
+    }
+
+    pub fn into_inner(self) -> PeakSetVec<P, C> {
+        self.peaks
+    }
+
+    pub fn into_parts(self) -> (PeakSetVec<P, C>, PeakSetMetadata) {
+        (self.peaks, self.metadata)
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> Deref for TaggedPeakSet<P, C> {
+    type Target = PeakSetVec<P, C>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.peaks
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> DerefMut for TaggedPeakSet<P, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.peaks
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> PeakSetVec<P, C> {
+    /// Tag this collection with `metadata`
+    pub fn with_metadata(self, metadata: PeakSetMetadata) -> TaggedPeakSet<P, C> {
+        TaggedPeakSet::new(self, metadata)
+    }
+}
+
+/// A precursor peak, e.g. a [`CentroidPeak`](crate::CentroidPeak) or a
+/// [`DeconvolutedPeak`](crate::DeconvolutedPeak), linked to the collection of product ions
+/// produced by fragmenting it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PrecursorProductPair<P> {
+    pub precursor: P,
+    /// The position of the product ion collection, in whatever sequence of collections the
+    /// producer and consumer share
+    pub product_index: usize,
+    /// The m/z window isolated for fragmentation
+    pub isolation_window: CoordinateRange<MZ>,
+}
+
+impl<P> PrecursorProductPair<P> {
+    pub fn new(precursor: P, product_index: usize, isolation_window: CoordinateRange<MZ>) -> Self {
+        Self {
+            precursor,
+            product_index,
+            isolation_window,
+        }
+    }
+
+    /// Look up the product ion collection in `collections`, or `None` if `product_index` is
+    /// out of bounds
+    pub fn product_peaks<'a, Q: IndexedCoordinate<C>, C>(
+        &self,
+        collections: &'a [PeakSetVec<Q, C>],
+    ) -> Option<&'a PeakSetVec<Q, C>> {
+        collections.get(self.product_index)
+    }
+}
+
+impl<P: CoordinateLike<MZ>> PrecursorProductPair<P> {
+    pub fn precursor_mz(&self) -> f64 {
+        self.precursor.coordinate()
+    }
+
+    /// Whether the precursor lies within the isolation window. A precursor outside its own
+    /// window usually means it was re-assigned after acquisition, e.g. to a different
+    /// isotopic peak.
+    pub fn is_precursor_isolated(&self) -> bool {
+        self.isolation_window.contains(&self.precursor)
+    }
+}
+
+impl<P: CoordinateLike<MZ>> Display for PrecursorProductPair<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.4} -> product {}",
+            self.precursor_mz(),
+            self.product_index
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CentroidPeak, DeconvolutedPeak, PeakCollection, PeakSet};
+
+    #[test]
+    fn test_tagged_peak_set() {
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(300.0, 10.0, 0),
+            CentroidPeak::new(200.0, 20.0, 1),
+        ]);
+        let metadata = PeakSetMetadata::default()
+            .with_ms_level(2)
+            .with_precursor_index(4)
+            .with_polarity(Polarity::Positive);
+        assert!(!metadata.is_empty());
+        assert!(!metadata.is_survey());
+        assert_eq!(metadata.to_string(), "MS2 positive (precursor 4)");
+        assert_eq!(PeakSetMetadata::default().to_string(), "MS?");
+
+        let tagged = peaks.with_metadata(metadata);
+        assert_eq!(tagged.len(), 2);
+        assert_eq!(tagged[0].mz, 200.0);
+        assert_eq!(tagged.metadata.ms_level, Some(2));
+    }
+
+    #[test]
+    fn test_precursor_product_pair() {
+        let products = vec![
+            PeakSet::new(vec![CentroidPeak::new(300.0, 10.0, 0)]),
+            PeakSet::new(vec![CentroidPeak::new(400.0, 10.0, 0)]),
+        ];
+        let pair = PrecursorProductPair::new(
+            DeconvolutedPeak::new(1000.0, 50.0, 2, 0),
+            1,
+            CoordinateRange::new(Some(500.0), Some(503.0)),
+        );
+        assert!((pair.precursor_mz() - 501.007276).abs() < 1e-6);
+        assert!(pair.is_precursor_isolated());
+        assert_eq!(pair.product_peaks(&products).unwrap()[0].mz, 400.0);
+        assert_eq!(pair.to_string(), "501.0073 -> product 1");
+
+        let pair = PrecursorProductPair::new(
+            CentroidPeak::new(510.0, 50.0, 0),
+            2,
+            CoordinateRange::new(Some(500.0), Some(503.0)),
+        );
+        assert!(!pair.is_precursor_isolated());
+        assert!(pair.product_peaks(&products).is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() -> Result<(), serde_json::Error> {
+        let tagged = PeakSet::new(vec![CentroidPeak::new(300.0, 10.0, 0)])
+            .with_metadata(PeakSetMetadata::default().with_ms_level(1));
+        let text = serde_json::to_string(&tagged)?;
+        let dup: TaggedPeakSet<CentroidPeak, crate::MZ> = serde_json::from_str(&text)?;
+        assert_eq!(dup.metadata, tagged.metadata);
+        assert_eq!(dup.peaks, tagged.peaks);
+        assert!(dup.metadata.is_survey());
+
+        let pair = PrecursorProductPair::new(
+            DeconvolutedPeak::new(1000.0, 50.0, 2, 0),
+            1,
+            CoordinateRange::new(Some(500.0), Some(503.0)),
+        );
+        let text = serde_json::to_string(&pair)?;
+        let dup: PrecursorProductPair<DeconvolutedPeak> = serde_json::from_str(&text)?;
+        assert_eq!(dup, pair);
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+//! Convert ion mobility measurements into collisional cross sections (CCS).
+//!
+//! An instrument reports ion mobility in its own units (drift time, 1/K0, ...) which
+//! only become comparable across runs and instruments once they are calibrated into
+//! the [`CCS`] coordinate system. The [`CcsCalibration`] trait describes such a conversion,
+//! and [`PolynomialCcsCalibration`] provides a single-field calibration model.
+//!
+//! Features and feature maps whose time-like dimension is [`IonMobility`] can be
+//! converted in bulk with their `map_to_ccs` methods.
+//!
+//! The m/z and ion mobility errors of a measurement are not independent, so checking each
+//! against its own tolerance accepts a box whose corners are further away than either
+//! tolerance suggests. [`matches_ellipse`] instead accepts the ellipse inscribed in that
+//! box, using the [`ellipse_distance`] between two entities.
+use crate::coordinate::{
+    CoordinateLike, CoordinateTransform, IonMobility, Mass, MassToMZ, CCS, MZ,
+};
+use crate::feature::{ChargedFeature, Feature};
+use crate::feature_map::FeatureMap;
+use crate::mass_error::Tolerance;
+
+/// The mass of the nitrogen (N<sub>2</sub>) drift gas, in Daltons
+pub const NITROGEN_MASS: f64 = 28.006148;
+
+/// The mass of the helium drift gas, in Daltons
+pub const HELIUM_MASS: f64 = 4.002602;
+
+/// A model that converts an ion mobility measurement into a collisional cross section
+pub trait CcsCalibration {
+    /// Compute the collisional cross section of an ion with m/z `mz` and charge `charge`
+    /// that was observed at `ion_mobility`
+    fn ccs(&self, ion_mobility: f64, mz: f64, charge: i32) -> f64;
+
+    /// Compute the collisional cross section of each ion mobility value in `ion_mobility`
+    /// for an ion of m/z `mz` and charge `charge`
+    fn ccs_of_all(&self, ion_mobility: &[f64], mz: f64, charge: i32) -> Vec<f64> {
+        ion_mobility
+            .iter()
+            .map(|im| self.ccs(*im, mz, charge))
+            .collect()
+    }
+}
+
+impl<T: CcsCalibration> CcsCalibration for &T {
+    fn ccs(&self, ion_mobility: f64, mz: f64, charge: i32) -> f64 {
+        (*self).ccs(ion_mobility, mz, charge)
+    }
+}
+
+/// A single-field calibration following the Mason-Schamp relation, where
+/// the collisional cross section is proportional to `|z| / sqrt(μ)` times
+/// a polynomial in the measured ion mobility, `μ` being the reduced mass of
+/// the ion and the drift gas.
+///
+/// The coefficients are given in increasing order of power, so `[a, b, c]`
+/// is the polynomial `a + b * im + c * im ^ 2`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolynomialCcsCalibration {
+    pub coefficients: Vec<f64>,
+    pub gas_mass: f64,
+}
+
+impl PolynomialCcsCalibration {
+    pub fn new(coefficients: Vec<f64>, gas_mass: f64) -> Self {
+        Self {
+            coefficients,
+            gas_mass,
+        }
+    }
+
+    /// Create a calibration using [`NITROGEN_MASS`] as the drift gas
+    pub fn nitrogen(coefficients: Vec<f64>) -> Self {
+        Self::new(coefficients, NITROGEN_MASS)
+    }
+
+    /// Evaluate the calibration polynomial at `ion_mobility`
+    pub fn evaluate(&self, ion_mobility: f64) -> f64 {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(0.0, |acc, c| acc * ion_mobility + c)
+    }
+
+    /// The reduced mass of an ion with m/z `mz` and charge `charge` and the drift gas
+    pub fn reduced_mass(&self, mz: f64, charge: i32) -> f64 {
+        let ion_mass = mz * charge.unsigned_abs() as f64;
+        (ion_mass * self.gas_mass) / (ion_mass + self.gas_mass)
+    }
+}
+
+impl CcsCalibration for PolynomialCcsCalibration {
+    fn ccs(&self, ion_mobility: f64, mz: f64, charge: i32) -> f64 {
+        let z = charge.unsigned_abs() as f64;
+        z / self.reduced_mass(mz, charge).sqrt() * self.evaluate(ion_mobility)
+    }
+}
+
+impl Feature<MZ, IonMobility> {
+    /// Convert the ion mobility dimension of this feature into [`CCS`], assuming
+    /// the ion has charge `charge`.
+    pub fn map_to_ccs<T: CcsCalibration>(&self, calibration: &T, charge: i32) -> Feature<MZ, CCS> {
+        self.iter()
+            .map(|(mz, im, inten)| (*mz, calibration.ccs(*im, *mz, charge), *inten))
+            .collect()
+    }
+}
+
+impl ChargedFeature<Mass, IonMobility> {
+    /// Convert the ion mobility dimension of this feature into [`CCS`] using the
+    /// feature's own charge state.
+    pub fn map_to_ccs<T: CcsCalibration>(&self, calibration: &T) -> ChargedFeature<Mass, CCS> {
+        let charge = self.charge;
+        let to_mz = MassToMZ::new(charge);
+        let feature = self
+            .iter()
+            .map(|(mass, im, inten)| {
+                let mz = to_mz.transform(*mass);
+                (*mass, calibration.ccs(*im, mz, charge), *inten)
+            })
+            .collect();
+        ChargedFeature::new(feature, charge)
+    }
+}
+
+impl FeatureMap<MZ, IonMobility, Feature<MZ, IonMobility>> {
+    /// Convert every feature in the map into the [`CCS`] dimension, assuming all
+    /// ions have charge `charge`.
+    pub fn map_to_ccs<T: CcsCalibration>(
+        &self,
+        calibration: &T,
+        charge: i32,
+    ) -> FeatureMap<MZ, CCS, Feature<MZ, CCS>> {
+        FeatureMap::new(
+            self.iter()
+                .map(|f| f.map_to_ccs(calibration, charge))
+                .collect(),
+        )
+    }
+}
+
+impl FeatureMap<Mass, IonMobility, ChargedFeature<Mass, IonMobility>> {
+    /// Convert every feature in the map into the [`CCS`] dimension using each
+    /// feature's own charge state.
+    pub fn map_to_ccs<T: CcsCalibration>(
+        &self,
+        calibration: &T,
+    ) -> FeatureMap<Mass, CCS, ChargedFeature<Mass, CCS>> {
+        FeatureMap::new(self.iter().map(|f| f.map_to_ccs(calibration)).collect())
+    }
+}
+
+/// The distance between `a` and `b` in the plane of m/z and ion mobility, scaling each
+/// dimension so that the error tolerated around `b` by `mz_tol` and `im_tol` is 1
+pub fn ellipse_distance<A, B>(a: &A, b: &B, mz_tol: Tolerance, im_tol: Tolerance) -> f64
+where
+    A: CoordinateLike<MZ> + CoordinateLike<IonMobility>,
+    B: CoordinateLike<MZ> + CoordinateLike<IonMobility>,
+{
+    let mz_error = mz_tol.normalized_error(MZ::coordinate(a), MZ::coordinate(b));
+    let im_error = im_tol.normalized_error(IonMobility::coordinate(a), IonMobility::coordinate(b));
+    mz_error.hypot(im_error)
+}
+
+/// Whether `a` lies within the ellipse around `b` whose semi-axes are the errors tolerated
+/// by `mz_tol` and `im_tol`, i.e. their [`ellipse_distance`] is at most 1
+pub fn matches_ellipse<A, B>(a: &A, b: &B, mz_tol: Tolerance, im_tol: Tolerance) -> bool
+where
+    A: CoordinateLike<MZ> + CoordinateLike<IonMobility>,
+    B: CoordinateLike<MZ> + CoordinateLike<IonMobility>,
+{
+    ellipse_distance(a, b, mz_tol, im_tol) <= 1.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::feature::{DeconvolvedIMSFeature, IMSFeature, TimeInterval};
+    use crate::prelude::*;
+    use crate::IonMobilityAwareCentroidPeak;
+
+    #[test]
+    fn test_polynomial_calibration() {
+        let cal = PolynomialCcsCalibration::nitrogen(vec![0.0, 500.0]);
+        assert_eq!(cal.evaluate(2.0), 1000.0);
+
+        let single = cal.ccs(1.0, 500.0, 1);
+        let double = cal.ccs(1.0, 500.0, 2);
+        assert!(double > single);
+
+        let mu = cal.reduced_mass(500.0, 1);
+        assert!((single - 500.0 / mu.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_matches_ellipse() {
+        let reference = IonMobilityAwareCentroidPeak::new(500.0, 1.0, 100.0, 0);
+        let (mz_tol, im_tol) = (Tolerance::Da(0.01), Tolerance::Da(0.02));
+
+        let inside = IonMobilityAwareCentroidPeak::new(500.005, 1.01, 100.0, 1);
+        assert!(
+            (ellipse_distance(&inside, &reference, mz_tol, im_tol) - 0.5f64.sqrt()).abs() < 1e-6
+        );
+        assert!(matches_ellipse(&inside, &reference, mz_tol, im_tol));
+
+        // Within both tolerances separately, but outside of the ellipse
+        let corner = IonMobilityAwareCentroidPeak::new(500.009, 1.019, 100.0, 2);
+        assert!(mz_tol.test(corner.mz, reference.mz));
+        assert!(!matches_ellipse(&corner, &reference, mz_tol, im_tol));
+        assert!(matches_ellipse(
+            &reference,
+            &reference,
+            mz_tol,
+            Tolerance::Da(0.0)
+        ));
+    }
+
+    #[test]
+    fn test_map_to_ccs() {
+        let cal = PolynomialCcsCalibration::nitrogen(vec![10.0, 400.0]);
+
+        let feature: IMSFeature = [(500.0, 0.8, 10.0), (500.0, 0.9, 50.0), (500.0, 1.0, 20.0)]
+            .into_iter()
+            .collect();
+        let converted = feature.map_to_ccs(&cal, 2);
+        assert_eq!(converted.len(), 3);
+        assert!((converted.mz() - feature.mz()).abs() < 1e-6);
+        let expected = cal.ccs(0.9, 500.0, 2);
+        assert!((converted.apex_time().unwrap() - expected).abs() < 1e-6);
+
+        let charged = DeconvolvedIMSFeature::new(
+            [(998.0, 0.8, 10.0), (998.0, 0.9, 50.0), (998.0, 1.0, 20.0)]
+                .into_iter()
+                .collect(),
+            2,
+        );
+        let mz = MZ::coordinate(&charged);
+        let charged_map = FeatureMap::new(vec![charged]).map_to_ccs(&cal);
+        assert_eq!(charged_map.len(), 1);
+        let start = charged_map[0].start_time().unwrap();
+        assert!((start - cal.ccs(0.8, mz, 2)).abs() < 1e-3);
+    }
+}
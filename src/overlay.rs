@@ -0,0 +1,167 @@
+//! Layer modified intensities over a peak list without copying it.
+//!
+//! Trying several normalizations or baseline corrections on the same spectrum would
+//! otherwise mean cloning the whole peak list for each attempt. An [`IntensityOverlay`]
+//! borrows the base [`PeakSetVec`] and records only the intensities that differ from it,
+//! keyed by peak position, until [`IntensityOverlay::resolve`] produces a concrete copy.
+use std::collections::BTreeMap;
+
+use crate::coordinate::IndexedCoordinate;
+use crate::peak::{IntensityMeasurement, IntensityMeasurementMut};
+use crate::peak_set::PeakSetVec;
+
+/// A copy-on-write layer of intensities over a borrowed [`PeakSetVec`]
+#[derive(Debug, Clone)]
+pub struct IntensityOverlay<'a, P: IndexedCoordinate<C>, C> {
+    base: &'a PeakSetVec<P, C>,
+    intensities: BTreeMap<usize, f32>,
+}
+
+impl<'a, P: IndexedCoordinate<C> + IntensityMeasurement, C> IntensityOverlay<'a, P, C> {
+    pub fn new(base: &'a PeakSetVec<P, C>) -> Self {
+        Self {
+            base,
+            intensities: BTreeMap::new(),
+        }
+    }
+
+    /// The peak list this overlay is layered over
+    pub fn base(&self) -> &'a PeakSetVec<P, C> {
+        self.base
+    }
+
+    pub fn len(&self) -> usize {
+        self.base.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.base.is_empty()
+    }
+
+    /// The intensity of the `i`th peak as seen through this overlay
+    ///
+    /// # Panics
+    /// If `i` is out of bounds
+    pub fn intensity(&self, i: usize) -> f32 {
+        match self.intensities.get(&i) {
+            Some(intensity) => *intensity,
+            None => self.base[i].intensity(),
+        }
+    }
+
+    /// Replace the intensity of the `i`th peak
+    ///
+    /// # Panics
+    /// If `i` is out of bounds
+    pub fn set_intensity(&mut self, i: usize, intensity: f32) {
+        assert!(
+            i < self.len(),
+            "Index {i} out of bounds for overlay of {} peaks",
+            self.len()
+        );
+        self.intensities.insert(i, intensity);
+    }
+
+    /// Shift the intensity of the `i`th peak by `offset`
+    ///
+    /// # Panics
+    /// If `i` is out of bounds
+    pub fn add_offset(&mut self, i: usize, offset: f32) {
+        let intensity = self.intensity(i) + offset;
+        self.set_intensity(i, intensity);
+    }
+
+    /// Replace the intensity of every peak with `f(peak, current_intensity)`
+    pub fn map_intensities<F: FnMut(&P, f32) -> f32>(&mut self, mut f: F) {
+        for (i, peak) in self.base.iter().enumerate() {
+            let intensity = f(peak, self.intensity(i));
+            self.intensities.insert(i, intensity);
+        }
+    }
+
+    /// Whether the `i`th peak's intensity differs from the base layer
+    pub fn is_modified(&self, i: usize) -> bool {
+        self.intensities.contains_key(&i)
+    }
+
+    /// The number of peaks whose intensity is stored in this layer
+    pub fn modified_count(&self) -> usize {
+        self.intensities.len()
+    }
+
+    /// Discard the change to the `i`th peak's intensity, if any
+    pub fn reset(&mut self, i: usize) {
+        self.intensities.remove(&i);
+    }
+
+    /// Discard all changes
+    pub fn clear(&mut self) {
+        self.intensities.clear();
+    }
+
+    /// Iterate over each peak of the base layer along with its overlaid intensity
+    pub fn iter(&self) -> impl Iterator<Item = (&'a P, f32)> + '_ {
+        self.base
+            .iter()
+            .enumerate()
+            .map(|(i, peak)| (peak, self.intensity(i)))
+    }
+
+    /// The total intensity of the peaks as seen through this overlay
+    pub fn total_intensity(&self) -> f64 {
+        self.iter().map(|(_, intensity)| intensity as f64).sum()
+    }
+}
+
+impl<'a, P: IndexedCoordinate<C> + IntensityMeasurementMut + Clone, C> IntensityOverlay<'a, P, C> {
+    /// Produce a new peak list with the overlaid intensities applied
+    pub fn resolve(&self) -> PeakSetVec<P, C> {
+        let mut peaks = PeakSetVec::wrap(self.base.peaks.clone());
+        for (i, intensity) in self.intensities.iter() {
+            *peaks[*i].intensity_mut() = *intensity;
+        }
+        peaks
+    }
+}
+
+impl<P: IndexedCoordinate<C> + IntensityMeasurement, C> PeakSetVec<P, C> {
+    /// Create an [`IntensityOverlay`] over this peak list
+    pub fn overlay(&self) -> IntensityOverlay<'_, P, C> {
+        IntensityOverlay::new(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_data;
+
+    #[test]
+    fn test_overlay() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let tic: f64 = peaks.iter().map(|p| p.intensity as f64).sum();
+
+        let mut normalized = peaks.overlay();
+        normalized.map_intensities(|_, intensity| (intensity as f64 / tic) as f32);
+        assert!((normalized.total_intensity() - 1.0).abs() < 1e-4);
+        assert_eq!(normalized.modified_count(), peaks.len());
+
+        let mut corrected = peaks.overlay();
+        corrected.add_offset(3, -10.0);
+        corrected.set_intensity(5, 0.0);
+        assert!(corrected.is_modified(3));
+        assert!(!corrected.is_modified(4));
+        assert_eq!(corrected.intensity(3), peaks[3].intensity - 10.0);
+        assert_eq!(corrected.intensity(4), peaks[4].intensity);
+
+        let resolved = corrected.resolve();
+        assert_eq!(resolved[5].intensity, 0.0);
+        assert_eq!(resolved[3].mz, peaks[3].mz);
+        assert_eq!(resolved[6].intensity, peaks[6].intensity);
+
+        corrected.reset(5);
+        assert_eq!(corrected.intensity(5), peaks[5].intensity);
+        corrected.clear();
+        assert_eq!(corrected.modified_count(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+//! Match a fixed list of named analytes against peak lists.
+//!
+//! Targeted assays (PRM, MRM, SIM and the like) look for the same list of analytes in every
+//! spectrum and report results by analyte name. A [`TargetPanel`] holds that list, and
+//! [`TargetPanel::match_panel`] produces [`PanelMatches`] which preserve the panel's order
+//! while allowing lookup by name.
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::ops;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::coordinate::{CoordinateLike, Mass, MZ};
+use crate::mass_error::Tolerance;
+use crate::peak_set::PeakCollection;
+
+/// A named analyte located at `coordinate` in dimension `C`, to be matched within `tolerance`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Target<C> {
+    pub name: String,
+    pub coordinate: f64,
+    pub tolerance: Tolerance,
+    _c: PhantomData<C>,
+}
+
+impl<C> Target<C> {
+    pub fn new(name: impl Into<String>, coordinate: f64, tolerance: Tolerance) -> Self {
+        Self {
+            name: name.into(),
+            coordinate,
+            tolerance,
+            _c: PhantomData,
+        }
+    }
+}
+
+/// An ordered list of uniquely named [`Target`]s
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TargetPanel<C> {
+    targets: Vec<Target<C>>,
+}
+
+impl<C> Default for TargetPanel<C> {
+    fn default() -> Self {
+        Self {
+            targets: Vec::new(),
+        }
+    }
+}
+
+impl<C> TargetPanel<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `target` to the end of the panel. If a target with the same name is already
+    /// in the panel, it is replaced in place and the old target is returned.
+    pub fn push(&mut self, target: Target<C>) -> Option<Target<C>> {
+        match self.targets.iter_mut().find(|t| t.name == target.name) {
+            Some(existing) => Some(std::mem::replace(existing, target)),
+            None => {
+                self.targets.push(target);
+                None
+            }
+        }
+    }
+
+    /// Add a new target to the panel, see [`TargetPanel::push`]
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        coordinate: f64,
+        tolerance: Tolerance,
+    ) -> &mut Self {
+        self.push(Target::new(name, coordinate, tolerance));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Find the target named `name`
+    pub fn get(&self, name: &str) -> Option<&Target<C>> {
+        self.targets.iter().find(|t| t.name == name)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Target<C>> {
+        self.targets.iter()
+    }
+
+    /// Search `peaks` for every target in the panel
+    pub fn match_panel<'a, P, S>(&self, peaks: &'a S) -> PanelMatches<'a, P>
+    where
+        P: CoordinateLike<C>,
+        S: PeakCollection<P, C>,
+        <S as ops::Index<usize>>::Output: CoordinateLike<C>,
+    {
+        let matches: Vec<PanelMatch<'a, P>> = self
+            .targets
+            .iter()
+            .map(|target| PanelMatch {
+                name: target.name.clone(),
+                coordinate: target.coordinate,
+                peaks: peaks.all_peaks_for(target.coordinate, target.tolerance),
+                nearest: peaks.has_peak(target.coordinate, target.tolerance),
+            })
+            .collect();
+        let index = matches
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (m.name.clone(), i))
+            .collect();
+        PanelMatches { matches, index }
+    }
+}
+
+impl<C> FromIterator<Target<C>> for TargetPanel<C> {
+    fn from_iter<T: IntoIterator<Item = Target<C>>>(iter: T) -> Self {
+        let mut panel = Self::new();
+        for target in iter {
+            panel.push(target);
+        }
+        panel
+    }
+}
+
+/// The peaks matched to a single [`Target`]
+#[derive(Debug, Clone)]
+pub struct PanelMatch<'a, P> {
+    pub name: String,
+    /// The target's coordinate
+    pub coordinate: f64,
+    /// All peaks within the target's tolerance
+    pub peaks: &'a [P],
+    /// The peak nearest to the target within its tolerance
+    pub nearest: Option<&'a P>,
+}
+
+impl<'a, P> PanelMatch<'a, P> {
+    pub fn is_empty(&self) -> bool {
+        self.peaks.is_empty()
+    }
+}
+
+/// The result of [`TargetPanel::match_panel`], in the same order as the panel
+#[derive(Debug, Clone)]
+pub struct PanelMatches<'a, P> {
+    matches: Vec<PanelMatch<'a, P>>,
+    index: HashMap<String, usize>,
+}
+
+impl<'a, P> PanelMatches<'a, P> {
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+
+    /// Get the match for the target named `name`
+    pub fn get(&self, name: &str) -> Option<&PanelMatch<'a, P>> {
+        self.index.get(name).map(|i| &self.matches[*i])
+    }
+
+    /// Iterate over the matches in panel order
+    pub fn iter(&self) -> std::slice::Iter<'_, PanelMatch<'a, P>> {
+        self.matches.iter()
+    }
+
+    /// Iterate over the names of the targets which matched no peaks, in panel order
+    pub fn missing(&self) -> impl Iterator<Item = &str> + '_ {
+        self.matches
+            .iter()
+            .filter(|m| m.is_empty())
+            .map(|m| m.name.as_str())
+    }
+}
+
+impl<'a, P> ops::Index<usize> for PanelMatches<'a, P> {
+    type Output = PanelMatch<'a, P>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.matches[index]
+    }
+}
+
+/// A [`TargetPanel`] of m/z targets
+pub type MZTargetPanel = TargetPanel<MZ>;
+
+/// A [`TargetPanel`] of neutral mass targets
+pub type MassTargetPanel = TargetPanel<Mass>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_data;
+
+    #[test]
+    fn test_match_panel() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+
+        let mut panel = MZTargetPanel::new();
+        panel
+            .add("b", 773.4414, Tolerance::PPM(10.0))
+            .add("a", 5000.0, Tolerance::PPM(10.0))
+            .add("c", 773.4414, Tolerance::Da(1.0));
+        assert!(panel
+            .push(Target::new("a", 240.3085, Tolerance::Da(0.01)))
+            .is_some());
+        assert_eq!(panel.len(), 3);
+
+        let matches = panel.match_panel(&peaks);
+        let names: Vec<&str> = matches.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "a", "c"]);
+
+        let b = matches.get("b").unwrap();
+        assert_eq!(b.peaks.len(), 1);
+        assert!((b.nearest.unwrap().mz - 773.4414).abs() < 1e-3);
+        assert!(matches.get("c").unwrap().peaks.len() > 1);
+        assert!(matches.get("a").unwrap().nearest.is_some());
+        assert!(matches.get("d").is_none());
+        assert_eq!(matches.missing().count(), 0);
+
+        let panel: MZTargetPanel = vec![Target::new("x", 10.0, Tolerance::Da(0.1))]
+            .into_iter()
+            .collect();
+        let matches = panel.match_panel(&peaks);
+        assert_eq!(matches.missing().collect::<Vec<_>>(), vec!["x"]);
+        Ok(())
+    }
+}
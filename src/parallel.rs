@@ -0,0 +1,113 @@
+//! Answer large batches of queries against a peak or feature collection across threads.
+//!
+//! Matching a panel of many thousands of targets against a large spectrum is embarrassingly
+//! parallel, as every query only reads the collection. These functions split the queries
+//! into one contiguous chunk per thread using [`std::thread::scope`], so they need no
+//! thread pool, and return the answers in the same order as the queries.
+use std::num::NonZeroUsize;
+use std::thread;
+
+use crate::coordinate::{CoordinateLike, IndexedCoordinate};
+use crate::feature::FeatureLike;
+use crate::feature_map::FeatureMapLike;
+use crate::mass_error::Tolerance;
+use crate::peak_set::PeakCollection;
+
+/// Apply `f` to every query, dividing the queries between the available threads
+fn map_queries<R: Send, F: Fn(f64) -> R + Sync>(queries: &[f64], f: F) -> Vec<R> {
+    let threads = thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1);
+    if threads <= 1 || queries.len() < 2 {
+        return queries.iter().map(|q| f(*q)).collect();
+    }
+    let chunk_size = queries.len().div_ceil(threads);
+    let f = &f;
+    thread::scope(|scope| {
+        let handles: Vec<_> = queries
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(|q| f(*q)).collect::<Vec<R>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// For each of `queries`, all peaks of `peaks` within `tolerance`, as
+/// [`PeakCollection::all_peaks_for`] would find
+pub fn par_all_peaks_for<'a, P, C, A>(
+    peaks: &'a A,
+    queries: &[f64],
+    tolerance: Tolerance,
+) -> Vec<&'a [P]>
+where
+    P: IndexedCoordinate<C> + Sync,
+    A: PeakCollection<P, C> + Sync,
+    A::Output: IndexedCoordinate<C>,
+{
+    map_queries(queries, |q| peaks.all_peaks_for(q, tolerance))
+}
+
+/// For each of `queries`, the index of the nearest peak of `peaks` within `tolerance`, as
+/// [`PeakCollection::search`] would find
+pub fn par_search<P, C, A>(peaks: &A, queries: &[f64], tolerance: Tolerance) -> Vec<Option<usize>>
+where
+    P: IndexedCoordinate<C>,
+    A: PeakCollection<P, C> + Sync,
+    A::Output: IndexedCoordinate<C>,
+{
+    map_queries(queries, |q| peaks.search(q, tolerance))
+}
+
+/// For each of `queries`, all features of `features` within `tolerance`, as
+/// [`FeatureMapLike::all_features_for`] would find
+pub fn par_all_features_for<'a, X, Y, F, A>(
+    features: &'a A,
+    queries: &[f64],
+    tolerance: Tolerance,
+) -> Vec<&'a [F]>
+where
+    F: FeatureLike<X, Y> + Sync,
+    A: FeatureMapLike<X, Y, F> + Sync,
+    A::Output: CoordinateLike<X>,
+{
+    map_queries(queries, |q| features.all_features_for(q, tolerance))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::feature::LCMSFeature;
+    use crate::feature_map::FeatureMap;
+    use crate::test_data;
+
+    #[test]
+    fn test_parallel_queries() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let queries: Vec<f64> = (0..1000).map(|i| 200.0 + i as f64 * 1.5).collect();
+        let tol = Tolerance::Da(0.5);
+
+        let found = par_all_peaks_for(&peaks, &queries, tol);
+        assert_eq!(found.len(), queries.len());
+        for (q, hits) in queries.iter().zip(found.iter()) {
+            assert_eq!(*hits, peaks.all_peaks_for(*q, tol));
+        }
+        let nearest = par_search(&peaks, &queries, tol);
+        for (q, hit) in queries.iter().zip(nearest) {
+            assert_eq!(hit, peaks.search(*q, tol));
+        }
+        assert!(par_search(&peaks, &[], tol).is_empty());
+
+        let features: FeatureMap<_, _, LCMSFeature> = peaks
+            .iter()
+            .map(|p| [(p.mz, 1.0, p.intensity)].into_iter().collect())
+            .collect();
+        let found = par_all_features_for(&features, &queries, tol);
+        for (q, hits) in queries.iter().zip(found.iter()) {
+            assert_eq!(hits.len(), features.all_features_for(*q, tol).len());
+        }
+        Ok(())
+    }
+}
@@ -7,8 +7,10 @@
 use std::cmp;
 use std::fmt;
 
-use crate::coordinate::{CoordinateLike, IndexType, IndexedCoordinate, Mass, MZ};
-use crate::{implement_centroidlike_inner, implement_deconvoluted_centroidlike_inner};
+use crate::coordinate::{
+    ChargeCarrier, CoordinateLike, CoordinateLikeMut, CoordinateTransform, IndexType,
+    IndexedCoordinate, IonMobility, MZToMass, Mass, MassToMZ, MZ,
+};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -40,6 +42,35 @@ impl<T: IntensityMeasurementMut> IntensityMeasurementMut for &mut T {
     }
 }
 
+/// An intensity measurement which may have been clipped because the detector
+/// reached the limit of its dynamic range, so the true abundance is at least
+/// the recorded intensity.
+pub trait SaturableIntensity: IntensityMeasurement {
+    fn is_saturated(&self) -> bool;
+}
+
+pub trait SaturableIntensityMut: SaturableIntensity {
+    fn set_saturated(&mut self, saturated: bool);
+}
+
+impl<T: SaturableIntensity> SaturableIntensity for &T {
+    fn is_saturated(&self) -> bool {
+        (*self).is_saturated()
+    }
+}
+
+impl<T: SaturableIntensity> SaturableIntensity for &mut T {
+    fn is_saturated(&self) -> bool {
+        SaturableIntensity::is_saturated(*self)
+    }
+}
+
+impl<T: SaturableIntensityMut> SaturableIntensityMut for &mut T {
+    fn set_saturated(&mut self, saturated: bool) {
+        SaturableIntensityMut::set_saturated(*self, saturated)
+    }
+}
+
 /// A [`CentroidLike`] entity is indexed in m/z coordinate space and
 /// is an [`IntensityMeasurement`]
 pub trait CentroidLike: IndexedCoordinate<MZ> + IntensityMeasurement {
@@ -80,6 +111,101 @@ impl<T: KnownChargeMut> KnownChargeMut for &mut T {
     }
 }
 
+/// An inclusive range of candidate charge states, all of the same sign. Zero is never a
+/// candidate, and a range with `low > high` is empty.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChargeRange {
+    pub low: i32,
+    pub high: i32,
+}
+
+impl ChargeRange {
+    /// Create a range spanning `a` and `b` in either order
+    pub fn new(a: i32, b: i32) -> Self {
+        Self {
+            low: a.min(b),
+            high: a.max(b),
+        }
+    }
+
+    /// A range holding only `charge`
+    pub fn single(charge: i32) -> Self {
+        Self::new(charge, charge)
+    }
+
+    pub fn contains(&self, charge: i32) -> bool {
+        charge != 0 && self.low <= charge && charge <= self.high
+    }
+
+    /// Iterate over the candidate charges from `low` to `high`
+    pub fn iter(&self) -> impl Iterator<Item = i32> {
+        (self.low..=self.high).filter(|z| *z != 0)
+    }
+
+    /// The number of candidate charges
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The single candidate charge, if there is exactly one
+    pub fn resolved(&self) -> Option<i32> {
+        (self.low == self.high && self.low != 0).then_some(self.low)
+    }
+}
+
+impl fmt::Display for ChargeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..={}", self.low, self.high)
+    }
+}
+
+impl From<std::ops::RangeInclusive<i32>> for ChargeRange {
+    fn from(value: std::ops::RangeInclusive<i32>) -> Self {
+        Self::new(*value.start(), *value.end())
+    }
+}
+
+/// A [`KnownChargeRange`] entity has a set of candidate charge states which has not yet
+/// been narrowed down to one. Anything with a [`KnownCharge`] has a single candidate.
+pub trait KnownChargeRange {
+    fn charge_range(&self) -> ChargeRange;
+
+    /// Iterate over each candidate charge and the neutral mass this entity would have
+    /// if it carried that charge in protons
+    fn candidate_neutral_masses(&self) -> impl Iterator<Item = (i32, f64)>
+    where
+        Self: CoordinateLike<MZ>,
+    {
+        self.candidate_neutral_masses_with_carrier(ChargeCarrier::Proton)
+    }
+
+    /// Iterate over each candidate charge and the neutral mass this entity would have
+    /// if it carried that charge in `carrier`
+    fn candidate_neutral_masses_with_carrier(
+        &self,
+        carrier: ChargeCarrier,
+    ) -> impl Iterator<Item = (i32, f64)>
+    where
+        Self: CoordinateLike<MZ>,
+    {
+        let mz = self.coordinate();
+        self.charge_range()
+            .iter()
+            .map(move |z| (z, MZToMass::new(z).with_carrier(carrier).transform(mz)))
+    }
+}
+
+impl<T: KnownCharge> KnownChargeRange for T {
+    fn charge_range(&self) -> ChargeRange {
+        ChargeRange::single(self.charge())
+    }
+}
+
 /// A [`DeconvolutedCentroidLike`] entity is indexed in the neutral mass
 /// coordinate space, has known charge state and an aggregated intensity
 /// measurement. Any [`DeconvolutedCentroidLike`] can be converted into
@@ -201,6 +327,318 @@ impl From<CentroidPeak> for MZPoint {
     }
 }
 
+/// Represent a single m/z coordinate with an intensity and an index, along with
+/// the shape of the profile signal it was fit from.
+#[derive(Default, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FittedPeak {
+    pub mz: f64,
+    pub intensity: f32,
+    pub index: IndexType,
+    /// The full width of the peak at half of its maximum intensity, in m/z
+    pub full_width_at_half_max: f32,
+    pub signal_to_noise: f32,
+    /// Whether the detector was saturated while measuring this peak
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub saturated: bool,
+}
+
+impl FittedPeak {
+    #[inline]
+    pub fn new(
+        mz: f64,
+        intensity: f32,
+        index: IndexType,
+        full_width_at_half_max: f32,
+        signal_to_noise: f32,
+    ) -> Self {
+        Self {
+            mz,
+            intensity,
+            index,
+            full_width_at_half_max,
+            signal_to_noise,
+            saturated: false,
+        }
+    }
+
+    /// The resolving power of the instrument at this peak, `m/Δm`, or `None` if
+    /// the peak has no width
+    pub fn resolution(&self) -> Option<f64> {
+        if self.full_width_at_half_max > 0.0 {
+            Some(self.mz / self.full_width_at_half_max as f64)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for FittedPeak {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "FittedPeak({}, {}, {}, {}, {})",
+            self.mz, self.intensity, self.index, self.full_width_at_half_max, self.signal_to_noise
+        )
+    }
+}
+
+implement_centroidlike_inner!(FittedPeak, true, true);
+
+impl SaturableIntensity for FittedPeak {
+    #[inline]
+    fn is_saturated(&self) -> bool {
+        self.saturated
+    }
+}
+
+impl SaturableIntensityMut for FittedPeak {
+    #[inline]
+    fn set_saturated(&mut self, saturated: bool) {
+        self.saturated = saturated
+    }
+}
+
+/// A [`CentroidPeak`] storing its m/z in single precision, halving the memory used by its
+/// coordinate when many millions of peaks are held at once.
+///
+/// The m/z is widened to `f64` whenever it is read through [`CoordinateLike`], but
+/// single precision only resolves about 7 significant digits, i.e. 0.0001 m/z at 1000 m/z,
+/// so it is not suitable where accuracy better than ~0.1 ppm matters. Because the stored
+/// value is not an `f64`, this type does not implement [`CoordinateLikeMut`].
+#[derive(Default, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CentroidPeakF32 {
+    pub mz: f32,
+    pub intensity: f32,
+    pub index: IndexType,
+}
+
+impl CentroidPeakF32 {
+    #[inline]
+    pub fn new(mz: f32, intensity: f32, index: IndexType) -> Self {
+        Self {
+            mz,
+            intensity,
+            index,
+        }
+    }
+}
+
+impl fmt::Display for CentroidPeakF32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "CentroidPeakF32({}, {}, {})",
+            self.mz, self.intensity, self.index
+        )
+    }
+}
+
+impl std::hash::Hash for CentroidPeakF32 {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let mz_val: i64 = self.mz.round() as i64;
+        mz_val.hash(state);
+    }
+}
+
+impl<T: CentroidLike> PartialEq<T> for CentroidPeakF32 {
+    #[inline]
+    fn eq(&self, other: &T) -> bool {
+        (self.coordinate() - other.coordinate()).abs() <= 1e-3
+            && (self.intensity - other.intensity()).abs() <= 1e-3
+    }
+}
+
+impl<T: CentroidLike> PartialOrd<T> for CentroidPeakF32 {
+    #[inline]
+    fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
+        self.coordinate().partial_cmp(&other.coordinate())
+    }
+}
+
+impl CoordinateLike<MZ> for CentroidPeakF32 {
+    #[inline]
+    fn coordinate(&self) -> f64 {
+        self.mz as f64
+    }
+}
+
+impl IndexedCoordinate<MZ> for CentroidPeakF32 {
+    #[inline]
+    fn get_index(&self) -> IndexType {
+        self.index
+    }
+
+    #[inline]
+    fn set_index(&mut self, index: IndexType) {
+        self.index = index
+    }
+}
+
+impl IntensityMeasurement for CentroidPeakF32 {
+    #[inline]
+    fn intensity(&self) -> f32 {
+        self.intensity
+    }
+}
+
+impl IntensityMeasurementMut for CentroidPeakF32 {
+    #[inline]
+    fn intensity_mut(&mut self) -> &mut f32 {
+        &mut self.intensity
+    }
+}
+
+/// Rounds the m/z to the nearest `f32`
+impl From<CentroidPeak> for CentroidPeakF32 {
+    fn from(peak: CentroidPeak) -> Self {
+        Self::new(peak.mz as f32, peak.intensity, peak.index)
+    }
+}
+
+impl From<CentroidPeakF32> for CentroidPeak {
+    fn from(peak: CentroidPeakF32) -> Self {
+        peak.as_centroid()
+    }
+}
+
+impl From<CentroidPeakF32> for MZPoint {
+    fn from(peak: CentroidPeakF32) -> Self {
+        Self::new(peak.coordinate(), peak.intensity)
+    }
+}
+
+/// Represent a single m/z coordinate with an intensity and an index, along with the
+/// ion mobility at which it was observed, e.g. the drift time or 1/K0 of a
+/// trapped ion mobility measurement.
+///
+/// This type is located in both [`MZ`] and [`IonMobility`], so calls to
+/// [`CoordinateLike::coordinate`] must name the coordinate system, e.g.
+/// `MZ::coordinate(&peak)`.
+#[derive(Default, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IonMobilityAwareCentroidPeak {
+    pub mz: f64,
+    pub ion_mobility: f64,
+    pub intensity: f32,
+    pub index: IndexType,
+}
+
+impl IonMobilityAwareCentroidPeak {
+    #[inline]
+    pub fn new(mz: f64, ion_mobility: f64, intensity: f32, index: IndexType) -> Self {
+        Self {
+            mz,
+            ion_mobility,
+            intensity,
+            index,
+        }
+    }
+
+    /// Attach an ion mobility value to a [`CentroidPeak`]
+    pub fn from_centroid(peak: CentroidPeak, ion_mobility: f64) -> Self {
+        Self::new(peak.mz, ion_mobility, peak.intensity, peak.index)
+    }
+}
+
+impl fmt::Display for IonMobilityAwareCentroidPeak {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "IonMobilityAwareCentroidPeak({}, {}, {}, {})",
+            self.mz, self.ion_mobility, self.intensity, self.index
+        )
+    }
+}
+
+implement_centroidlike_inner!(IonMobilityAwareCentroidPeak, true, false);
+
+impl CoordinateLike<IonMobility> for IonMobilityAwareCentroidPeak {
+    #[inline]
+    fn coordinate(&self) -> f64 {
+        self.ion_mobility
+    }
+}
+
+impl CoordinateLikeMut<IonMobility> for IonMobilityAwareCentroidPeak {
+    #[inline]
+    fn coordinate_mut(&mut self) -> &mut f64 {
+        &mut self.ion_mobility
+    }
+}
+
+/// Drops the ion mobility
+impl From<IonMobilityAwareCentroidPeak> for CentroidPeak {
+    fn from(peak: IonMobilityAwareCentroidPeak) -> Self {
+        peak.as_centroid()
+    }
+}
+
+/// The ion mobility is unknown and is set to zero
+impl From<CentroidPeak> for IonMobilityAwareCentroidPeak {
+    fn from(peak: CentroidPeak) -> Self {
+        Self::from_centroid(peak, 0.0)
+    }
+}
+
+impl From<IonMobilityAwareCentroidPeak> for MZPoint {
+    fn from(peak: IonMobilityAwareCentroidPeak) -> Self {
+        Self::new(peak.mz, peak.intensity)
+    }
+}
+
+/// Represent a single m/z coordinate with an intensity and an index whose charge state is
+/// ambiguous, carrying the range of candidate charges until one is chosen with
+/// [`MultiChargedCentroid::resolve`].
+#[derive(Default, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MultiChargedCentroid {
+    pub mz: f64,
+    pub intensity: f32,
+    pub index: IndexType,
+    pub charges: ChargeRange,
+}
+
+impl MultiChargedCentroid {
+    #[inline]
+    pub fn new(mz: f64, intensity: f32, index: IndexType, charges: ChargeRange) -> Self {
+        Self {
+            mz,
+            intensity,
+            index,
+            charges,
+        }
+    }
+
+    /// Convert to a [`DeconvolutedPeak`] carrying `charge`, or `None` if `charge` is
+    /// not a candidate
+    pub fn resolve(&self, charge: i32) -> Option<DeconvolutedPeak> {
+        self.candidate_neutral_masses()
+            .find(|(z, _)| *z == charge)
+            .map(|(z, mass)| DeconvolutedPeak::new(mass, self.intensity, z, self.index))
+    }
+}
+
+impl fmt::Display for MultiChargedCentroid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "MultiChargedCentroid({}, {}, {}, {})",
+            self.mz, self.intensity, self.index, self.charges
+        )
+    }
+}
+
+implement_centroidlike_inner!(MultiChargedCentroid, true, true);
+
+impl KnownChargeRange for MultiChargedCentroid {
+    fn charge_range(&self) -> ChargeRange {
+        self.charges
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Represent a single neutral mass coordinate with an
@@ -222,10 +660,16 @@ impl DeconvolutedPeak {
         }
     }
 
+    /// The m/z of this peak as an ion charged by protons
     pub fn mz(&self) -> f64 {
-        let charge_carrier: f64 = 1.007276;
-        let charge = self.charge as f64;
-        (self.neutral_mass + charge_carrier * charge) / charge
+        self.mz_with_carrier(ChargeCarrier::Proton)
+    }
+
+    /// The m/z of this peak as an ion charged by `carrier`
+    pub fn mz_with_carrier(&self, carrier: ChargeCarrier) -> f64 {
+        MassToMZ::new(self.charge)
+            .with_carrier(carrier)
+            .transform(self.neutral_mass)
     }
 }
 
@@ -264,6 +708,108 @@ mod test {
         assert_eq!(z, y);
     }
 
+    #[test]
+    fn test_fitted_peak() {
+        let fitted = FittedPeak::new(500.0, 1000.0, 3, 0.0625, 42.0);
+        assert_eq!(fitted.to_string(), "FittedPeak(500, 1000, 3, 0.0625, 42)");
+        assert_eq!(fitted.resolution(), Some(8000.0));
+
+        let centroid: CentroidPeak = fitted.clone().into();
+        assert_eq!(centroid, CentroidPeak::new(500.0, 1000.0, 3));
+        let back: FittedPeak = centroid.into();
+        assert_eq!(back.full_width_at_half_max, 0.0);
+        assert_eq!(back.resolution(), None);
+        assert_eq!(back, fitted);
+    }
+
+    #[test]
+    fn test_charge_range() {
+        let charges = ChargeRange::new(4, 2);
+        assert_eq!(charges, (2..=4).into());
+        assert_eq!(charges.iter().collect::<Vec<_>>(), [2, 3, 4]);
+        assert_eq!(charges.to_string(), "2..=4");
+        assert!(charges.contains(3) && !charges.contains(5));
+        assert_eq!(charges.resolved(), None);
+        assert_eq!(ChargeRange::new(-1, 1).len(), 2);
+        assert!(ChargeRange::default().is_empty());
+
+        let peak = MultiChargedCentroid::new(501.007276, 100.0, 3, charges);
+        let masses: Vec<_> = peak.candidate_neutral_masses().collect();
+        assert_eq!(masses.len(), 3);
+        assert!((masses[0].1 - 1000.0).abs() < 1e-6);
+        assert!((masses[2].1 - 2000.0).abs() < 1e-6);
+        let (_, sodiated) = peak
+            .candidate_neutral_masses_with_carrier(ChargeCarrier::Sodium)
+            .next()
+            .unwrap();
+        assert!((sodiated - (1000.0 + 2.0 * (PROTON - 22.989218))).abs() < 1e-6);
+
+        let resolved = peak.resolve(2).unwrap();
+        assert_eq!(resolved.charge, 2);
+        assert_eq!(resolved.index, 3);
+        assert!((resolved.neutral_mass - 1000.0).abs() < 1e-6);
+        assert!(peak.resolve(5).is_none());
+
+        let negative = MultiChargedCentroid::new(499.0, 1.0, 0, ChargeRange::single(-2));
+        let (z, mass) = negative.candidate_neutral_masses().next().unwrap();
+        assert_eq!(z, -2);
+        assert!((mass - 2.0 * (499.0 + PROTON)).abs() < 1e-6);
+
+        let deconvoluted = DeconvolutedPeak::new(1000.0, 1.0, 2, 0);
+        assert_eq!(deconvoluted.charge_range().resolved(), Some(2));
+        let centroid: CentroidPeak = peak.into();
+        assert_eq!(centroid.mz, 501.007276);
+    }
+
+    #[test]
+    fn test_single_precision_peak() {
+        let x = CentroidPeak::new(1204.0712, 5000f32, 19);
+        let y: CentroidPeakF32 = x.clone().into();
+        assert_eq!(std::mem::size_of::<CentroidPeakF32>(), 12);
+        assert!((y.coordinate() - x.mz).abs() < 1e-4);
+        assert_eq!(y, x);
+        assert_eq!(y.get_index(), 19);
+        let z: CentroidPeak = y.into();
+        assert_eq!(z.index, 19);
+        assert_eq!(
+            y.to_string(),
+            format!("CentroidPeakF32({}, 5000, 19)", y.mz)
+        );
+
+        let peaks: crate::MZPeakSetType<CentroidPeakF32> = vec![
+            CentroidPeakF32::new(300.5, 1.0, 0),
+            CentroidPeakF32::new(200.25, 2.0, 0),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(peaks[0].mz, 200.25);
+        assert_eq!(peaks[1].index, 1);
+    }
+
+    #[test]
+    fn test_ion_mobility_aware_peak() {
+        let x = IonMobilityAwareCentroidPeak::new(204.07, 0.85, 5000f32, 3);
+        assert_eq!(MZ::coordinate(&x), 204.07);
+        assert_eq!(IonMobility::coordinate(&x), 0.85);
+        assert_eq!(x.get_index(), 3);
+
+        let c: CentroidPeak = x.clone().into();
+        assert_eq!(c, x);
+        assert_eq!(c.index, 3);
+        let y = IonMobilityAwareCentroidPeak::from(c.clone());
+        assert_eq!(y.ion_mobility, 0.0);
+        let y = IonMobilityAwareCentroidPeak::from_centroid(c, 0.85);
+        assert_eq!(y.ion_mobility, x.ion_mobility);
+
+        let mut peaks = crate::peak_set::PeakSetVec::<_, MZ>::new(vec![
+            IonMobilityAwareCentroidPeak::new(300.0, 0.9, 10.0, 0),
+            x,
+        ]);
+        assert_eq!(peaks[0].mz, 204.07);
+        *CoordinateLikeMut::<IonMobility>::coordinate_mut(&mut peaks[1]) = 1.1;
+        assert_eq!(IonMobility::coordinate(&peaks[1]), 1.1);
+    }
+
     #[test]
     fn test_coordinate_context() {
         let x = DeconvolutedPeak {
@@ -276,6 +822,8 @@ mod test {
         assert_eq!(CoordinateLike::<Mass>::coordinate(&x), 799.359964027);
         assert_eq!(Mass::coordinate(&x), 799.359964027);
         assert!((x.mz() - 400.68725848027003).abs() < 1e-6);
+        let ammoniated = x.mz_with_carrier(ChargeCarrier::Ammonium);
+        assert!((ammoniated - (x.neutral_mass / 2.0 + 18.033823)).abs() < 1e-6);
         assert!((MZ::coordinate(&x) - 400.68725848027003).abs() < 1e-6);
     }
 
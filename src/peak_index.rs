@@ -34,7 +34,7 @@ pub struct PeakSliceMap<'lifespan, T: CoordinateLike<C>, C> {
     _1: PhantomData<C>,
 }
 
-impl<'transient, 'lifespan: 'transient, T: CoordinateLike<C>, C> PeakSliceMap<'lifespan, T, C> {
+impl<'lifespan, T: CoordinateLike<C>, C> PeakSliceMap<'lifespan, T, C> {
     pub fn with_capacity(capacity: usize) -> PeakSliceMap<'lifespan, T, C> {
         PeakSliceMap {
             map: HashMap::with_capacity(capacity),
@@ -42,7 +42,7 @@ impl<'transient, 'lifespan: 'transient, T: CoordinateLike<C>, C> PeakSliceMap<'l
         }
     }
 
-    pub fn get(&'lifespan self, key: f64) -> Option<&&'lifespan [T]> {
+    pub fn get(&self, key: f64) -> Option<&&'lifespan [T]> {
         let k = CoordinateKey(key);
         self.map.get(&k)
     }
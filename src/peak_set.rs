@@ -15,15 +15,23 @@
 use std::fmt::{self, Display};
 use std::iter::{Extend, FromIterator};
 use std::marker::{self, PhantomData};
-use std::ops::{self, Deref};
+use std::ops::{self, Bound, Deref, RangeBounds};
+use std::slice::SliceIndex;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::mass_error::Tolerance;
+use crate::error::MzPeaksError;
+use crate::mass_error::{CascadedTolerance, TieredMatch, Tolerance};
 
-use crate::coordinate::{CoordinateLike, IndexType, IndexedCoordinate, Mass, MZ};
-use crate::peak::{CentroidPeak, DeconvolutedPeak};
+use crate::coordinate::{
+    CoordinateLike, CoordinateLikeMut, CoordinateRange, IndexType, IndexedCoordinate, Mass,
+    Reindex, TaperWindow, WindowScheme, MZ,
+};
+use crate::peak::{
+    CentroidLike, CentroidPeak, CentroidPeakF32, DeconvolutedPeak, FittedPeak,
+    IntensityMeasurement, IntensityMeasurementMut, SaturableIntensity, SaturableIntensityMut,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// When adding a peak to a [`PeakCollection`], indicate
@@ -37,6 +45,69 @@ pub enum OrderUpdateEvent {
     InsertResorted,
 }
 
+/// How to treat peaks whose coordinate is NaN when sorting a [`PeakSetVec`].
+///
+/// A NaN coordinate cannot be ordered relative to any other value, so without a policy
+/// a single bad value from an external source would either abort sorting or leave the
+/// collection subtly out of order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NanPolicy {
+    /// Panic when a NaN coordinate is encountered
+    #[default]
+    Panic,
+    /// Place peaks with NaN coordinates after all other peaks, where searches will
+    /// not find them
+    SortToEnd,
+    /// Remove peaks with NaN coordinates from the collection
+    Drop,
+}
+
+/// How to combine peaks which [`PeakSetVec::merge_peaks_within`] collapses into one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MergeStrategy {
+    /// Sum the intensities and place the peak at the intensity-weighted mean coordinate
+    #[default]
+    WeightedSum,
+    /// Keep the most intense peak as-is and discard the rest
+    MostIntense,
+}
+
+/// A description of a failed search, from [`PeakCollection::try_search`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoPeakInWindow {
+    /// The coordinate searched for
+    pub query: f64,
+    /// The lowest and highest coordinates that would have matched
+    pub window: (f64, f64),
+    /// The index of the peak nearest to `query`, or `None` if the collection is empty
+    pub nearest: Option<usize>,
+    /// The absolute difference between `query` and the nearest peak's coordinate,
+    /// or infinity if the collection is empty
+    pub distance: f64,
+}
+
+impl Display for NoPeakInWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "No peak found for {} in [{}, {}]",
+            self.query, self.window.0, self.window.1
+        )?;
+        match self.nearest {
+            Some(i) => write!(
+                f,
+                ", the nearest peak at index {i} is {} away",
+                self.distance
+            ),
+            None => write!(f, ", the collection is empty"),
+        }
+    }
+}
+
+impl std::error::Error for NoPeakInWindow {}
+
 /// A trait for an ordered container of mass spectral peaks. The trait
 /// builds upon [`CoordinateLike`].
 pub trait PeakCollection<T: CoordinateLike<C>, C>: ops::Index<usize>
@@ -101,7 +172,7 @@ where
             }
             j += 1;
         }
-        if best_err > tol {
+        if best_err.is_nan() || best_err > tol {
             return None;
         }
         Some(best)
@@ -123,10 +194,53 @@ where
     /// Return the peak nearest to `query` within `error_tolerance` in
     /// this peak collection, or `None`.
     fn has_peak(&self, query: f64, error_tolerance: Tolerance) -> Option<&T> {
-        return match self.search(query, error_tolerance) {
-            Some(j) => Some(self.get_item(j)),
-            None => None,
+        self.search(query, error_tolerance)
+            .map(|j| self.get_item(j))
+    }
+
+    /// The index of the peak whose coordinate is nearest to `query` regardless of
+    /// any tolerance, or `None` if the collection is empty
+    fn nearest_index(&self, query: f64) -> Option<usize> {
+        let n = self.len();
+        if n == 0 {
+            return None;
+        }
+        let j = match self.search_by(query) {
+            Ok(j) => return Some(j),
+            Err(j) => j,
         };
+        if j == 0 {
+            Some(0)
+        } else if j >= n {
+            Some(n - 1)
+        } else {
+            let before = (query - self.get_item(j - 1).coordinate()).abs();
+            let after = (self.get_item(j).coordinate() - query).abs();
+            Some(if before <= after { j - 1 } else { j })
+        }
+    }
+
+    /// Like [`PeakCollection::search`], but when no peak is found, describe the
+    /// window that was searched and the nearest peak outside of it.
+    fn try_search(&self, query: f64, error_tolerance: Tolerance) -> Result<usize, NoPeakInWindow> {
+        self.search(query, error_tolerance).ok_or_else(|| {
+            let nearest = self.nearest_index(query);
+            NoPeakInWindow {
+                query,
+                window: error_tolerance.bounds(query),
+                nearest,
+                distance: nearest
+                    .map(|i| (self.get_item(i).coordinate() - query).abs())
+                    .unwrap_or(f64::INFINITY),
+            }
+        })
+    }
+
+    /// Like [`PeakCollection::has_peak`], but when no peak is found, describe the
+    /// window that was searched and the nearest peak outside of it.
+    fn try_has_peak(&self, query: f64, error_tolerance: Tolerance) -> Result<&T, NoPeakInWindow> {
+        self.try_search(query, error_tolerance)
+            .map(|j| self.get_item(j))
     }
 
     #[inline]
@@ -171,6 +285,75 @@ where
         subset
     }
 
+    /// The number of leading peaks whose coordinate is less than `x`, or less than
+    /// or equal to `x` if `inclusive` is `true`
+    #[inline]
+    fn _count_before(&self, x: f64, inclusive: bool) -> usize {
+        let mut lo = 0;
+        let mut hi = self.len();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let c = self.get_item(mid).coordinate();
+            if c < x || (inclusive && c == x) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Count the peaks whose coordinates fall within `range` using two binary
+    /// searches, without iterating over or collecting the peaks.
+    fn count_in_range<R: RangeBounds<f64>>(&self, range: R) -> usize {
+        let start = match range.start_bound() {
+            Bound::Included(x) => self._count_before(*x, false),
+            Bound::Excluded(x) => self._count_before(*x, true),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(x) => self._count_before(*x, true),
+            Bound::Excluded(x) => self._count_before(*x, false),
+            Bound::Unbounded => self.len(),
+        };
+        end.saturating_sub(start)
+    }
+
+    /// The number of peaks per unit coordinate within `range`. An unbounded side of
+    /// `range` is taken to end at the outermost peak on that side. Returns `0.0` if
+    /// the range has no width.
+    fn density<R: RangeBounds<f64>>(&self, range: R) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let start = match range.start_bound() {
+            Bound::Included(x) | Bound::Excluded(x) => *x,
+            Bound::Unbounded => self.get_item(0).coordinate(),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(x) | Bound::Excluded(x) => *x,
+            Bound::Unbounded => self.get_item(self.len() - 1).coordinate(),
+        };
+        let width = end - start;
+        if width > 0.0 {
+            self.count_in_range(range) as f64 / width
+        } else {
+            0.0
+        }
+    }
+
+    /// Iterate over every pair of indices `(i, j)` with `i < j` whose peaks' coordinates
+    /// differ by at most `delta`, in O(n + k) time for k pairs by relying on the sort order.
+    fn pairs_within(&self, delta: f64) -> impl Iterator<Item = (usize, usize)> {
+        let n = self.len();
+        (0..n).flat_map(move |i| {
+            let x = self.get_item(i).coordinate();
+            (i + 1..n)
+                .take_while(move |j| self.get_item(*j).coordinate() - x <= delta)
+                .map(move |j| (i, j))
+        })
+    }
+
     #[inline]
     /// Find all peaks which could match `query` within `error_tolerance` units
     fn all_peaks_for(&self, query: f64, error_tolerance: Tolerance) -> &[T] {
@@ -211,7 +394,17 @@ where
             lower_index += 1;
         }
         let c = lower_index..upper_index + 1;
-        return self.get_slice(c);
+        self.get_slice(c)
+    }
+
+    /// Like [`PeakCollection::search`], trying each tier of `cascade` in turn and reporting
+    /// the tier the peak was found under
+    fn search_cascaded(
+        &self,
+        query: f64,
+        cascade: &CascadedTolerance,
+    ) -> Option<TieredMatch<usize>> {
+        cascade.find(|tol| self.search(query, tol))
     }
 }
 
@@ -267,6 +460,24 @@ macro_rules! impl_slicing {
             }
         }
 
+        impl<$($args)+> std::ops::Index<std::ops::RangeInclusive<usize>> for $t {
+            type Output = [<Self as std::ops::Index<usize>>::Output];
+
+            fn index(&self, index: std::ops::RangeInclusive<usize>) -> &Self::Output {
+                let idx = std::ops::Range { start: *index.start(), end: *index.end() + 1 };
+                <Self as std::ops::Index<std::ops::Range<usize>>>::index(self, idx)
+            }
+        }
+
+        impl<$($args)+> std::ops::Index<std::ops::RangeToInclusive<usize>> for $t {
+            type Output = [<Self as std::ops::Index<usize>>::Output];
+
+            fn index(&self, index: std::ops::RangeToInclusive<usize>) -> &Self::Output {
+                let idx = std::ops::Range { start: 0, end: index.end + 1 };
+                <Self as std::ops::Index<std::ops::Range<usize>>>::index(self, idx)
+            }
+        }
+
     };
 }
 
@@ -301,8 +512,7 @@ impl<P: IndexedCoordinate<C>, C> PeakSetVec<P, C> {
         Self::with_capacity(0)
     }
 
-    pub fn from_iter<I: Iterator<Item=P>>(peaks: I, sort: bool) -> Self
-    {
+    pub fn from_iter<I: Iterator<Item = P>>(peaks: I, sort: bool) -> Self {
         let peaks: Vec<P> = peaks.collect();
         if sort {
             Self::new(peaks)
@@ -322,7 +532,67 @@ impl<P: IndexedCoordinate<C>, C> PeakSetVec<P, C> {
         }
     }
 
-    fn _sort(peaks: &mut [P]) {
+    /// Create a new [`PeakSetVec`] from an existing `Vec<P>` like [`PeakSetVec::new`],
+    /// but return an error instead of panicking if a peak has a NaN coordinate or there
+    /// are too many peaks to index.
+    pub fn try_new(peaks: Vec<P>) -> Result<Self, MzPeaksError> {
+        if peaks.len() > IndexType::MAX as usize + 1 {
+            return Err(MzPeaksError::IndexOverflow {
+                length: peaks.len(),
+            });
+        }
+        if let Some(index) = peaks.iter().position(|p| p.coordinate().is_nan()) {
+            return Err(MzPeaksError::NanCoordinate { index });
+        }
+        Ok(Self::new(peaks))
+    }
+
+    /// Create a new [`PeakSetVec`] from an existing `Vec<P>`, sorting it and
+    /// handling NaN coordinates according to `policy`
+    pub fn new_with_nan_policy(mut peaks: Vec<P>, policy: NanPolicy) -> Self {
+        Self::_sort_with_nan_policy(&mut peaks, policy);
+        Self::wrap(peaks)
+    }
+
+    /// Sort the collection, updating the peak indexing, handling NaN coordinates
+    /// according to `policy`.
+    ///
+    /// Returns the number of peaks with NaN coordinates that were found.
+    pub fn sort_with_nan_policy(&mut self, policy: NanPolicy) -> usize {
+        Self::_sort_with_nan_policy(&mut self.peaks, policy)
+    }
+
+    fn _sort_with_nan_policy(peaks: &mut Vec<P>, policy: NanPolicy) -> usize {
+        let n_nan = peaks.iter().filter(|p| p.coordinate().is_nan()).count();
+        match policy {
+            NanPolicy::Panic => {
+                if let Some(i) = peaks.iter().position(|p| p.coordinate().is_nan()) {
+                    panic!("The peak at position {i} has a NaN coordinate");
+                }
+                Self::_sort(peaks);
+            }
+            NanPolicy::SortToEnd => {
+                peaks.sort_by(
+                    |a, b| match (a.coordinate().is_nan(), b.coordinate().is_nan()) {
+                        (true, true) => std::cmp::Ordering::Equal,
+                        (true, false) => std::cmp::Ordering::Greater,
+                        (false, true) => std::cmp::Ordering::Less,
+                        (false, false) => a.partial_cmp(b).unwrap(),
+                    },
+                );
+                for (i, p) in peaks.iter_mut().enumerate() {
+                    p.set_index(i as IndexType);
+                }
+            }
+            NanPolicy::Drop => {
+                peaks.retain(|p| !p.coordinate().is_nan());
+                Self::_sort(peaks);
+            }
+        }
+        n_nan
+    }
+
+    pub(crate) fn _sort(peaks: &mut [P]) {
         peaks.sort_by(|a, b| a.partial_cmp(b).unwrap());
         for (i, p) in peaks.iter_mut().enumerate() {
             p.set_index(i as IndexType);
@@ -330,15 +600,31 @@ impl<P: IndexedCoordinate<C>, C> PeakSetVec<P, C> {
     }
 
     /// Iterate over references to peaks
-    pub fn iter(&self) -> PeakSetIter<P, C> {
+    pub fn iter(&self) -> PeakSetIter<'_, P, C> {
         PeakSetIter::new(self)
     }
 
     /// Iterate over mutable references to peaks
-    pub fn iter_mut(&mut self) -> PeakSetIterMut<P, C> {
+    pub fn iter_mut(&mut self) -> PeakSetIterMut<'_, P, C> {
         PeakSetIterMut::new(self)
     }
 
+    /// Get a peak or a slice of peaks by position, or `None` if out of bounds,
+    /// as with [`slice::get`]
+    #[inline]
+    pub fn get<I: SliceIndex<[P]>>(&self, index: I) -> Option<&I::Output> {
+        self.peaks.get(index)
+    }
+
+    /// Get a peak or a slice of peaks by position without bounds checking
+    ///
+    /// # Safety
+    /// `index` must be in bounds, see [`slice::get_unchecked`]
+    #[inline]
+    pub unsafe fn get_unchecked<I: SliceIndex<[P]>>(&self, index: I) -> &I::Output {
+        self.peaks.get_unchecked(index)
+    }
+
     fn _push(&mut self, peak: P) {
         self.peaks.push(peak);
     }
@@ -350,6 +636,18 @@ impl<P: IndexedCoordinate<C>, C> PeakSetVec<P, C> {
     pub fn as_mut_slice(&mut self) -> &mut [P] {
         self.peaks.as_mut_slice()
     }
+
+    /// Remove every peak whose coordinate lies outside `range`, re-indexing the remaining
+    /// peaks, and return the number of peaks removed
+    pub fn clip_to(&mut self, range: CoordinateRange<C>) -> usize {
+        let n = self.peaks.len();
+        self.peaks.retain(|p| range.contains_raw(&p.coordinate()));
+        let removed = n - self.peaks.len();
+        if removed > 0 {
+            self.sort();
+        }
+        removed
+    }
 }
 
 impl<P: IndexedCoordinate<C>, C> PeakCollectionMut<P, C> for PeakSetVec<P, C> {
@@ -400,8 +698,11 @@ impl<P: IndexedCoordinate<C>, C> PeakCollection<P, C> for PeakSetVec<P, C> {
 
     #[inline]
     fn search_by(&self, query: f64) -> Result<usize, usize> {
-        self.peaks
-            .binary_search_by(|peak| peak.coordinate().partial_cmp(&query).unwrap())
+        self.peaks.binary_search_by(|peak| {
+            peak.coordinate()
+                .partial_cmp(&query)
+                .unwrap_or(std::cmp::Ordering::Greater)
+        })
     }
 
     fn iter(&self) -> impl Iterator<Item = &P> {
@@ -530,6 +831,396 @@ impl<P: IndexedCoordinate<C>, C> Deref for PeakSetVec<P, C> {
     }
 }
 
+// ---- Fallible Conversion -----
+
+/// An item which could not be converted into a peak, and where it occurred in the
+/// source sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionFailure<E> {
+    /// The position of the item in the source sequence
+    pub position: usize,
+    /// The reason the conversion failed
+    pub error: E,
+}
+
+/// A summary of the items that failed to convert while building a [`PeakSetVec`]
+/// with [`TryConvertCollect::try_convert_collect`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionReport<E> {
+    /// The total number of items consumed from the source
+    pub total: usize,
+    /// Every failed conversion, in the order they were encountered
+    pub failures: Vec<ConversionFailure<E>>,
+}
+
+impl<E> Default for ConversionReport<E> {
+    fn default() -> Self {
+        Self {
+            total: 0,
+            failures: Vec::new(),
+        }
+    }
+}
+
+impl<E> ConversionReport<E> {
+    /// Whether every item was converted successfully
+    pub fn is_clean(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// The number of items that were converted
+    pub fn succeeded(&self) -> usize {
+        self.total - self.failures.len()
+    }
+
+    /// The number of items that failed to convert
+    pub fn failed(&self) -> usize {
+        self.failures.len()
+    }
+
+    /// Iterate over the source positions of the items that failed to convert
+    pub fn positions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.failures.iter().map(|f| f.position)
+    }
+}
+
+impl<E: Display> Display for ConversionReport<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} items failed to convert",
+            self.failed(),
+            self.total
+        )?;
+        for failure in self.failures.iter() {
+            write!(f, "\n  at {}: {}", failure.position, failure.error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Build a [`PeakSetVec`] from a sequence of items whose conversion into a peak may
+/// fail, keeping every peak that converted and reporting the rest instead of aborting
+/// on the first bad item.
+pub trait TryConvertCollect: Iterator + Sized {
+    /// Convert each item into `P`, collecting the successes into a sorted [`PeakSetVec`]
+    /// and every failure into a [`ConversionReport`].
+    fn try_convert_collect<P, C, E>(self) -> (PeakSetVec<P, C>, ConversionReport<E>)
+    where
+        P: IndexedCoordinate<C>,
+        Self::Item: TryInto<P, Error = E>,
+    {
+        let mut peaks = Vec::new();
+        let mut report = ConversionReport::default();
+        for (position, item) in self.enumerate() {
+            report.total += 1;
+            match item.try_into() {
+                Ok(peak) => peaks.push(peak),
+                Err(error) => report.failures.push(ConversionFailure { position, error }),
+            }
+        }
+        (PeakSetVec::new(peaks), report)
+    }
+}
+
+impl<I: Iterator> TryConvertCollect for I {}
+
+impl<P: IndexedCoordinate<C>, C> PeakSetVec<P, C> {
+    /// Build a new [`PeakSetVec`] from items that may fail to convert into `P`.
+    ///
+    /// See [`TryConvertCollect::try_convert_collect`]
+    pub fn try_convert_from<T, E, I>(items: I) -> (Self, ConversionReport<E>)
+    where
+        I: IntoIterator<Item = T>,
+        T: TryInto<P, Error = E>,
+    {
+        items.into_iter().try_convert_collect()
+    }
+}
+
+// ---- Streaming Construction -----
+
+/// The number of peaks a [`PeakSetBuilder`] buffers before merging them by default
+pub const DEFAULT_BUILDER_BUFFER_CAPACITY: usize = 1024;
+
+/// Incrementally build a [`PeakSetVec`] from peaks that arrive in arbitrary order, such as
+/// those read from several interleaved data sources.
+///
+/// Peaks are collected in a bounded unsorted buffer, which is sorted into a run of its own
+/// whenever it fills. Runs of similar length are merged as they accumulate, like the digits
+/// of a binary counter, so only a logarithmic number of runs are ever kept and ordering
+/// all of the peaks costs `O(n log n)` in total. [`PeakSetBuilder::build`] merges the
+/// remaining runs at once and produces a sorted, re-indexed peak set.
+///
+/// Peaks with equal coordinates keep the order they were pushed in, and when
+/// deduplication is enabled only the first of them is kept.
+///
+/// # Panics
+/// Sorting the buffer panics if a peak has a NaN coordinate, as [`PeakSetVec::new`] does.
+#[derive(Debug, Clone)]
+pub struct PeakSetBuilder<P: IndexedCoordinate<C>, C> {
+    /// Sorted runs in the order their peaks were pushed, each less than half as long as
+    /// the run before it
+    runs: Vec<Vec<P>>,
+    buffer: Vec<P>,
+    buffer_capacity: usize,
+    deduplicate: bool,
+    phantom: marker::PhantomData<C>,
+}
+
+impl<P: IndexedCoordinate<C>, C> Default for PeakSetBuilder<P, C> {
+    fn default() -> Self {
+        Self {
+            runs: Vec::new(),
+            buffer: Vec::new(),
+            buffer_capacity: DEFAULT_BUILDER_BUFFER_CAPACITY,
+            deduplicate: false,
+            phantom: marker::PhantomData,
+        }
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> PeakSetBuilder<P, C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of peaks to buffer before sorting them, at least one
+    pub fn with_buffer_capacity(mut self, buffer_capacity: usize) -> Self {
+        self.buffer_capacity = buffer_capacity.max(1);
+        self
+    }
+
+    /// Whether to keep only the first peak pushed at any given coordinate
+    pub fn with_deduplication(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    pub fn buffer_capacity(&self) -> usize {
+        self.buffer_capacity
+    }
+
+    pub fn deduplicate(&self) -> bool {
+        self.deduplicate
+    }
+
+    /// Add a peak, sorting the buffer if it is full
+    pub fn push(&mut self, peak: P) {
+        self.buffer.push(peak);
+        if self.buffer.len() >= self.buffer_capacity {
+            self.flush();
+        }
+    }
+
+    /// The number of peaks added so far. Duplicates are only removed by
+    /// [`PeakSetBuilder::build`].
+    pub fn len(&self) -> usize {
+        self.runs.iter().map(|run| run.len()).sum::<usize>() + self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sort the buffer into a new run, then merge the newest runs while the newer of the
+    /// two is at least half as long as the older
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let mut run = std::mem::replace(&mut self.buffer, Vec::with_capacity(self.buffer_capacity));
+        Self::sort_run(&mut run);
+        self.runs.push(run);
+        while let [.., older, newer] = self.runs.as_slice() {
+            if newer.len() * 2 < older.len() {
+                break;
+            }
+            let mut newer = self.runs.pop().unwrap();
+            let older = self.runs.last_mut().unwrap();
+            older.append(&mut newer);
+            // A stable sort finds the two ascending runs and merges them in linear time
+            Self::sort_run(older);
+        }
+    }
+
+    fn sort_run(run: &mut [P]) {
+        run.sort_by(|a, b| {
+            a.partial_cmp(b)
+                .expect("A peak with a NaN coordinate cannot be ordered")
+        });
+    }
+
+    /// Merge sorted `runs` into one, taking ties from the earliest run so that peaks keep
+    /// the order they were pushed in, and keeping only the first peak at each coordinate
+    /// if `deduplicate` is set
+    fn merge_runs(runs: Vec<Vec<P>>, deduplicate: bool) -> Vec<P> {
+        let mut merged = Vec::with_capacity(runs.iter().map(|run| run.len()).sum());
+        let mut runs: Vec<_> = runs
+            .into_iter()
+            .map(|run| run.into_iter().peekable())
+            .collect();
+        loop {
+            // There are only a logarithmic number of runs, so a linear scan for the least
+            // head is cheaper than maintaining a heap
+            let mut least: Option<(usize, f64)> = None;
+            for (i, run) in runs.iter_mut().enumerate() {
+                if let Some(peak) = run.peek() {
+                    let x = peak.coordinate();
+                    if least.is_none_or(|(_, y)| x < y) {
+                        least = Some((i, x));
+                    }
+                }
+            }
+            let Some((i, _)) = least else {
+                break;
+            };
+            let peak = runs[i].next().unwrap();
+            if deduplicate
+                && merged
+                    .last()
+                    .is_some_and(|last: &P| last.coordinate() == peak.coordinate())
+            {
+                continue;
+            }
+            merged.push(peak);
+        }
+        merged
+    }
+
+    /// Merge any buffered peaks and produce the sorted, re-indexed peak set
+    pub fn build(mut self) -> PeakSetVec<P, C> {
+        self.flush();
+        let mut peaks = Self::merge_runs(self.runs, self.deduplicate);
+        for (i, p) in peaks.iter_mut().enumerate() {
+            p.set_index(i as IndexType);
+        }
+        PeakSetVec::wrap(peaks)
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> Extend<P> for PeakSetBuilder<P, C> {
+    fn extend<T: IntoIterator<Item = P>>(&mut self, iter: T) {
+        for peak in iter {
+            self.push(peak);
+        }
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> FromIterator<P> for PeakSetBuilder<P, C> {
+    fn from_iter<T: IntoIterator<Item = P>>(iter: T) -> Self {
+        let mut builder = Self::new();
+        builder.extend(iter);
+        builder
+    }
+}
+
+// ---- Validated Deserialization -----
+
+/// What to do when a deserialized [`PeakSetVec`] is not sorted and indexed
+/// correctly, see [`PeakSetSeed`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SortValidation {
+    /// Accept the data as-is without checking it
+    Trust,
+    /// Sort and re-index the data if it is out of order
+    #[default]
+    Repair,
+    /// Fail deserialization if the data is out of order
+    Reject,
+}
+
+/// A [`serde::de::DeserializeSeed`] that deserializes a [`PeakSetVec`] and verifies that
+/// it is sorted and indexed, handling violations according to a [`SortValidation`] policy.
+///
+/// Binary search over a peak list that is not actually sorted silently returns wrong
+/// answers, so data from an untrusted source should be deserialized through this seed
+/// rather than through [`serde::Deserialize`] directly.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy)]
+pub struct PeakSetSeed<P, C> {
+    pub validation: SortValidation,
+    /// How to treat NaN coordinates when repairing the ordering
+    pub nan_policy: NanPolicy,
+    _p: PhantomData<(P, C)>,
+}
+
+#[cfg(feature = "serde")]
+impl<P, C> PeakSetSeed<P, C> {
+    pub fn new(validation: SortValidation, nan_policy: NanPolicy) -> Self {
+        Self {
+            validation,
+            nan_policy,
+            _p: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<P, C> Default for PeakSetSeed<P, C> {
+    fn default() -> Self {
+        Self::new(SortValidation::default(), NanPolicy::default())
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> PeakSetVec<P, C> {
+    /// Check that the peaks are sorted by coordinate and that each peak's index
+    /// matches its position. A NaN coordinate is never considered sorted.
+    pub fn is_sorted_and_indexed(&self) -> bool {
+        self.peaks
+            .iter()
+            .enumerate()
+            .all(|(i, p)| p.get_index() as usize == i && !p.coordinate().is_nan())
+            && self.peaks.windows(2).all(|w| w[0] <= w[1])
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> Reindex<C> for PeakSetVec<P, C> {
+    fn reindex(&mut self) {
+        self.sort()
+    }
+
+    fn is_index_consistent(&self) -> bool {
+        self.is_sorted_and_indexed()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, P, C> serde::de::DeserializeSeed<'de> for PeakSetSeed<P, C>
+where
+    P: IndexedCoordinate<C> + Deserialize<'de>,
+{
+    type Value = PeakSetVec<P, C>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let mut peaks = PeakSetVec::<P, C>::deserialize(deserializer)?;
+        match self.validation {
+            SortValidation::Trust => {}
+            _ if peaks.is_sorted_and_indexed() => {}
+            SortValidation::Reject => {
+                return Err(D::Error::custom(
+                    "peak list is not sorted and indexed by its coordinate",
+                ))
+            }
+            SortValidation::Repair => {
+                if self.nan_policy == NanPolicy::Panic
+                    && peaks.iter().any(|p| p.coordinate().is_nan())
+                {
+                    return Err(D::Error::custom("peak list contains a NaN coordinate"));
+                }
+                peaks.sort_with_nan_policy(self.nan_policy);
+            }
+        }
+        Ok(peaks)
+    }
+}
+
 // ---- Iterators -----
 
 /// Reference Iterator over [`PeakSetVec`]
@@ -578,6 +1269,378 @@ impl<'a, P, C> Iterator for PeakSetIterMut<'a, P, C> {
     }
 }
 
+impl<P: IndexedCoordinate<C> + IntensityMeasurementMut, C> PeakSetVec<P, C> {
+    /// Get a mutable reference to the intensity of the peak at position `i`, or `None`
+    /// if out of bounds. Unlike the peak itself, the intensity can be changed without
+    /// disturbing the ordering of the collection.
+    #[inline]
+    pub fn get_mut_intensity(&mut self, i: usize) -> Option<&mut f32> {
+        self.peaks.get_mut(i).map(|p| p.intensity_mut())
+    }
+
+    /// Get a mutable reference to the intensity of the first peak, if any
+    #[inline]
+    pub fn first_mut_intensity(&mut self) -> Option<&mut f32> {
+        self.get_mut_intensity(0)
+    }
+
+    /// Get a mutable reference to the intensity of the last peak, if any
+    #[inline]
+    pub fn last_mut_intensity(&mut self) -> Option<&mut f32> {
+        self.peaks.last_mut().map(|p| p.intensity_mut())
+    }
+
+    /// Attenuate the intensities of the peaks within `taper_width` of the edges of `range`
+    /// following `window`, and set those outside of `range` to zero, as
+    /// [`TaperWindow::edge_weight`] describes.
+    ///
+    /// Tapering the extractions of ranges which overlap by `taper_width` with
+    /// [`TaperWindow::Hann`] or [`TaperWindow::Linear`] lets them be summed back together
+    /// without a step in intensity where one range hands over to the next.
+    pub fn taper_edges(
+        &mut self,
+        range: CoordinateRange<C>,
+        taper_width: f64,
+        window: TaperWindow,
+    ) {
+        for peak in self.peaks.iter_mut() {
+            let weight = window.edge_weight(peak.coordinate(), &range, taper_width);
+            *peak.intensity_mut() *= weight as f32;
+        }
+    }
+}
+
+impl<P: IndexedCoordinate<C> + SaturableIntensity, C> PeakSetVec<P, C> {
+    /// Iterate over the peaks whose intensity was clipped by the detector
+    pub fn saturated_peaks(&self) -> impl Iterator<Item = &P> + '_ {
+        self.iter().filter(|p| p.is_saturated())
+    }
+
+    /// Iterate over the peaks whose intensity was measured within the detector's
+    /// dynamic range
+    pub fn unsaturated_peaks(&self) -> impl Iterator<Item = &P> + '_ {
+        self.iter().filter(|p| !p.is_saturated())
+    }
+}
+
+impl<P: IndexedCoordinate<C> + SaturableIntensityMut, C> PeakSetVec<P, C> {
+    /// Flag every peak whose intensity is at or above `detector_limit` as saturated,
+    /// returning the number of newly flagged peaks. Peaks which are already flagged
+    /// are left as-is.
+    pub fn mark_saturated(&mut self, detector_limit: f32) -> usize {
+        let mut flagged = 0;
+        for peak in self.iter_mut() {
+            if !peak.is_saturated() && peak.intensity() >= detector_limit {
+                peak.set_saturated(true);
+                flagged += 1;
+            }
+        }
+        flagged
+    }
+}
+
+impl<P: IndexedCoordinate<C> + Clone, C> PeakSetVec<P, C> {
+    /// Copy the peaks whose entry in `mask` is `true` into a new, re-indexed collection.
+    ///
+    /// Returns an error if `mask` does not have exactly one entry per peak.
+    pub fn subset_by_mask(&self, mask: &[bool]) -> Result<Self, MzPeaksError> {
+        if mask.len() != self.len() {
+            return Err(MzPeaksError::MaskLength {
+                expected: self.len(),
+                found: mask.len(),
+            });
+        }
+        let peaks = self
+            .peaks
+            .iter()
+            .zip(mask)
+            .filter(|(_, keep)| **keep)
+            .map(|(p, _)| p.clone())
+            .collect();
+        Ok(Self::new(peaks))
+    }
+
+    /// Copy the peaks at the positions in `indices` into a new, re-indexed collection,
+    /// which is ordered by coordinate regardless of the order of `indices`. A position
+    /// listed more than once is copied more than once.
+    ///
+    /// Returns an error if any position is out of bounds.
+    pub fn subset_by_indices(&self, indices: &[usize]) -> Result<Self, MzPeaksError> {
+        let peaks = indices
+            .iter()
+            .map(|i| {
+                self.peaks
+                    .get(*i)
+                    .cloned()
+                    .ok_or(MzPeaksError::IndexOutOfBounds {
+                        index: *i,
+                        length: self.len(),
+                    })
+            })
+            .collect::<Result<Vec<P>, _>>()?;
+        Ok(Self::new(peaks))
+    }
+}
+
+impl<P: IndexedCoordinate<C> + Clone, C> PeakSetVec<P, C> {
+    /// The peaks of this collection which match a peak of `other` within `tolerance`
+    pub fn intersect_with<Q, A>(&self, other: &A, tolerance: Tolerance) -> Self
+    where
+        Q: IndexedCoordinate<C>,
+        A: PeakCollection<Q, C>,
+        A::Output: IndexedCoordinate<C>,
+    {
+        Self::new(
+            self.peaks
+                .iter()
+                .filter(|p| other.search(p.coordinate(), tolerance).is_some())
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// The peaks of this collection which do not match any peak of `other` within `tolerance`
+    pub fn difference<Q, A>(&self, other: &A, tolerance: Tolerance) -> Self
+    where
+        Q: IndexedCoordinate<C>,
+        A: PeakCollection<Q, C>,
+        A::Output: IndexedCoordinate<C>,
+    {
+        Self::new(
+            self.peaks
+                .iter()
+                .filter(|p| other.search(p.coordinate(), tolerance).is_none())
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// The peaks of both collections, where each peak of this collection is paired with its
+    /// nearest peak in `other` within `tolerance` and the pair replaced by `merge_fn(ours,
+    /// theirs)`. Each peak of `other` is merged at most once, with the first peak of this
+    /// collection to claim it, and unmatched peaks of either collection are kept as-is.
+    pub fn union_with<F: FnMut(&P, &P) -> P>(
+        &self,
+        other: &Self,
+        tolerance: Tolerance,
+        mut merge_fn: F,
+    ) -> Self {
+        let mut used = vec![false; other.len()];
+        let mut peaks = Vec::with_capacity(self.len() + other.len());
+        for peak in self.peaks.iter() {
+            match other.search(peak.coordinate(), tolerance) {
+                Some(j) if !used[j] => {
+                    used[j] = true;
+                    peaks.push(merge_fn(peak, &other.peaks[j]));
+                }
+                _ => peaks.push(peak.clone()),
+            }
+        }
+        peaks.extend(
+            other
+                .peaks
+                .iter()
+                .zip(used)
+                .filter(|(_, used)| !used)
+                .map(|(p, _)| p.clone()),
+        );
+        Self::new(peaks)
+    }
+}
+
+impl<P: IndexedCoordinate<MZ> + Clone> PeakSetVec<P, MZ> {
+    /// Split the peaks into one collection per window of `scheme`, assigning each peak to a
+    /// single window with [`WindowScheme::assign`] so that no intensity is counted twice.
+    /// Peaks outside of every window are left out.
+    pub fn split_by_windows(&self, scheme: &WindowScheme) -> Vec<Self> {
+        let mut sets: Vec<Vec<P>> = vec![Vec::new(); scheme.len()];
+        for peak in self.peaks.iter() {
+            if let Some(i) = scheme.assign(peak.coordinate()) {
+                sets[i].push(peak.clone());
+            }
+        }
+        sets.into_iter().map(Self::new).collect()
+    }
+
+    /// Merge per-window collections, one for each window of `scheme` in order, back into a
+    /// single collection. Where windows overlap, a peak is kept only from the window it is
+    /// assigned to, so the same signal observed in two windows is not counted twice, and
+    /// this is the inverse of [`PeakSetVec::split_by_windows`].
+    pub fn recombine(sets: &[Self], scheme: &WindowScheme) -> Self {
+        let peaks = sets
+            .iter()
+            .enumerate()
+            .flat_map(|(i, set)| {
+                set.peaks
+                    .iter()
+                    .filter(move |p| scheme.assign(p.coordinate()) == Some(i))
+                    .cloned()
+            })
+            .collect();
+        Self::new(peaks)
+    }
+}
+
+impl<P: IndexedCoordinate<C> + IntensityMeasurement, C> PeakSetVec<P, C> {
+    /// Keep only the most intense peaks which together account for at least `fraction`
+    /// of the total intensity, removing the rest, and return the number of peaks removed.
+    ///
+    /// `fraction` is clamped to `[0, 1]`. The surviving peaks remain ordered by coordinate.
+    pub fn keep_top_tic_fraction(&mut self, fraction: f32) -> usize {
+        let n = self.len();
+        if n == 0 || fraction >= 1.0 {
+            return 0;
+        }
+        let total: f64 = self.iter().map(|p| p.intensity() as f64).sum();
+        let target = total * fraction.max(0.0) as f64;
+
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|a, b| {
+            self.peaks[*b]
+                .intensity()
+                .total_cmp(&self.peaks[*a].intensity())
+        });
+
+        let mut keep = vec![false; n];
+        let mut accumulated = 0.0;
+        for i in order {
+            if accumulated >= target {
+                break;
+            }
+            accumulated += self.peaks[i].intensity() as f64;
+            keep[i] = true;
+        }
+
+        let mut keep = keep.into_iter();
+        self.peaks.retain(|_| keep.next().unwrap());
+        let removed = n - self.len();
+        if removed > 0 {
+            self.sort();
+        }
+        removed
+    }
+
+    /// A digest of the coordinates and intensities of the peaks after quantizing them to
+    /// `precision`, for recognizing identical spectra, e.g. to deduplicate or cache them.
+    ///
+    /// The digest is stable across runs, platforms and releases of this crate. Values which
+    /// differ by less than the bin width hash identically unless they straddle a bin edge.
+    pub fn content_hash(&self, precision: ContentHashPrecision) -> u64 {
+        const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+        let mut hash = FNV_OFFSET;
+        let mut write = |value: i64| {
+            for byte in value.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        };
+        write(self.len() as i64);
+        for peak in self.iter() {
+            write((peak.coordinate() / precision.coordinate).round() as i64);
+            write((peak.intensity() as f64 / precision.intensity).round() as i64);
+        }
+        hash
+    }
+}
+
+/// The bin widths [`PeakSetVec::content_hash`] quantizes peaks to
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContentHashPrecision {
+    pub coordinate: f64,
+    pub intensity: f64,
+}
+
+impl Default for ContentHashPrecision {
+    fn default() -> Self {
+        Self::new(1e-4, 1e-2)
+    }
+}
+
+impl ContentHashPrecision {
+    pub fn new(coordinate: f64, intensity: f64) -> Self {
+        Self {
+            coordinate,
+            intensity,
+        }
+    }
+}
+
+impl<P: IndexedCoordinate<C> + CoordinateLikeMut<C> + IntensityMeasurementMut, C> PeakSetVec<P, C> {
+    /// Round every coordinate to `decimals` decimal places, which may be negative to round
+    /// to tens, hundreds and so on, and merge peaks whose rounded coordinates are equal by
+    /// summing their intensities into the first of them. Returns the number of peaks removed.
+    ///
+    /// The collection is sorted and indexed afterwards.
+    pub fn round_coordinates(&mut self, decimals: i32) -> usize {
+        let scale = 10f64.powi(decimals);
+        for peak in self.peaks.iter_mut() {
+            let x = peak.coordinate_mut();
+            *x = (*x * scale).round() / scale;
+        }
+        let n = self.len();
+        Self::_sort(&mut self.peaks);
+        self.peaks.dedup_by(|later, kept| {
+            if later.coordinate() == kept.coordinate() {
+                *kept.intensity_mut() += later.intensity();
+                true
+            } else {
+                false
+            }
+        });
+        let removed = n - self.len();
+        if removed > 0 {
+            self.sort();
+        }
+        removed
+    }
+
+    /// Collapse each run of neighboring peaks within `tolerance` of each other into a single
+    /// peak combined by `strategy`, as needed after concatenating scan segments or combining
+    /// replicate spectra. Each peak is compared with the peak it would be merged into, so a
+    /// run may span more than `tolerance` in total. Returns the number of peaks removed.
+    ///
+    /// The collection is sorted and indexed afterwards.
+    pub fn merge_peaks_within(&mut self, tolerance: Tolerance, strategy: MergeStrategy) -> usize {
+        let n = self.len();
+        let mut merged: Vec<P> = Vec::with_capacity(n);
+        for peak in std::mem::take(&mut self.peaks) {
+            let Some(last) = merged.last_mut() else {
+                merged.push(peak);
+                continue;
+            };
+            if !tolerance.test(peak.coordinate(), last.coordinate()) {
+                merged.push(peak);
+                continue;
+            }
+            match strategy {
+                MergeStrategy::WeightedSum => {
+                    let (a, b) = (last.intensity(), peak.intensity());
+                    let total = a + b;
+                    if total > 0.0 {
+                        *last.coordinate_mut() = (last.coordinate() * a as f64
+                            + peak.coordinate() * b as f64)
+                            / total as f64;
+                    }
+                    *last.intensity_mut() = total;
+                }
+                MergeStrategy::MostIntense => {
+                    if peak.intensity() > last.intensity() {
+                        *last = peak;
+                    }
+                }
+            }
+        }
+        self.peaks = merged;
+        let removed = n - self.len();
+        if removed > 0 {
+            self.sort();
+        }
+        removed
+    }
+}
+
 // ----- Specializations -----
 
 /// A [`PeakSetVec`] of [`CentroidPeak`] items
@@ -596,6 +1659,120 @@ pub type MZPeakSetType<P> = PeakSetVec<P, MZ>;
 /// coordinate is neutral mass
 pub type MassPeakSetType<D> = PeakSetVec<D, Mass>;
 
+/// The observed resolving power (`m/Δm`) of an instrument as a function of m/z,
+/// produced by [`PeakSetVec::resolution_profile`]. Each entry summarizes one m/z bin,
+/// and bins without any peaks are omitted.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ResolutionProfile {
+    /// The mean m/z of the peaks in each bin
+    pub mz: Vec<f64>,
+    /// The mean resolution of the peaks in each bin
+    pub resolution: Vec<f64>,
+    /// The number of peaks in each bin
+    pub counts: Vec<usize>,
+}
+
+impl ResolutionProfile {
+    pub fn len(&self) -> usize {
+        self.mz.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mz.is_empty()
+    }
+
+    /// Iterate over `(m/z, resolution)` pairs
+    pub fn iter(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.mz.iter().copied().zip(self.resolution.iter().copied())
+    }
+}
+
+impl MZPeakSetType<FittedPeak> {
+    /// Estimate the resolving power of the instrument across the m/z range of this
+    /// peak list from the peak widths, divided into `bins` equally wide m/z bins.
+    ///
+    /// Peaks without a positive width are ignored.
+    pub fn resolution_profile(&self, bins: usize) -> ResolutionProfile {
+        let mut profile = ResolutionProfile::default();
+        let (low, high) = match (self.first(), self.last()) {
+            (Some(first), Some(last)) if bins > 0 => (first.mz, last.mz),
+            _ => return profile,
+        };
+        let width = ((high - low) / bins as f64).max(f64::EPSILON);
+
+        let mut mz_acc = vec![0.0; bins];
+        let mut resolution_acc = vec![0.0; bins];
+        let mut counts = vec![0usize; bins];
+        for peak in self.iter() {
+            if let Some(resolution) = peak.resolution() {
+                let i = (((peak.mz - low) / width) as usize).min(bins - 1);
+                mz_acc[i] += peak.mz;
+                resolution_acc[i] += resolution;
+                counts[i] += 1;
+            }
+        }
+
+        for ((mz, resolution), count) in mz_acc.into_iter().zip(resolution_acc).zip(counts) {
+            if count > 0 {
+                profile.mz.push(mz / count as f64);
+                profile.resolution.push(resolution / count as f64);
+                profile.counts.push(count);
+            }
+        }
+        profile
+    }
+}
+
+/// The error introduced by storing coordinates at a lower precision, produced by
+/// [`PeakSetVec::to_f32_lossy_with_report`]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PrecisionLossReport {
+    /// The number of coordinates converted
+    pub count: usize,
+    /// The largest absolute error of any coordinate, in parts-per-million
+    pub max_ppm_error: f64,
+    /// The mean absolute error of the coordinates, in parts-per-million
+    pub mean_ppm_error: f64,
+}
+
+impl PrecisionLossReport {
+    /// Whether every coordinate is within `ppm` parts-per-million of its original value
+    pub fn is_within(&self, ppm: f64) -> bool {
+        self.max_ppm_error <= ppm
+    }
+}
+
+impl<P: CentroidLike> MZPeakSetType<P> {
+    /// Convert to a peak list storing m/z in single precision, along with the error that
+    /// introduces so the caller can check it is acceptable
+    pub fn to_f32_lossy_with_report(
+        &self,
+    ) -> (MZPeakSetType<CentroidPeakF32>, PrecisionLossReport) {
+        let mut report = PrecisionLossReport {
+            count: self.len(),
+            ..Default::default()
+        };
+        let mut total = 0.0;
+        let peaks = self
+            .iter()
+            .map(|p| {
+                let mz = p.coordinate();
+                let peak = CentroidPeakF32::new(mz as f32, p.intensity(), p.get_index());
+                let error = ((peak.coordinate() - mz) / mz * 1e6).abs();
+                if error.is_finite() {
+                    report.max_ppm_error = report.max_ppm_error.max(error);
+                    total += error;
+                }
+                peak
+            })
+            .collect();
+        if report.count > 0 {
+            report.mean_ppm_error = total / report.count as f64;
+        }
+        (PeakSetVec::new(peaks), report)
+    }
+}
+
 /// A borrowed view of a peak list that assumes that it is sorted by its coordinate
 /// dimension ahead of time. Unlike [`PeakSetVec`], this collection does not attempt
 /// to sort or re-index the peaks it contains.
@@ -635,6 +1812,22 @@ impl<'a, P: IndexedCoordinate<C>, C> PeakSetView<'a, P, C> {
             phantom: PhantomData,
         }
     }
+
+    /// Get a peak or a slice of peaks by position, or `None` if out of bounds,
+    /// as with [`slice::get`]
+    #[inline]
+    pub fn get<I: SliceIndex<[P]>>(&self, index: I) -> Option<&'a I::Output> {
+        self.peaks.get(index)
+    }
+
+    /// Get a peak or a slice of peaks by position without bounds checking
+    ///
+    /// # Safety
+    /// `index` must be in bounds, see [`slice::get_unchecked`]
+    #[inline]
+    pub unsafe fn get_unchecked<I: SliceIndex<[P]>>(&self, index: I) -> &'a I::Output {
+        self.peaks.get_unchecked(index)
+    }
 }
 
 impl<'a, P: IndexedCoordinate<C>, C> ops::Index<usize> for PeakSetView<'a, P, C> {
@@ -665,8 +1858,11 @@ impl<'a, P: IndexedCoordinate<C>, C> PeakCollection<P, C> for PeakSetView<'a, P,
 
     #[inline]
     fn search_by(&self, query: f64) -> Result<usize, usize> {
-        self.peaks
-            .binary_search_by(|peak| peak.coordinate().partial_cmp(&query).unwrap())
+        self.peaks.binary_search_by(|peak| {
+            peak.coordinate()
+                .partial_cmp(&query)
+                .unwrap_or(std::cmp::Ordering::Greater)
+        })
     }
 
     fn iter(&self) -> impl Iterator<Item = &P> {
@@ -719,9 +1915,92 @@ impl<'a, P: IndexedCoordinate<C>, C> TryFrom<&'a [P]> for PeakSetView<'a, P, C>
     }
 }
 
+// ---- Reordered Views -----
+
+/// A non-destructive reordering of a borrowed peak list, holding a permutation of
+/// positions rather than a sorted copy of the peaks. See [`PeakSetVec::view_sorted_by`].
+#[derive(Debug, Clone)]
+pub struct ReorderedView<'a, P> {
+    peaks: &'a [P],
+    order: Vec<usize>,
+}
+
+impl<'a, P> ReorderedView<'a, P> {
+    /// Order `peaks` by `cmp`. The sort is stable, so peaks which compare equal keep
+    /// their original relative order.
+    pub fn new<F: FnMut(&P, &P) -> std::cmp::Ordering>(peaks: &'a [P], mut cmp: F) -> Self {
+        let mut order: Vec<usize> = (0..peaks.len()).collect();
+        order.sort_by(|a, b| cmp(&peaks[*a], &peaks[*b]));
+        Self { peaks, order }
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// The peak at position `i` in the new order
+    pub fn get(&self, i: usize) -> Option<&'a P> {
+        self.order.get(i).map(|j| &self.peaks[*j])
+    }
+
+    /// The original position of each peak, in the new order
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'a P> + ExactSizeIterator + '_ {
+        let peaks = self.peaks;
+        self.order.iter().map(move |j| &peaks[*j])
+    }
+}
+
+impl<P> ops::Index<usize> for ReorderedView<'_, P> {
+    type Output = P;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.peaks[self.order[index]]
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C> PeakSetVec<P, C> {
+    /// Iterate over the peaks in descending coordinate order
+    pub fn iter_rev(&self) -> std::iter::Rev<std::slice::Iter<'_, P>> {
+        self.peaks.iter().rev()
+    }
+
+    /// View the peaks ordered by `cmp` without moving or copying them, e.g. from most to
+    /// least intense for display
+    pub fn view_sorted_by<F: FnMut(&P, &P) -> std::cmp::Ordering>(
+        &self,
+        cmp: F,
+    ) -> ReorderedView<'_, P> {
+        ReorderedView::new(&self.peaks, cmp)
+    }
+}
+
+impl<'a, P: IndexedCoordinate<C>, C> PeakSetView<'a, P, C> {
+    /// Iterate over the peaks in descending coordinate order
+    pub fn iter_rev(&self) -> std::iter::Rev<std::slice::Iter<'a, P>> {
+        self.peaks.iter().rev()
+    }
+
+    /// View the peaks ordered by `cmp` without moving or copying them
+    pub fn view_sorted_by<F: FnMut(&P, &P) -> std::cmp::Ordering>(
+        &self,
+        cmp: F,
+    ) -> ReorderedView<'a, P> {
+        ReorderedView::new(self.peaks, cmp)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::coordinate::IsolationWindow;
     use crate::test_data;
 
     #[test]
@@ -776,6 +2055,242 @@ mod test {
         assert_eq!(block.len(), 3);
     }
 
+    #[test]
+    fn test_try_convert_collect() {
+        struct Line(&'static str);
+
+        impl TryFrom<Line> for CentroidPeak {
+            type Error = std::num::ParseFloatError;
+
+            fn try_from(value: Line) -> Result<Self, Self::Error> {
+                let (mz, intensity) = value.0.split_once(' ').unwrap_or((value.0, ""));
+                Ok(CentroidPeak::new(mz.parse()?, intensity.parse()?, 0))
+            }
+        }
+
+        let lines = vec![
+            Line("300.5 100.0"),
+            Line("200.1 50.0"),
+            Line("garbage"),
+            Line("250.0 x"),
+            Line("150.2 20.0"),
+        ];
+        let (peaks, report): (PeakSet, _) = lines.into_iter().try_convert_collect();
+        assert_eq!(peaks.len(), 3);
+        assert!((peaks[0].mz - 150.2).abs() < 1e-6);
+        assert_eq!(peaks[2].index, 2);
+        assert!(!report.is_clean());
+        assert_eq!(report.succeeded(), 3);
+        assert_eq!(report.positions().collect::<Vec<_>>(), vec![2, 3]);
+
+        let (peaks, report) = PeakSet::try_convert_from(vec![Line("300.5 100.0")]);
+        assert_eq!(peaks.len(), 1);
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn test_resolution_profile() {
+        let peaks: MZPeakSetType<FittedPeak> = MZPeakSetType::new(vec![
+            FittedPeak::new(200.0, 100.0, 0, 0.002, 10.0),
+            FittedPeak::new(250.0, 100.0, 0, 0.0025, 10.0),
+            FittedPeak::new(800.0, 100.0, 0, 0.016, 10.0),
+            FittedPeak::new(900.0, 100.0, 0, 0.0, 10.0),
+        ]);
+        let profile = peaks.resolution_profile(2);
+        assert_eq!(profile.len(), 2);
+        assert_eq!(profile.counts, vec![2, 1]);
+        assert!((profile.resolution[0] - 100_000.0).abs() < 1.0);
+        assert!((profile.mz[1] - 800.0).abs() < 1e-6);
+        assert!((profile.resolution[1] - 50_000.0).abs() < 1.0);
+
+        assert!(MZPeakSetType::<FittedPeak>::empty()
+            .resolution_profile(10)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_saturated_peaks() {
+        let mut peaks: MZPeakSetType<FittedPeak> = MZPeakSetType::new(vec![
+            FittedPeak::new(200.0, 1e6, 0, 0.002, 10.0),
+            FittedPeak::new(250.0, 5e7, 0, 0.0025, 10.0),
+            FittedPeak::new(300.0, 8e7, 0, 0.003, 10.0),
+        ]);
+        assert_eq!(peaks.saturated_peaks().count(), 0);
+        assert_eq!(peaks.mark_saturated(5e7), 2);
+        assert_eq!(peaks.mark_saturated(5e7), 0);
+        let mz: Vec<f64> = peaks.saturated_peaks().map(|p| p.mz).collect();
+        assert_eq!(mz, vec![250.0, 300.0]);
+        assert_eq!(peaks.unsaturated_peaks().count(), 1);
+    }
+
+    #[test]
+    fn test_index_access() -> std::io::Result<()> {
+        let mut peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let n = peaks.len();
+        assert_eq!(peaks[..=2].len(), 3);
+        assert_eq!(peaks[1..=2].len(), 2);
+        assert_eq!(peaks[..].len(), n);
+
+        assert_eq!(peaks.get(3).map(|p| p.index), Some(3));
+        assert!(peaks.get(n).is_none());
+        assert_eq!(peaks.get(2..5).map(|s| s.len()), Some(3));
+        assert_eq!(unsafe { peaks.get_unchecked(1) }.index, 1);
+
+        let view = PeakSetView::try_from(peaks.as_slice()).unwrap();
+        assert_eq!(view[..=1].len(), 2);
+        assert_eq!(view.get(0).map(|p| p.index), Some(0));
+        assert!(view.get(n..n + 1).is_none());
+
+        *peaks.first_mut_intensity().unwrap() = 42.0;
+        assert_eq!(peaks[0].intensity, 42.0);
+        *peaks.get_mut_intensity(4).unwrap() *= 2.0;
+        assert!(peaks.get_mut_intensity(n).is_none());
+        assert!(peaks.last_mut_intensity().is_some());
+        assert!(PeakSet::empty().first_mut_intensity().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_cascaded() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let cascade: CascadedTolerance = [Tolerance::PPM(5.0), Tolerance::PPM(20.0)]
+            .into_iter()
+            .collect();
+        let hit = peaks.search_cascaded(773.4414, &cascade).unwrap();
+        assert_eq!((hit.value, hit.tier), (300, 0));
+        let hit = peaks.search_cascaded(773.4414 + 0.01, &cascade).unwrap();
+        assert_eq!((hit.value, hit.tier), (300, 1));
+        assert!(peaks.search_cascaded(773.4414 + 0.1, &cascade).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_nan_policy() {
+        let peaks = vec![
+            CentroidPeak::new(300.0, 1.0, 0),
+            CentroidPeak::new(f64::NAN, 1.0, 0),
+            CentroidPeak::new(100.0, 1.0, 0),
+            CentroidPeak::new(200.0, 1.0, 0),
+        ];
+
+        let sorted = PeakSet::new_with_nan_policy(peaks.clone(), NanPolicy::SortToEnd);
+        assert_eq!(sorted.len(), 4);
+        assert_eq!(sorted[0].mz, 100.0);
+        assert_eq!(sorted[2].mz, 300.0);
+        assert!(sorted[3].mz.is_nan());
+        assert_eq!(sorted[3].index, 3);
+        assert!(sorted.has_peak(300.0, Tolerance::Da(0.1)).is_some());
+        assert!(sorted.has_peak(400.0, Tolerance::Da(0.1)).is_none());
+
+        let mut dropped = PeakSet::wrap(peaks.clone());
+        assert_eq!(dropped.sort_with_nan_policy(NanPolicy::Drop), 1);
+        assert_eq!(dropped.len(), 3);
+        assert_eq!(dropped[2].index, 2);
+
+        let result =
+            std::panic::catch_unwind(|| PeakSet::new_with_nan_policy(peaks, NanPolicy::Panic));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_keep_top_tic_fraction() -> std::io::Result<()> {
+        let mut peaks = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 10.0, 0),
+            CentroidPeak::new(200.0, 60.0, 0),
+            CentroidPeak::new(300.0, 5.0, 0),
+            CentroidPeak::new(400.0, 25.0, 0),
+        ]);
+        assert_eq!(peaks.clone().keep_top_tic_fraction(1.0), 0);
+        assert_eq!(peaks.clone().keep_top_tic_fraction(0.0), 4);
+
+        assert_eq!(peaks.keep_top_tic_fraction(0.8), 2);
+        assert_eq!(peaks[0].mz, 200.0);
+        assert_eq!(peaks[1].mz, 400.0);
+        assert_eq!(peaks[1].index, 1);
+
+        let mut peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let total: f32 = peaks.iter().map(|p| p.intensity).sum();
+        let n = peaks.len();
+        let removed = peaks.keep_top_tic_fraction(0.5);
+        assert!(removed > 0 && removed < n);
+        let kept: f32 = peaks.iter().map(|p| p.intensity).sum();
+        assert!(kept >= total * 0.5);
+        assert!(peaks.windows(2).all(|w| w[0].mz <= w[1].mz));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pairs_within() -> std::io::Result<()> {
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 1.0, 0),
+            CentroidPeak::new(100.5, 1.0, 0),
+            CentroidPeak::new(101.0, 1.0, 0),
+            CentroidPeak::new(103.0, 1.0, 0),
+        ]);
+        let pairs: Vec<_> = peaks.pairs_within(1.0).collect();
+        assert_eq!(pairs, vec![(0, 1), (0, 2), (1, 2)]);
+        assert_eq!(peaks.pairs_within(0.1).count(), 0);
+        assert_eq!(peaks.pairs_within(10.0).count(), 6);
+
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let expected = (0..peaks.len())
+            .flat_map(|i| (i + 1..peaks.len()).map(move |j| (i, j)))
+            .filter(|(i, j)| peaks[*j].mz - peaks[*i].mz <= 1.5)
+            .count();
+        assert_eq!(peaks.pairs_within(1.5).count(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_try_search() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        assert_eq!(
+            peaks.try_search(773.4414, Tolerance::Da(0.01)).ok(),
+            peaks.search(773.4414, Tolerance::Da(0.01))
+        );
+
+        let miss = peaks.try_has_peak(773.5, Tolerance::PPM(10.0)).unwrap_err();
+        assert_eq!(miss.window, Tolerance::PPM(10.0).bounds(773.5));
+        let nearest = &peaks[miss.nearest.unwrap()];
+        assert!((miss.distance - (nearest.mz - 773.5).abs()).abs() < 1e-9);
+        assert!(peaks.iter().all(|p| (p.mz - 773.5).abs() >= miss.distance));
+
+        let miss = peaks.try_search(1.0, Tolerance::Da(0.1)).unwrap_err();
+        assert_eq!(miss.nearest, Some(0));
+        let miss = PeakSet::empty()
+            .try_search(1.0, Tolerance::Da(0.1))
+            .unwrap_err();
+        assert_eq!(miss.nearest, None);
+        assert!(miss.to_string().contains("empty"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_in_range() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let expected = peaks
+            .iter()
+            .filter(|p| (500.0..800.0).contains(&p.mz))
+            .count();
+        assert_eq!(peaks.count_in_range(500.0..800.0), expected);
+        assert_eq!(peaks.count_in_range(..), peaks.len());
+        assert_eq!(peaks.count_in_range(5000.0..), 0);
+        assert_eq!(peaks.count_in_range(800.0..500.0), 0);
+
+        let first = peaks[0].mz;
+        assert_eq!(peaks.count_in_range(first..=first), 1);
+        assert_eq!(peaks.count_in_range(..first), 0);
+
+        let density = peaks.density(500.0..800.0);
+        assert!((density - expected as f64 / 300.0).abs() < 1e-9);
+        assert_eq!(peaks.density(500.0..500.0), 0.0);
+        assert_eq!(PeakSet::empty().density(..), 0.0);
+
+        let view = PeakSetView::try_from(peaks.as_slice()).unwrap();
+        assert_eq!(view.count_in_range(500.0..800.0), expected);
+        Ok(())
+    }
+
     #[test]
     fn test_edgecases() {
         let peaks = PeakSet::new(vec![CentroidPeak::new(500.0, 2., 0)]);
@@ -792,7 +2307,380 @@ mod test {
         assert!(p.is_none());
 
         let p = peaks.all_peaks_for(500.0, Tolerance::Da(1.0));
-        assert!(p.len() == 0);
+        assert!(p.is_empty());
+    }
+
+    #[test]
+    fn test_reordered_views() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let last: Vec<f64> = peaks.iter_rev().take(2).map(|p| p.mz).collect();
+        assert_eq!(last, [peaks[484].mz, peaks[483].mz]);
+
+        let by_intensity = peaks.view_sorted_by(|a, b| b.intensity.total_cmp(&a.intensity));
+        assert_eq!(by_intensity.len(), peaks.len());
+        let max = peaks.iter().map(|p| p.intensity).fold(0.0, f32::max);
+        assert_eq!(by_intensity[0].intensity, max);
+        assert!(by_intensity
+            .iter()
+            .zip(by_intensity.iter().skip(1))
+            .all(|(a, b)| a.intensity >= b.intensity));
+        assert_eq!(
+            by_intensity.get(0).unwrap().index as usize,
+            by_intensity.order()[0]
+        );
+        assert!(by_intensity.get(peaks.len()).is_none());
+
+        let view = PeakSetView::try_from(peaks.as_slice()).unwrap();
+        assert_eq!(view.iter_rev().next().unwrap().mz, peaks[484].mz);
+        let reversed = view.view_sorted_by(|a, b| b.mz.total_cmp(&a.mz));
+        assert!(reversed
+            .iter()
+            .zip(view.iter_rev())
+            .all(|(a, b)| a.mz == b.mz));
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(200.0, 50.0, 0),
+            CentroidPeak::new(300.1234, 1000.0, 0),
+        ]);
+        let precision = ContentHashPrecision::default();
+        let hash = peaks.content_hash(precision);
+        assert_eq!(hash, peaks.clone().content_hash(precision));
+
+        let noisy = PeakSet::new(vec![
+            CentroidPeak::new(200.000001, 50.001, 0),
+            CentroidPeak::new(300.1234, 1000.0, 0),
+        ]);
+        assert_eq!(noisy.content_hash(precision), hash);
+
+        let shifted = PeakSet::new(vec![
+            CentroidPeak::new(200.01, 50.0, 0),
+            CentroidPeak::new(300.1234, 1000.0, 0),
+        ]);
+        assert_ne!(shifted.content_hash(precision), hash);
+        assert_ne!(PeakSet::empty().content_hash(precision), hash);
+    }
+
+    #[test]
+    fn test_round_coordinates() {
+        let mut peaks = PeakSet::new(vec![
+            CentroidPeak::new(200.0149, 5.0, 0),
+            CentroidPeak::new(200.0051, 10.0, 0),
+            CentroidPeak::new(200.0099, 1.0, 0),
+            CentroidPeak::new(300.126, 2.0, 0),
+        ]);
+        let removed = peaks.round_coordinates(2);
+        assert_eq!(removed, 2);
+        assert_eq!(peaks.len(), 2);
+        assert_eq!(peaks[0].mz, 200.01);
+        assert_eq!(peaks[0].intensity, 16.0);
+        assert_eq!(peaks[1].mz, 300.13);
+        assert!(peaks.is_sorted_and_indexed());
+
+        assert_eq!(peaks.round_coordinates(-3), 1);
+        assert_eq!(peaks[0].mz, 0.0);
+        assert_eq!(peaks[0].intensity, 18.0);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 1.0, 0),
+            CentroidPeak::new(200.0, 2.0, 0),
+            CentroidPeak::new(300.0, 3.0, 0),
+        ]);
+        let b = PeakSet::new(vec![
+            CentroidPeak::new(200.001, 20.0, 0),
+            CentroidPeak::new(300.5, 30.0, 0),
+            CentroidPeak::new(400.0, 40.0, 0),
+        ]);
+        let tol = Tolerance::PPM(10.0);
+
+        let both = a.intersect_with(&b, tol);
+        assert_eq!(both.len(), 1);
+        assert_eq!(both[0].mz, 200.0);
+        assert_eq!(both[0].index, 0);
+
+        let only_a = a.difference(&b, tol);
+        assert_eq!(only_a.len(), 2);
+        assert_eq!(only_a[1].mz, 300.0);
+
+        let union = a.union_with(&b, tol, |x, y| {
+            CentroidPeak::new(x.mz, x.intensity + y.intensity, 0)
+        });
+        assert_eq!(union.len(), 5);
+        assert_eq!(union[1].intensity, 22.0);
+        assert!(union.is_sorted_and_indexed());
+        assert_eq!(a.union_with(&PeakSet::empty(), tol, |x, _| x.clone()), a);
+    }
+
+    #[test]
+    fn test_split_by_windows() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let range = CoordinateRange::new(Some(200.0), Some(2000.0));
+        let scheme = WindowScheme::tiled(range, 100.0, 5.0);
+        let sets = peaks.split_by_windows(&scheme);
+        assert_eq!(sets.len(), scheme.len());
+        let n: usize = sets.iter().map(|s| s.len()).sum();
+        assert_eq!(
+            n,
+            peaks
+                .iter()
+                .filter(|p| p.mz >= 200.0 && p.mz <= 2000.0)
+                .count()
+        );
+        for (set, window) in sets.iter().zip(scheme.windows()) {
+            assert!(set.iter().all(|p| window.contains_peak(p)));
+        }
+
+        let merged = PeakSetVec::recombine(&sets, &scheme);
+        assert_eq!(merged.len(), n);
+        let total: f32 = sets
+            .iter()
+            .flat_map(|s| s.iter())
+            .map(|p| p.intensity)
+            .sum();
+        assert_eq!(merged.iter().map(|p| p.intensity).sum::<f32>(), total);
+
+        // A peak observed in both of two overlapping windows is kept once
+        let scheme = WindowScheme::new(vec![
+            IsolationWindow::symmetric(400.0, 10.0),
+            IsolationWindow::symmetric(416.0, 10.0),
+        ]);
+        let sets = vec![
+            PeakSet::new(vec![CentroidPeak::new(407.0, 10.0, 0)]),
+            PeakSet::new(vec![CentroidPeak::new(407.0, 12.0, 0)]),
+        ];
+        let merged = PeakSetVec::recombine(&sets, &scheme);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].intensity, 10.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_peaks_within() {
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 10.0, 0),
+            CentroidPeak::new(100.004, 30.0, 1),
+            CentroidPeak::new(100.008, 20.0, 2),
+            CentroidPeak::new(200.0, 5.0, 3),
+        ]);
+        let tol = Tolerance::Da(0.005);
+
+        let mut summed = peaks.clone();
+        assert_eq!(
+            summed.merge_peaks_within(tol, MergeStrategy::WeightedSum),
+            2
+        );
+        assert_eq!(summed.len(), 2);
+        assert_eq!(summed[0].intensity, 60.0);
+        let expected = (100.0 * 10.0 + 100.004 * 30.0 + 100.008 * 20.0) / 60.0;
+        assert!((summed[0].mz - expected).abs() < 1e-9);
+        assert!(summed.is_sorted_and_indexed());
+
+        let mut winner = peaks.clone();
+        assert_eq!(
+            winner.merge_peaks_within(tol, MergeStrategy::MostIntense),
+            2
+        );
+        assert_eq!(winner[0].mz, 100.004);
+        assert_eq!(winner[0].intensity, 30.0);
+        assert_eq!(winner[1].index, 1);
+
+        let mut apart = peaks.clone();
+        assert_eq!(
+            apart.merge_peaks_within(Tolerance::Da(0.001), MergeStrategy::default()),
+            0
+        );
+        assert_eq!(apart, peaks);
+    }
+
+    #[test]
+    fn test_peak_set_builder() {
+        let mzs = [500.0, 100.0, 300.0, 100.0, 200.0, 400.0, 300.0, 50.0];
+        let peaks = mzs
+            .iter()
+            .enumerate()
+            .map(|(i, mz)| CentroidPeak::new(*mz, i as f32, 0));
+
+        let mut builder: PeakSetBuilder<CentroidPeak, MZ> =
+            PeakSetBuilder::new().with_buffer_capacity(3);
+        builder.extend(peaks.clone());
+        assert_eq!(builder.len(), mzs.len());
+        let built = builder.build();
+        assert_eq!(built, PeakSet::new(peaks.clone().collect()));
+        assert!(built.is_sorted_and_indexed());
+        // Equal coordinates keep the order they were pushed in
+        assert_eq!(built[1].intensity, 1.0);
+        assert_eq!(built[2].intensity, 3.0);
+
+        let mut builder: PeakSetBuilder<CentroidPeak, MZ> = PeakSetBuilder::new()
+            .with_buffer_capacity(2)
+            .with_deduplication(true);
+        builder.extend(peaks);
+        let built = builder.build();
+        assert_eq!(built.len(), 6);
+        assert!(built.is_sorted_and_indexed());
+        assert_eq!(built[1].intensity, 1.0);
+        assert_eq!(built[3].intensity, 2.0);
+
+        let empty: PeakSetBuilder<CentroidPeak, MZ> = PeakSetBuilder::default();
+        assert!(empty.is_empty());
+        assert!(empty.build().is_empty());
+
+        // Enough buffers to merge many runs of different lengths
+        let peaks: Vec<_> = (0..1000)
+            .map(|i| CentroidPeak::new(((i * 37) % 101) as f64, i as f32, 0))
+            .collect();
+        let mut builder: PeakSetBuilder<CentroidPeak, MZ> =
+            PeakSetBuilder::new().with_buffer_capacity(7);
+        builder.extend(peaks.iter().cloned());
+        let built = builder.build();
+        assert_eq!(built.len(), 1000);
+        assert!(built.is_sorted_and_indexed());
+        assert!(built
+            .as_slice()
+            .windows(2)
+            .all(|w| w[0].mz < w[1].mz || w[0].intensity < w[1].intensity));
+
+        let mut builder: PeakSetBuilder<CentroidPeak, MZ> = PeakSetBuilder::new()
+            .with_buffer_capacity(7)
+            .with_deduplication(true);
+        builder.extend(peaks);
+        let built = builder.build();
+        assert_eq!(built.len(), 101);
+        assert!(built.iter().all(|p| p.intensity < 101.0));
+    }
+
+    #[test]
+    fn test_taper_edges() {
+        let peaks = PeakSet::new(
+            (0..=40)
+                .map(|i| CentroidPeak::new(100.0 + i as f64 * 2.5, 10.0 + i as f32, 0))
+                .collect(),
+        );
+        let first = CoordinateRange::new(Some(100.0), Some(160.0));
+        let second = CoordinateRange::new(Some(150.0), Some(200.0));
+        for window in [TaperWindow::Hann, TaperWindow::Linear] {
+            let mut a = peaks.clone();
+            a.taper_edges(first, 10.0, window);
+            let mut b = peaks.clone();
+            b.taper_edges(second, 10.0, window);
+            // Across the overlap the two tapers sum to the original intensity
+            for (i, peak) in peaks.iter().enumerate() {
+                if peak.mz >= 110.0 && peak.mz <= 190.0 {
+                    let total = a[i].intensity + b[i].intensity;
+                    assert!((total - peak.intensity).abs() < 1e-4);
+                }
+            }
+            assert_eq!(a[0].intensity, 0.0);
+            assert_eq!(a[40].intensity, 0.0);
+        }
+
+        let mut open = peaks.clone();
+        open.taper_edges(
+            CoordinateRange::new(Some(100.0), None),
+            5.0,
+            TaperWindow::Hann,
+        );
+        assert_eq!(open[40].intensity, peaks[40].intensity);
+        assert!(open[1].intensity < peaks[1].intensity);
+    }
+
+    #[test]
+    fn test_to_f32_lossy() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let (compact, report) = peaks.to_f32_lossy_with_report();
+        assert_eq!(compact.len(), peaks.len());
+        assert_eq!(report.count, peaks.len());
+        assert!(report.max_ppm_error > 0.0);
+        assert!(report.mean_ppm_error <= report.max_ppm_error);
+        assert!(report.is_within(0.1));
+        assert!(!report.is_within(0.0));
+        for (a, b) in compact.iter().zip(peaks.iter()) {
+            assert_eq!(a.index, b.index);
+            assert!(Tolerance::PPM(0.1).test(a.coordinate(), b.mz));
+        }
+
+        let (_, report) = PeakSet::empty().to_f32_lossy_with_report();
+        assert_eq!(report, PrecisionLossReport::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_clip_to() -> std::io::Result<()> {
+        let mut peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let n = peaks.len();
+        let inside = peaks
+            .iter()
+            .filter(|p| p.mz >= 500.0 && p.mz <= 1000.0)
+            .count();
+        let removed = peaks.clip_to(CoordinateRange::new(Some(500.0), Some(1000.0)));
+        assert_eq!(removed, n - inside);
+        assert_eq!(peaks.len(), inside);
+        assert!(peaks.iter().all(|p| p.mz >= 500.0 && p.mz <= 1000.0));
+        assert!(peaks.iter().enumerate().all(|(i, p)| p.index as usize == i));
+        assert_eq!(peaks.clip_to(CoordinateRange::new(None, None)), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_subset() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let mask: Vec<bool> = peaks.iter().map(|p| p.intensity > 1000.0).collect();
+        let subset = peaks.subset_by_mask(&mask).unwrap();
+        assert_eq!(subset.len(), mask.iter().filter(|m| **m).count());
+        assert!(subset
+            .iter()
+            .enumerate()
+            .all(|(i, p)| p.index as usize == i));
+        assert!(subset.iter().all(|p| p.intensity > 1000.0));
+        assert!(matches!(
+            peaks.subset_by_mask(&mask[1..]),
+            Err(MzPeaksError::MaskLength {
+                expected: 485,
+                found: 484
+            })
+        ));
+
+        let subset = peaks.subset_by_indices(&[300, 10, 20]).unwrap();
+        assert_eq!(subset.len(), 3);
+        assert_eq!(subset[0].mz, peaks[10].mz);
+        assert_eq!(subset[2].mz, peaks[300].mz);
+        assert_eq!(subset[2].index, 2);
+        assert!(matches!(
+            peaks.subset_by_indices(&[0, 485]),
+            Err(MzPeaksError::IndexOutOfBounds {
+                index: 485,
+                length: 485
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_mass_coordinate_search() {
+        let peaks = DeconvolutedPeakSet::new(vec![
+            DeconvolutedPeak::new(1500.2, 30.0, 2, 0),
+            DeconvolutedPeak::new(1000.0, 10.0, 1, 0),
+            DeconvolutedPeak::new(1000.4, 20.0, 3, 0),
+            DeconvolutedPeak::new(2000.0, 40.0, 2, 0),
+        ]);
+        assert_eq!(peaks[0].neutral_mass, 1000.0);
+        assert_eq!(peaks[3].index, 3);
+
+        let p = peaks.has_peak(1000.3, Tolerance::Da(0.2)).unwrap();
+        assert_eq!(p.charge, 3);
+        assert!(peaks.has_peak(1200.0, Tolerance::PPM(20.0)).is_none());
+
+        assert_eq!(peaks.all_peaks_for(1000.2, Tolerance::Da(0.5)).len(), 2);
+        let between = peaks.between(1000.2, 1600.0, Tolerance::Da(0.0));
+        assert_eq!(between.len(), 2);
+        assert_eq!(between[0].neutral_mass, 1000.4);
+        assert_eq!(between[1].neutral_mass, 1500.2);
     }
 
     #[cfg(feature = "serde")]
@@ -814,4 +2702,35 @@ mod test {
         assert_eq!(peaks, dup);
         Ok(())
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_seed() -> std::io::Result<()> {
+        use serde::de::DeserializeSeed;
+
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 1.0, 0),
+            CentroidPeak::new(200.0, 2.0, 0),
+            CentroidPeak::new(300.0, 3.0, 0),
+        ]);
+        assert!(peaks.is_sorted_and_indexed());
+        let mut shuffled = peaks.clone();
+        shuffled.peaks.swap(0, 2);
+        assert!(!shuffled.is_sorted_and_indexed());
+        let text = serde_json::to_string(&shuffled)?;
+
+        let load = |validation| {
+            let seed = PeakSetSeed::<CentroidPeak, MZ>::new(validation, NanPolicy::Panic);
+            seed.deserialize(&mut serde_json::Deserializer::from_str(&text))
+        };
+        assert_eq!(load(SortValidation::Repair)?, peaks);
+        assert!(!load(SortValidation::Trust)?.is_sorted_and_indexed());
+        assert!(load(SortValidation::Reject).is_err());
+
+        let text = serde_json::to_string(&peaks)?;
+        let seed = PeakSetSeed::<CentroidPeak, MZ>::new(SortValidation::Reject, NanPolicy::Panic);
+        let dup = seed.deserialize(&mut serde_json::Deserializer::from_str(&text))?;
+        assert_eq!(dup, peaks);
+        Ok(())
+    }
 }
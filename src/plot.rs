@@ -0,0 +1,143 @@
+//! A uniform view of peaks, features and signals as weighted points in a plane.
+//!
+//! Plotting libraries generally want to draw a series of `(x, y)` points, optionally
+//! weighted by a third value for color or size. [`Plottable2D`] exposes every entity
+//! in this crate that way, with the axes named by coordinate systems:
+//!
+//! - Peaks, peak lists and profile signals are plotted as coordinate versus [`Intensity`]
+//! - Features and feature maps are plotted as coordinate `X` versus time-like coordinate `Y`,
+//!   weighted by intensity
+use std::iter;
+
+use crate::coordinate::{CoordinateLike, IndexedCoordinate, Mass, MZ};
+use crate::feature::FeatureLike;
+use crate::feature_map::FeatureMap;
+use crate::peak::{CentroidPeak, DeconvolutedPeak, FittedPeak, IntensityMeasurement, MZPoint};
+use crate::peak_set::{PeakSetVec, PeakSetView};
+use crate::profile::ProfileSignal;
+
+/// A marker for the intensity axis of a plot
+#[derive(Default, Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Intensity();
+
+/// Something that can be drawn as `(x, y, weight)` points where `x` lies in coordinate
+/// system `X` and `y` lies in coordinate system `Y`
+pub trait Plottable2D<X, Y> {
+    /// Iterate over the `(x, y, weight)` points to draw
+    fn points_2d(&self) -> impl Iterator<Item = (f64, f64, f64)>;
+
+    /// The smallest and largest `x` and `y` values, as `((x_min, x_max), (y_min, y_max))`,
+    /// or `None` if there are no points
+    fn extent_2d(&self) -> Option<((f64, f64), (f64, f64))> {
+        self.points_2d().fold(None, |acc, (x, y, _)| match acc {
+            None => Some(((x, x), (y, y))),
+            Some(((x0, x1), (y0, y1))) => Some(((x0.min(x), x1.max(x)), (y0.min(y), y1.max(y)))),
+        })
+    }
+}
+
+macro_rules! impl_plottable_peak {
+    ($t:ty, $c:ty) => {
+        impl Plottable2D<$c, Intensity> for $t {
+            fn points_2d(&self) -> impl Iterator<Item = (f64, f64, f64)> {
+                let intensity = self.intensity() as f64;
+                iter::once((CoordinateLike::<$c>::coordinate(self), intensity, intensity))
+            }
+        }
+    };
+}
+
+impl_plottable_peak!(CentroidPeak, MZ);
+impl_plottable_peak!(FittedPeak, MZ);
+impl_plottable_peak!(MZPoint, MZ);
+impl_plottable_peak!(DeconvolutedPeak, Mass);
+impl_plottable_peak!(DeconvolutedPeak, MZ);
+
+impl<P: IndexedCoordinate<C> + IntensityMeasurement, C> Plottable2D<C, Intensity>
+    for PeakSetVec<P, C>
+{
+    fn points_2d(&self) -> impl Iterator<Item = (f64, f64, f64)> {
+        self.iter().map(|p| {
+            let intensity = p.intensity() as f64;
+            (p.coordinate(), intensity, intensity)
+        })
+    }
+}
+
+impl<'a, P: IndexedCoordinate<C> + IntensityMeasurement, C> Plottable2D<C, Intensity>
+    for PeakSetView<'a, P, C>
+{
+    fn points_2d(&self) -> impl Iterator<Item = (f64, f64, f64)> {
+        self.iter().map(|p| {
+            let intensity = p.intensity() as f64;
+            (p.coordinate(), intensity, intensity)
+        })
+    }
+}
+
+impl<C> Plottable2D<C, Intensity> for ProfileSignal<C> {
+    fn points_2d(&self) -> impl Iterator<Item = (f64, f64, f64)> {
+        self.iter().map(|(x, y)| (x, y as f64, y as f64))
+    }
+}
+
+impl<X, Y, T: FeatureLike<X, Y>> Plottable2D<X, Y> for T {
+    fn points_2d(&self) -> impl Iterator<Item = (f64, f64, f64)> {
+        self.iter().map(|(x, y, z)| (*x, *y, *z as f64))
+    }
+}
+
+impl<X, Y, T: FeatureLike<X, Y>> Plottable2D<X, Y> for FeatureMap<X, Y, T> {
+    fn points_2d(&self) -> impl Iterator<Item = (f64, f64, f64)> {
+        self.iter()
+            .flat_map(|f| f.iter().map(|(x, y, z)| (*x, *y, *z as f64)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::coordinate::Time;
+    use crate::feature::LCMSFeature;
+    use crate::profile::MZProfile;
+    use crate::PeakSet;
+
+    fn collect<X, Y, T: Plottable2D<X, Y>>(item: &T) -> Vec<(f64, f64, f64)> {
+        item.points_2d().collect()
+    }
+
+    #[test]
+    fn test_plottable() {
+        let peak = DeconvolutedPeak::new(1000.0, 50.0, 2, 0);
+        let mass: Vec<_> = collect::<Mass, Intensity, _>(&peak);
+        assert_eq!(mass, vec![(1000.0, 50.0, 50.0)]);
+        let mz: Vec<_> = collect::<MZ, Intensity, _>(&peak);
+        assert!((mz[0].0 - peak.mz()).abs() < 1e-9);
+
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(200.0, 5.0, 0),
+            CentroidPeak::new(100.0, 10.0, 0),
+        ]);
+        assert_eq!(
+            collect(&peaks),
+            vec![(100.0, 10.0, 10.0), (200.0, 5.0, 5.0)]
+        );
+        assert_eq!(peaks.extent_2d(), Some(((100.0, 200.0), (5.0, 10.0))));
+
+        let signal = MZProfile::new(vec![1.0, 2.0], vec![3.0, 4.0]);
+        assert_eq!(collect(&signal).len(), 2);
+
+        let feature: LCMSFeature = [(500.0, 10.0, 1.0), (500.1, 11.0, 4.0)]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            collect::<MZ, Time, _>(&feature),
+            vec![(500.0, 10.0, 1.0), (500.1, 11.0, 4.0)]
+        );
+        let map = FeatureMap::new(vec![feature.clone(), feature]);
+        assert_eq!(collect(&map).len(), 4);
+        assert!(FeatureMap::<MZ, Time, LCMSFeature>::empty()
+            .extent_2d()
+            .is_none());
+    }
+}
@@ -1,11 +1,16 @@
 //! A prelude to bring into scope all the traits of this library.
 
-pub use crate::coordinate::{CoordinateLike, IndexedCoordinate, MZLocated, MassLocated};
-pub use crate::feature::{FeatureLike, FeatureLikeMut, TimeInterval, SplittableFeatureLike};
+pub use crate::coordinate::{
+    CoordinateLike, CoordinateTransform, IndexedCoordinate, MZLocated, MassLocated, Reindex,
+};
+pub use crate::feature::{
+    FeatureLike, FeatureLikeMut, SliceableFeatureLike, SplittableFeatureLike, TimeInterval,
+};
 pub use crate::feature_map::{FeatureMapLike, FeatureMapLikeMut};
 pub use crate::mass_error::Tolerance;
 pub use crate::peak::{
     CentroidLike, DeconvolutedCentroidLike, IntensityMeasurement, IntensityMeasurementMut,
-    KnownCharge, KnownChargeMut,
+    KnownCharge, KnownChargeMut, SaturableIntensity, SaturableIntensityMut,
 };
-pub use crate::peak_set::{PeakCollection, PeakCollectionMut};
+pub use crate::peak_set::{PeakCollection, PeakCollectionMut, TryConvertCollect};
+pub use crate::plot::Plottable2D;
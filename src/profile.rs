@@ -0,0 +1,215 @@
+//! Continuous signals sampled along a single coordinate dimension.
+//!
+//! Unlike a peak list, a profile signal is a densely sampled curve, such as a profile mode
+//! spectrum along m/z or a chromatogram along time, where the intensity between two samples
+//! is assumed to vary linearly.
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::coordinate::{Span1D, Time, MZ};
+
+/// A signal sampled at increasing coordinates in dimension `C`
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProfileSignal<C> {
+    coordinates: Vec<f64>,
+    intensities: Vec<f32>,
+    _c: PhantomData<C>,
+}
+
+impl<C> ProfileSignal<C> {
+    /// Create a new signal from paired coordinates and intensities, which will be sorted
+    /// by coordinate if they are not already.
+    ///
+    /// # Panics
+    /// If `coordinates` and `intensities` have different lengths
+    pub fn new(coordinates: Vec<f64>, intensities: Vec<f32>) -> Self {
+        assert_eq!(
+            coordinates.len(),
+            intensities.len(),
+            "Coordinates and intensities must be the same length"
+        );
+        if coordinates.windows(2).all(|w| w[0] <= w[1]) {
+            Self {
+                coordinates,
+                intensities,
+                _c: PhantomData,
+            }
+        } else {
+            coordinates.into_iter().zip(intensities).collect()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.coordinates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coordinates.is_empty()
+    }
+
+    pub fn coordinates(&self) -> &[f64] {
+        &self.coordinates
+    }
+
+    pub fn intensities(&self) -> &[f32] {
+        &self.intensities
+    }
+
+    pub fn intensities_mut(&mut self) -> &mut [f32] {
+        &mut self.intensities
+    }
+
+    /// Iterate over `(coordinate, intensity)` pairs
+    pub fn iter(&self) -> impl Iterator<Item = (f64, f32)> + '_ {
+        self.coordinates
+            .iter()
+            .copied()
+            .zip(self.intensities.iter().copied())
+    }
+
+    /// The intensity of the signal at `coordinate`, linearly interpolated between the
+    /// nearest samples, or `None` if `coordinate` lies outside the sampled range.
+    pub fn intensity_at(&self, coordinate: f64) -> Option<f32> {
+        let (first, last) = match (self.coordinates.first(), self.coordinates.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return None,
+        };
+        if coordinate < first || coordinate > last {
+            return None;
+        }
+        Some(self.interpolate(coordinate) as f32)
+    }
+
+    fn interpolate(&self, coordinate: f64) -> f64 {
+        let i = self.coordinates.partition_point(|x| *x < coordinate);
+        if i == 0 {
+            return self.intensities[0] as f64;
+        }
+        if i == self.len() {
+            return self.intensities[i - 1] as f64;
+        }
+        let (x0, x1) = (self.coordinates[i - 1], self.coordinates[i]);
+        let (y0, y1) = (self.intensities[i - 1] as f64, self.intensities[i] as f64);
+        if x1 == x0 {
+            y1
+        } else {
+            y0 + (y1 - y0) * (coordinate - x0) / (x1 - x0)
+        }
+    }
+
+    /// Resolve `range` against the sampled range, returning `None` if they do not overlap
+    fn clamp_range<R: RangeBounds<f64>>(&self, range: &R) -> Option<(f64, f64)> {
+        let first = *self.coordinates.first()?;
+        let last = *self.coordinates.last()?;
+        let start = match range.start_bound() {
+            Bound::Included(x) | Bound::Excluded(x) => x.max(first),
+            Bound::Unbounded => first,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(x) | Bound::Excluded(x) => x.min(last),
+            Bound::Unbounded => last,
+        };
+        if start > end {
+            None
+        } else {
+            Some((start, end))
+        }
+    }
+
+    /// Integrate the signal over `range` using the trapezoid rule, interpolating
+    /// the intensity at the edges of the range. The parts of `range` outside the
+    /// sampled range contribute nothing.
+    pub fn integrate<R: RangeBounds<f64>>(&self, range: R) -> f64 {
+        let (start, end) = match self.clamp_range(&range) {
+            Some(bounds) => bounds,
+            None => return 0.0,
+        };
+        let lo = self.coordinates.partition_point(|x| *x <= start);
+        let hi = self.coordinates.partition_point(|x| *x < end);
+
+        let mut total = 0.0;
+        let mut prev = (start, self.interpolate(start));
+        for i in lo..hi {
+            let point = (self.coordinates[i], self.intensities[i] as f64);
+            total += (point.0 - prev.0) * (point.1 + prev.1) / 2.0;
+            prev = point;
+        }
+        total += (end - prev.0) * (self.interpolate(end) + prev.1) / 2.0;
+        total
+    }
+
+    /// Find the sample with the greatest intensity within `range`, returning its
+    /// `(coordinate, intensity)` pair, or `None` if there are no samples in `range`.
+    pub fn max_in<R: RangeBounds<f64>>(&self, range: R) -> Option<(f64, f32)> {
+        self.iter().filter(|(x, _)| range.contains(x)).fold(
+            None,
+            |best: Option<(f64, f32)>, (x, y)| match best {
+                Some((_, best_y)) if best_y >= y => best,
+                _ => Some((x, y)),
+            },
+        )
+    }
+}
+
+/// An empty signal spans `NaN`, overlapping nothing
+impl<C> Span1D<C> for ProfileSignal<C> {
+    fn start(&self) -> f64 {
+        self.coordinates.first().copied().unwrap_or(f64::NAN)
+    }
+
+    fn end(&self) -> f64 {
+        self.coordinates.last().copied().unwrap_or(f64::NAN)
+    }
+}
+
+impl<C> FromIterator<(f64, f32)> for ProfileSignal<C> {
+    fn from_iter<T: IntoIterator<Item = (f64, f32)>>(iter: T) -> Self {
+        let mut points: Vec<(f64, f32)> = iter.into_iter().collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (coordinates, intensities) = points.into_iter().unzip();
+        Self {
+            coordinates,
+            intensities,
+            _c: PhantomData,
+        }
+    }
+}
+
+/// A profile mode spectrum sampled along m/z
+pub type MZProfile = ProfileSignal<MZ>;
+
+/// A chromatogram sampled along time
+pub type Chromatogram = ProfileSignal<Time>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CoordinateRange;
+
+    #[test]
+    fn test_profile_signal() {
+        let signal = MZProfile::new(vec![100.0, 101.0, 102.0, 103.0], vec![0.0, 10.0, 20.0, 0.0]);
+        assert_eq!(signal.intensity_at(101.5), Some(15.0));
+        assert_eq!(signal.intensity_at(103.0), Some(0.0));
+        assert_eq!(signal.intensity_at(99.0), None);
+
+        assert!((signal.integrate(..) - 30.0).abs() < 1e-9);
+        assert!((signal.integrate(100.5..101.5) - 10.0).abs() < 1e-9);
+        assert!(
+            (signal.integrate(CoordinateRange::<MZ>::new(Some(102.0), None)) - 10.0).abs() < 1e-9
+        );
+        assert_eq!(signal.integrate(200.0..300.0), 0.0);
+
+        assert_eq!(signal.max_in(..), Some((102.0, 20.0)));
+        assert_eq!(signal.max_in(100.0..101.5), Some((101.0, 10.0)));
+        assert_eq!(signal.max_in(101.1..101.9), None);
+
+        let unsorted = MZProfile::new(vec![101.0, 100.0], vec![5.0, 1.0]);
+        assert_eq!(unsorted.coordinates(), &[100.0, 101.0]);
+        assert_eq!(unsorted.intensities(), &[1.0, 5.0]);
+    }
+}
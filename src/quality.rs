@@ -0,0 +1,140 @@
+//! Summary metrics describing the quality of a single spectrum.
+//!
+//! [`SpectrumQuality`] bundles several cheap metrics computed in one pass over a
+//! coordinate-sorted peak list, suitable for deciding whether a spectrum should be
+//! kept before doing anything more expensive with it.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::coordinate::{CoordinateLike, IndexedCoordinate};
+use crate::peak::IntensityMeasurement;
+use crate::peak_set::PeakSetVec;
+
+/// Quality metrics for a single spectrum
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpectrumQuality {
+    /// The number of peaks in the spectrum
+    pub peak_count: usize,
+    /// The total ion current, the sum of all peak intensities
+    pub total_ion_current: f64,
+    /// The coordinate of the most intense peak
+    pub base_peak_coordinate: f64,
+    /// The intensity of the most intense peak
+    pub base_peak_intensity: f32,
+    /// The fraction of the total ion current contributed by the most intense peak.
+    /// Values near 1 indicate a spectrum dominated by a single signal.
+    pub base_peak_fraction: f64,
+    /// The distance between the first and last peak
+    pub coordinate_span: f64,
+    /// How evenly spaced the peaks are across [`SpectrumQuality::coordinate_span`], from
+    /// 1 for perfectly uniform spacing towards 0 as the peaks bunch together. This is
+    /// `1 / (1 + cv)` where `cv` is the coefficient of variation of the spacing between
+    /// consecutive peaks.
+    pub coverage_uniformity: f64,
+    /// An estimate of the high-frequency noise level, the mean absolute difference between
+    /// the intensities of consecutive peaks divided by `√2`
+    pub noise_estimate: f64,
+}
+
+impl SpectrumQuality {
+    /// Compute the quality metrics of `peaks`, which must be sorted by their coordinate
+    pub fn compute<C, P, I>(peaks: I) -> Self
+    where
+        P: CoordinateLike<C> + IntensityMeasurement,
+        I: IntoIterator<Item = P>,
+    {
+        let mut quality = Self::default();
+        let mut first: Option<f64> = None;
+        let mut prev: Option<(f64, f32)> = None;
+
+        let mut spacing_mean = 0.0;
+        let mut spacing_m2 = 0.0;
+        let mut abs_diff_total = 0.0;
+
+        for peak in peaks {
+            let x = peak.coordinate();
+            let y = peak.intensity();
+            quality.peak_count += 1;
+            quality.total_ion_current += y as f64;
+            if quality.peak_count == 1 || y > quality.base_peak_intensity {
+                quality.base_peak_intensity = y;
+                quality.base_peak_coordinate = x;
+            }
+            first.get_or_insert(x);
+
+            if let Some((prev_x, prev_y)) = prev {
+                // Welford's online update of the spacing mean and variance
+                let k = (quality.peak_count - 1) as f64;
+                let spacing = x - prev_x;
+                let delta = spacing - spacing_mean;
+                spacing_mean += delta / k;
+                spacing_m2 += delta * (spacing - spacing_mean);
+                abs_diff_total += (y - prev_y).abs() as f64;
+            }
+            prev = Some((x, y));
+        }
+
+        if let (Some(first), Some((last, _))) = (first, prev) {
+            quality.coordinate_span = last - first;
+        }
+        if quality.total_ion_current > 0.0 {
+            quality.base_peak_fraction =
+                quality.base_peak_intensity as f64 / quality.total_ion_current;
+        }
+        let gaps = quality.peak_count.saturating_sub(1);
+        if gaps > 0 {
+            let spacing_sd = (spacing_m2 / gaps as f64).sqrt();
+            quality.coverage_uniformity = if spacing_mean > 0.0 {
+                1.0 / (1.0 + spacing_sd / spacing_mean)
+            } else {
+                0.0
+            };
+            quality.noise_estimate = abs_diff_total / gaps as f64 / std::f64::consts::SQRT_2;
+        }
+        quality
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peak_count == 0
+    }
+}
+
+impl<P: IndexedCoordinate<C> + IntensityMeasurement, C> PeakSetVec<P, C> {
+    /// Compute the [`SpectrumQuality`] of this peak list
+    pub fn quality(&self) -> SpectrumQuality {
+        SpectrumQuality::compute(self.iter())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_data;
+    use crate::{CentroidPeak, PeakSet};
+
+    #[test]
+    fn test_spectrum_quality() -> std::io::Result<()> {
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 10.0, 0),
+            CentroidPeak::new(101.0, 30.0, 0),
+            CentroidPeak::new(102.0, 10.0, 0),
+            CentroidPeak::new(103.0, 50.0, 0),
+        ]);
+        let quality = peaks.quality();
+        assert_eq!(quality.peak_count, 4);
+        assert_eq!(quality.total_ion_current, 100.0);
+        assert_eq!(quality.base_peak_coordinate, 103.0);
+        assert!((quality.base_peak_fraction - 0.5).abs() < 1e-9);
+        assert_eq!(quality.coordinate_span, 3.0);
+        assert!((quality.coverage_uniformity - 1.0).abs() < 1e-9);
+        assert!((quality.noise_estimate - 80.0 / 3.0 / 2f64.sqrt()).abs() < 1e-9);
+
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let quality = peaks.quality();
+        assert_eq!(quality.peak_count, peaks.len());
+        assert!(quality.coverage_uniformity > 0.0 && quality.coverage_uniformity < 1.0);
+
+        assert!(PeakSet::empty().quality().is_empty());
+        Ok(())
+    }
+}
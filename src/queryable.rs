@@ -0,0 +1,146 @@
+//! A minimal, read-only interface for searching a sorted collection of peaks.
+//!
+//! [`PeakCollection`] requires its implementors to hand out contiguous slices of peaks,
+//! which a storage engine that keeps its peaks in columns, on disk, or behind a cache cannot
+//! always do. [`PeakQueryable`] asks only for random access and coordinate search, so such
+//! an engine can implement it and be passed to the analysis functions of this crate, like
+//! [`match_peaks`](crate::matching::match_peaks), without first converting its peaks into a
+//! [`PeakSet`](crate::PeakSet).
+//!
+//! Every container and view in this crate implements [`PeakQueryable`], as do plain slices,
+//! which are assumed to be sorted by coordinate as in [`search`](crate::search). Its methods
+//! share their names with those of [`PeakCollection`], so bring only one of the two traits
+//! into scope when calling them as methods.
+use std::ops::Range;
+
+use crate::coordinate::{CoordinateLike, IndexedCoordinate};
+use crate::mass_error::Tolerance;
+use crate::peak_set::{PeakCollection, PeakSetVec, PeakSetView};
+use crate::search::{indices_between, nearest_in_tolerance};
+
+#[cfg(feature = "indexes")]
+use crate::frozen::FrozenPeakSet;
+#[cfg(feature = "indexes")]
+use crate::storage::{PeakStorage, StoredPeakSet};
+
+/// Random access to, and coordinate search over, a collection of peaks sorted by
+/// coordinate `C`
+pub trait PeakQueryable<C> {
+    /// The type of peak in the collection
+    type Peak: CoordinateLike<C>;
+
+    /// The number of peaks in the collection
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The peak at position `i`, or `None` if `i` is out of bounds
+    fn get(&self, i: usize) -> Option<&Self::Peak>;
+
+    /// The position of the peak nearest to `query` within `tolerance`, or `None` if there
+    /// is none
+    fn search(&self, query: f64, tolerance: Tolerance) -> Option<usize>;
+
+    /// The positions of every peak between `low` and `high`, widened by `tolerance`
+    fn between(&self, low: f64, high: f64, tolerance: Tolerance) -> Range<usize>;
+}
+
+impl<C, P: CoordinateLike<C>> PeakQueryable<C> for [P] {
+    type Peak = P;
+
+    fn len(&self) -> usize {
+        <[P]>::len(self)
+    }
+
+    fn get(&self, i: usize) -> Option<&P> {
+        <[P]>::get(self, i)
+    }
+
+    fn search(&self, query: f64, tolerance: Tolerance) -> Option<usize> {
+        nearest_in_tolerance(self, query, tolerance)
+    }
+
+    fn between(&self, low: f64, high: f64, tolerance: Tolerance) -> Range<usize> {
+        indices_between(self, low, high, tolerance)
+    }
+}
+
+/// Implement [`PeakQueryable`] for a [`PeakCollection`] of peaks `P` in coordinate system `C`
+macro_rules! impl_queryable_for_collection {
+    ($t:ty, $($args:tt)+) => {
+        impl<$($args)+> PeakQueryable<C> for $t {
+            type Peak = P;
+
+            fn len(&self) -> usize {
+                <Self as PeakCollection<P, C>>::len(self)
+            }
+
+            fn get(&self, i: usize) -> Option<&P> {
+                (i < PeakQueryable::len(self)).then(|| self.get_item(i))
+            }
+
+            fn search(&self, query: f64, tolerance: Tolerance) -> Option<usize> {
+                <Self as PeakCollection<P, C>>::search(self, query, tolerance)
+            }
+
+            fn between(&self, low: f64, high: f64, tolerance: Tolerance) -> Range<usize> {
+                let peaks = self.get_slice(0..PeakQueryable::len(self));
+                indices_between(peaks, low, high, tolerance)
+            }
+        }
+    };
+}
+
+impl_queryable_for_collection!(PeakSetVec<P, C>, P: IndexedCoordinate<C>, C);
+impl_queryable_for_collection!(PeakSetView<'a, P, C>, 'a, P: IndexedCoordinate<C>, C);
+#[cfg(feature = "indexes")]
+impl_queryable_for_collection!(FrozenPeakSet<P, C>, P: IndexedCoordinate<C>, C);
+#[cfg(feature = "indexes")]
+impl_queryable_for_collection!(
+    StoredPeakSet<P, C, S>, P: IndexedCoordinate<C>, C, S: PeakStorage<P>
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::coordinate::MZ;
+    use crate::{CentroidPeak, PeakSet};
+
+    /// Check `peaks` against the slice it should answer the same as
+    fn check_queryable<Q: PeakQueryable<MZ, Peak = CentroidPeak> + ?Sized>(
+        peaks: &Q,
+        expected: &[CentroidPeak],
+    ) {
+        let tol = Tolerance::Da(0.5);
+        assert_eq!(peaks.len(), expected.len());
+        assert_eq!(peaks.get(1), expected.get(1));
+        assert!(peaks.get(expected.len()).is_none());
+        assert_eq!(peaks.search(200.2, tol), Some(1));
+        assert_eq!(peaks.search(250.0, tol), None);
+        assert_eq!(peaks.between(150.0, 300.0, tol), 1..3);
+        assert_eq!(peaks.between(500.0, 600.0, tol), 4..4);
+    }
+
+    #[test]
+    fn test_peak_queryable() {
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 1.0, 0),
+            CentroidPeak::new(200.0, 2.0, 0),
+            CentroidPeak::new(300.0, 3.0, 0),
+            CentroidPeak::new(400.0, 4.0, 0),
+        ]);
+        check_queryable(&peaks, &peaks);
+        check_queryable(&peaks[..], &peaks);
+        #[cfg(feature = "indexes")]
+        check_queryable(&FrozenPeakSet::new(peaks.clone()), &peaks);
+
+        let empty: PeakSet = PeakSet::empty();
+        assert!(PeakQueryable::is_empty(&empty));
+        assert_eq!(
+            PeakQueryable::between(&empty, 0.0, 1.0, Tolerance::Da(1.0)),
+            0..0
+        );
+    }
+}
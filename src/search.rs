@@ -0,0 +1,124 @@
+//! Binary search over plain slices of peaks.
+//!
+//! [`PeakCollection`](crate::PeakCollection) provides these searches for its implementors,
+//! but code which keeps peaks in a plain `Vec` or slice can use these functions directly.
+//! Every function assumes the slice is sorted in ascending order by coordinate `C`.
+//!
+//! When a peak type is located in more than one coordinate system, e.g.
+//! [`DeconvolutedPeak`](crate::DeconvolutedPeak), name the system to search by, as in
+//! `search::nearest::<Mass, _>(&peaks, 1000.0)`.
+use std::ops::Range;
+
+use crate::coordinate::CoordinateLike;
+use crate::mass_error::Tolerance;
+
+/// The position of the first item whose coordinate is not less than `query`, or the length
+/// of `items` if there is none
+pub fn lower_bound<C, T: CoordinateLike<C>>(items: &[T], query: f64) -> usize {
+    items.partition_point(|p| p.coordinate() < query)
+}
+
+/// The position of the first item whose coordinate is greater than `query`, or the length
+/// of `items` if there is none
+pub fn upper_bound<C, T: CoordinateLike<C>>(items: &[T], query: f64) -> usize {
+    items.partition_point(|p| p.coordinate() <= query)
+}
+
+/// The position of the item whose coordinate is closest to `query`, preferring the earlier
+/// item on a tie, or `None` if `items` is empty
+pub fn nearest<C, T: CoordinateLike<C>>(items: &[T], query: f64) -> Option<usize> {
+    if items.is_empty() {
+        return None;
+    }
+    let i = lower_bound(items, query);
+    if i == 0 {
+        return Some(0);
+    }
+    if i == items.len() {
+        return Some(i - 1);
+    }
+    let before = query - items[i - 1].coordinate();
+    let after = items[i].coordinate() - query;
+    Some(if before <= after { i - 1 } else { i })
+}
+
+/// The positions of every item whose coordinate is within `tolerance` of `query`
+pub fn indices_in_tolerance<C, T: CoordinateLike<C>>(
+    items: &[T],
+    query: f64,
+    tolerance: Tolerance,
+) -> Range<usize> {
+    indices_between(items, query, query, tolerance)
+}
+
+/// The positions of every item whose coordinate is between `low` and `high`, widened by
+/// `tolerance`
+pub fn indices_between<C, T: CoordinateLike<C>>(
+    items: &[T],
+    low: f64,
+    high: f64,
+    tolerance: Tolerance,
+) -> Range<usize> {
+    let lower = tolerance.bounds(low).0;
+    let upper = tolerance.bounds(high).1;
+    let start = lower_bound(items, lower);
+    start..(start + upper_bound(&items[start..], upper))
+}
+
+/// The position of the item closest to `query` within `tolerance`, or `None` if there is none
+pub fn nearest_in_tolerance<C, T: CoordinateLike<C>>(
+    items: &[T],
+    query: f64,
+    tolerance: Tolerance,
+) -> Option<usize> {
+    let range = indices_in_tolerance(items, query, tolerance);
+    let start = range.start;
+    nearest(&items[range], query).map(|i| start + i)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::coordinate::{Mass, MZ};
+    use crate::{test_data, CentroidPeak, DeconvolutedPeak, PeakCollection};
+
+    #[test]
+    fn test_slice_search() -> std::io::Result<()> {
+        let peaks: Vec<CentroidPeak> = test_data::read_peaks_from_file("./test/data/test.txt")?
+            .into_iter()
+            .collect();
+
+        let tol = Tolerance::PPM(10.0);
+        let set = crate::PeakSet::wrap(peaks.clone());
+        assert_eq!(
+            nearest_in_tolerance(&peaks, 773.4414, tol),
+            set.search(773.4414, tol)
+        );
+        let range = indices_in_tolerance(&peaks, 773.4414, Tolerance::Da(2.0));
+        assert_eq!(
+            range.len(),
+            set.all_peaks_for(773.4414, Tolerance::Da(2.0)).len()
+        );
+        let range = indices_between(&peaks, 700.0, 800.0, Tolerance::Da(1.0));
+        assert_eq!(
+            range.len(),
+            set.between(700.0, 800.0, Tolerance::Da(1.0)).len()
+        );
+        assert_eq!(nearest(&peaks, 0.0), Some(0));
+        assert_eq!(nearest(&peaks, 1e6), Some(peaks.len() - 1));
+        assert_eq!(nearest(&peaks, 773.4414), Some(300));
+
+        let mz = peaks[10].mz;
+        assert_eq!(lower_bound(&peaks, mz), 10);
+        assert_eq!(upper_bound(&peaks, mz), 11);
+        assert_eq!(nearest::<MZ, CentroidPeak>(&[], 1.0), None);
+
+        let masses = vec![
+            DeconvolutedPeak::new(1000.0, 1.0, 2, 0),
+            DeconvolutedPeak::new(1002.0, 1.0, 1, 1),
+        ];
+        assert_eq!(nearest::<Mass, _>(&masses, 1001.5), Some(1));
+        assert_eq!(nearest::<Mass, _>(&masses, 1001.0), Some(0));
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+//! Fit analytical peak shape models to sampled signals.
+//!
+//! A peak observed as a series of `(coordinate, intensity)` samples, whether a profile
+//! mode m/z peak or a chromatographic elution profile, can be summarized by a model
+//! whose apex and area are less sensitive to noise and sampling than those of the raw
+//! samples. This module provides three common models implementing [`PeakShape`]:
+//!
+//! - [`GaussianShape`], a symmetric peak, fit with Caruana's log-parabola method
+//! - [`LorentzianShape`], a symmetric peak with heavy tails, fit with a parabola on the
+//!   reciprocal intensities
+//! - [`EMGShape`], an exponentially modified Gaussian for tailing peaks, fit by the method
+//!   of moments
+//!
+//! Each fit returns a [`FitResult`] carrying goodness-of-fit statistics.
+use std::f64::consts::{PI, SQRT_2};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::feature::Feature;
+use crate::profile::ProfileSignal;
+
+/// A model of the shape of a single peak
+pub trait PeakShape: Sized {
+    /// The model's predicted intensity at `x`
+    fn evaluate(&self, x: f64) -> f64;
+
+    /// The coordinate where the model reaches its maximum
+    fn apex(&self) -> f64;
+
+    /// The total area under the model
+    fn area(&self) -> f64;
+
+    /// Estimate the model parameters from `(x, intensity)` samples, returning `None`
+    /// if there are too few samples or the samples do not describe a peak.
+    fn fit_model(points: &[(f64, f64)]) -> Option<Self>;
+
+    /// Fit the model to `(x, intensity)` samples and measure how well it describes them
+    fn fit(points: &[(f64, f64)]) -> Option<FitResult<Self>> {
+        let model = Self::fit_model(points)?;
+        Some(FitResult::new(model, points))
+    }
+}
+
+/// A fitted [`PeakShape`] model along with measures of its agreement with the data
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FitResult<M> {
+    pub model: M,
+    /// The sum of the squared differences between the samples and the model
+    pub residual_sum_of_squares: f64,
+    /// The coefficient of determination, the fraction of the variance in the
+    /// sampled intensities explained by the model
+    pub r_squared: f64,
+}
+
+impl<M: PeakShape> FitResult<M> {
+    /// Score `model` against `points`
+    pub fn new(model: M, points: &[(f64, f64)]) -> Self {
+        let n = points.len().max(1) as f64;
+        let mean = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let mut residual_sum_of_squares = 0.0;
+        let mut total_sum_of_squares = 0.0;
+        for (x, y) in points {
+            residual_sum_of_squares += (y - model.evaluate(*x)).powi(2);
+            total_sum_of_squares += (y - mean).powi(2);
+        }
+        let r_squared = if total_sum_of_squares > 0.0 {
+            1.0 - residual_sum_of_squares / total_sum_of_squares
+        } else {
+            0.0
+        };
+        Self {
+            model,
+            residual_sum_of_squares,
+            r_squared,
+        }
+    }
+
+    pub fn evaluate(&self, x: f64) -> f64 {
+        self.model.evaluate(x)
+    }
+
+    pub fn apex(&self) -> f64 {
+        self.model.apex()
+    }
+
+    pub fn area(&self) -> f64 {
+        self.model.area()
+    }
+}
+
+/// Solve the weighted least squares problem `y ~ a + b * (x - shift) + c * (x - shift) ^ 2`,
+/// returning `(a, b, c)`
+fn fit_parabola<I: Iterator<Item = (f64, f64, f64)>>(
+    points: I,
+    shift: f64,
+) -> Option<(f64, f64, f64)> {
+    // Accumulate the normal equations
+    let mut s = [0.0; 5];
+    let mut t = [0.0; 3];
+    for (x, y, w) in points {
+        let x = x - shift;
+        let mut xk = 1.0;
+        for (k, sk) in s.iter_mut().enumerate() {
+            *sk += w * xk;
+            if k < 3 {
+                t[k] += w * y * xk;
+            }
+            xk *= x;
+        }
+    }
+    let m = [[s[0], s[1], s[2]], [s[1], s[2], s[3]], [s[2], s[3], s[4]]];
+    let det = |m: &[[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let d = det(&m);
+    if d.abs() < f64::EPSILON * s[4].abs().max(1.0) {
+        return None;
+    }
+    // Cramer's rule
+    let mut coefs = [0.0; 3];
+    for (j, c) in coefs.iter_mut().enumerate() {
+        let mut mj = m;
+        for (i, row) in mj.iter_mut().enumerate() {
+            row[j] = t[i];
+        }
+        *c = det(&mj) / d;
+    }
+    Some((coefs[0], coefs[1], coefs[2]))
+}
+
+fn weighted_center(points: &[(f64, f64)]) -> f64 {
+    let total: f64 = points.iter().map(|(_, y)| y.max(0.0)).sum();
+    if total > 0.0 {
+        points.iter().map(|(x, y)| x * y.max(0.0)).sum::<f64>() / total
+    } else {
+        0.0
+    }
+}
+
+/// A Gaussian peak, `amplitude * exp(-(x - center) ^ 2 / (2 * sigma ^ 2))`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GaussianShape {
+    pub center: f64,
+    pub amplitude: f64,
+    pub sigma: f64,
+}
+
+impl GaussianShape {
+    pub fn new(center: f64, amplitude: f64, sigma: f64) -> Self {
+        Self {
+            center,
+            amplitude,
+            sigma,
+        }
+    }
+
+    /// The full width of the peak at half of its maximum
+    pub fn full_width_at_half_max(&self) -> f64 {
+        2.0 * (2.0 * 2f64.ln()).sqrt() * self.sigma
+    }
+}
+
+impl PeakShape for GaussianShape {
+    fn evaluate(&self, x: f64) -> f64 {
+        self.amplitude * (-(x - self.center).powi(2) / (2.0 * self.sigma.powi(2))).exp()
+    }
+
+    fn apex(&self) -> f64 {
+        self.center
+    }
+
+    fn area(&self) -> f64 {
+        self.amplitude * self.sigma * (2.0 * PI).sqrt()
+    }
+
+    fn fit_model(points: &[(f64, f64)]) -> Option<Self> {
+        let positive = points.iter().filter(|(_, y)| *y > 0.0);
+        if positive.clone().count() < 3 {
+            return None;
+        }
+        let shift = weighted_center(points);
+        // Weighting by the squared intensity counteracts the amplification of noise
+        // in low intensity samples by the logarithm
+        let (a, b, c) = fit_parabola(positive.map(|(x, y)| (*x, y.ln(), y * y)), shift)?;
+        if c >= 0.0 {
+            return None;
+        }
+        let sigma = (-1.0 / (2.0 * c)).sqrt();
+        let offset = -b / (2.0 * c);
+        let amplitude = (a - b * b / (4.0 * c)).exp();
+        Some(Self::new(shift + offset, amplitude, sigma))
+    }
+}
+
+/// A Lorentzian (Cauchy) peak, `amplitude * gamma ^ 2 / ((x - center) ^ 2 + gamma ^ 2)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LorentzianShape {
+    pub center: f64,
+    pub amplitude: f64,
+    /// The half width of the peak at half of its maximum
+    pub gamma: f64,
+}
+
+impl LorentzianShape {
+    pub fn new(center: f64, amplitude: f64, gamma: f64) -> Self {
+        Self {
+            center,
+            amplitude,
+            gamma,
+        }
+    }
+
+    /// The full width of the peak at half of its maximum
+    pub fn full_width_at_half_max(&self) -> f64 {
+        2.0 * self.gamma
+    }
+}
+
+impl PeakShape for LorentzianShape {
+    fn evaluate(&self, x: f64) -> f64 {
+        let g2 = self.gamma.powi(2);
+        self.amplitude * g2 / ((x - self.center).powi(2) + g2)
+    }
+
+    fn apex(&self) -> f64 {
+        self.center
+    }
+
+    fn area(&self) -> f64 {
+        self.amplitude * self.gamma * PI
+    }
+
+    fn fit_model(points: &[(f64, f64)]) -> Option<Self> {
+        let positive = points.iter().filter(|(_, y)| *y > 0.0);
+        if positive.clone().count() < 3 {
+            return None;
+        }
+        let shift = weighted_center(points);
+        // The reciprocal of a Lorentzian is a parabola. Weighting by the squared intensity
+        // keeps the tails, where the reciprocal is large and noisy, from dominating.
+        let (a, b, c) = fit_parabola(positive.map(|(x, y)| (*x, 1.0 / y, y * y)), shift)?;
+        if c <= 0.0 {
+            return None;
+        }
+        let offset = -b / (2.0 * c);
+        let minimum = a - b * b / (4.0 * c);
+        if minimum <= 0.0 {
+            return None;
+        }
+        let amplitude = 1.0 / minimum;
+        let gamma = (minimum / c).sqrt();
+        Some(Self::new(shift + offset, amplitude, gamma))
+    }
+}
+
+/// The complementary error function, accurate to about `1e-7`
+/// (Abramowitz and Stegun 7.1.26)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let r = poly * (-z * z).exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+/// Compute `exp(a) * erfc(x)` without overflowing when `a` is large and `erfc(x)` is tiny
+fn exp_erfc(a: f64, x: f64) -> f64 {
+    if x < 5.0 {
+        a.exp() * erfc(x)
+    } else {
+        // Asymptotic expansion of erfc for large arguments
+        let x2 = x * x;
+        (a - x2).exp() / (x * PI.sqrt()) * (1.0 - 1.0 / (2.0 * x2) + 3.0 / (4.0 * x2 * x2))
+    }
+}
+
+/// An exponentially modified Gaussian peak, a Gaussian with mean `mu` and standard deviation
+/// `sigma` convolved with an exponential decay with time constant `tau`, scaled to enclose
+/// `total_area`. This describes the tailing peaks common in chromatography.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EMGShape {
+    pub mu: f64,
+    pub sigma: f64,
+    pub tau: f64,
+    pub total_area: f64,
+}
+
+impl EMGShape {
+    pub fn new(mu: f64, sigma: f64, tau: f64, total_area: f64) -> Self {
+        Self {
+            mu,
+            sigma,
+            tau,
+            total_area,
+        }
+    }
+}
+
+impl PeakShape for EMGShape {
+    fn evaluate(&self, x: f64) -> f64 {
+        if self.tau <= 0.0 {
+            return GaussianShape::new(
+                self.mu,
+                self.total_area / (self.sigma * (2.0 * PI).sqrt()),
+                self.sigma,
+            )
+            .evaluate(x);
+        }
+        let lambda = 1.0 / self.tau;
+        let s2 = self.sigma.powi(2);
+        let a = lambda / 2.0 * (2.0 * self.mu + lambda * s2 - 2.0 * x);
+        let z = (self.mu + lambda * s2 - x) / (SQRT_2 * self.sigma);
+        self.total_area * lambda / 2.0 * exp_erfc(a, z)
+    }
+
+    fn apex(&self) -> f64 {
+        // The mode has no closed form, so search for it between the Gaussian
+        // center and a few widths past it with a golden section search.
+        let phi = (5f64.sqrt() - 1.0) / 2.0;
+        let mut lo = self.mu - self.sigma;
+        let mut hi = self.mu + self.tau + 3.0 * self.sigma;
+        for _ in 0..100 {
+            let m1 = hi - phi * (hi - lo);
+            let m2 = lo + phi * (hi - lo);
+            if self.evaluate(m1) < self.evaluate(m2) {
+                lo = m1;
+            } else {
+                hi = m2;
+            }
+        }
+        (lo + hi) / 2.0
+    }
+
+    fn area(&self) -> f64 {
+        self.total_area
+    }
+
+    fn fit_model(points: &[(f64, f64)]) -> Option<Self> {
+        if points.len() < 3 {
+            return None;
+        }
+        let mut total_area = 0.0;
+        for w in points.windows(2) {
+            total_area += (w[1].0 - w[0].0) * (w[1].1.max(0.0) + w[0].1.max(0.0)) / 2.0;
+        }
+        let weight: f64 = points.iter().map(|(_, y)| y.max(0.0)).sum();
+        if weight <= 0.0 || total_area <= 0.0 {
+            return None;
+        }
+        let mean = weighted_center(points);
+        let moment = |k: i32| {
+            points
+                .iter()
+                .map(|(x, y)| (x - mean).powi(k) * y.max(0.0))
+                .sum::<f64>()
+                / weight
+        };
+        let variance = moment(2);
+        let third = moment(3).max(0.0);
+        // For an EMG, the third central moment is 2 * tau ^ 3 and the variance is sigma ^ 2 + tau ^ 2
+        let tau = (third / 2.0).cbrt().min(variance.sqrt() * 0.99);
+        let sigma = (variance - tau * tau).sqrt();
+        if sigma.is_nan() || sigma <= 0.0 {
+            return None;
+        }
+        Some(Self::new(mean - tau, sigma, tau, total_area))
+    }
+}
+
+impl<C> ProfileSignal<C> {
+    /// Fit a [`PeakShape`] model to this signal
+    pub fn fit_shape<M: PeakShape>(&self) -> Option<FitResult<M>> {
+        let points: Vec<(f64, f64)> = self.iter().map(|(x, y)| (x, y as f64)).collect();
+        M::fit(&points)
+    }
+}
+
+impl<X, Y> Feature<X, Y> {
+    /// Fit a [`PeakShape`] model to this feature's intensity over its time dimension,
+    /// whose [`PeakShape::apex`] and [`PeakShape::area`] may be used in place of the
+    /// feature's own.
+    pub fn fit_shape<M: PeakShape>(&self) -> Option<FitResult<M>> {
+        let points: Vec<(f64, f64)> = self.iter().map(|(_, y, z)| (*y, *z as f64)).collect();
+        M::fit(&points)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::feature::LCMSFeature;
+    use crate::profile::MZProfile;
+
+    fn sample<M: PeakShape>(model: &M, start: f64, end: f64, n: usize) -> Vec<(f64, f64)> {
+        (0..n)
+            .map(|i| {
+                let x = start + (end - start) * i as f64 / (n - 1) as f64;
+                (x, model.evaluate(x))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gaussian() {
+        let truth = GaussianShape::new(500.25, 1e4, 0.01);
+        let fit = GaussianShape::fit(&sample(&truth, 500.2, 500.3, 21)).unwrap();
+        assert!((fit.apex() - 500.25).abs() < 1e-6);
+        assert!((fit.model.sigma - 0.01).abs() < 1e-6);
+        assert!((fit.area() - truth.area()).abs() / truth.area() < 1e-4);
+        assert!(fit.r_squared > 0.999);
+
+        let signal: MZProfile = sample(&truth, 500.2, 500.3, 21)
+            .into_iter()
+            .map(|(x, y)| (x, y as f32))
+            .collect();
+        let fit: FitResult<GaussianShape> = signal.fit_shape().unwrap();
+        assert!((fit.apex() - 500.25).abs() < 1e-4);
+
+        assert!(GaussianShape::fit(&[(1.0, 1.0), (2.0, 2.0)]).is_none());
+    }
+
+    #[test]
+    fn test_lorentzian() {
+        let truth = LorentzianShape::new(12.0, 500.0, 0.3);
+        let fit = LorentzianShape::fit(&sample(&truth, 10.0, 14.0, 41)).unwrap();
+        assert!((fit.apex() - 12.0).abs() < 1e-6);
+        assert!((fit.model.gamma - 0.3).abs() < 1e-6);
+        assert!((fit.model.amplitude - 500.0).abs() < 1e-3);
+        assert!(fit.r_squared > 0.999);
+    }
+
+    #[test]
+    fn test_emg() {
+        let truth = EMGShape::new(10.0, 0.2, 0.3, 1000.0);
+        let points = sample(&truth, 8.0, 16.0, 400);
+        let total: f64 = points
+            .windows(2)
+            .map(|w| (w[1].0 - w[0].0) * (w[1].1 + w[0].1) / 2.0)
+            .sum();
+        assert!((total - 1000.0).abs() < 1.0);
+        assert!(truth.apex() > truth.mu);
+
+        let fit = EMGShape::fit(&points).unwrap();
+        assert!((fit.model.tau - 0.3).abs() < 0.02);
+        assert!((fit.model.sigma - 0.2).abs() < 0.02);
+        assert!((fit.area() - 1000.0).abs() < 1.0);
+        assert!((fit.apex() - truth.apex()).abs() < 0.02);
+        assert!(fit.r_squared > 0.99);
+
+        let feature: LCMSFeature = points.iter().map(|(x, y)| (500.0, *x, *y as f32)).collect();
+        let fit: FitResult<EMGShape> = feature.fit_shape().unwrap();
+        assert!((fit.area() - 1000.0).abs() < 1.0);
+    }
+}
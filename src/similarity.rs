@@ -0,0 +1,191 @@
+//! Score the similarity of two spectra.
+//!
+//! Each score pairs up the peaks of the two spectra one-to-one within a [`Tolerance`] with
+//! [`match_peaks`] and compares the intensities of the pairs:
+//!
+//! - [`dot_product`] and [`cosine_similarity`], the sum of the products of the paired
+//!   intensities, without and with normalization by the magnitude of each spectrum
+//! - [`modified_cosine_similarity`], which also pairs peaks separated by a mass shift, e.g.
+//!   the difference between the precursor masses of two related molecules
+//! - [`entropy_similarity`], which compares the spectral entropy of the two spectra with that
+//!   of their mixture, as described by Li et al. (2021)
+use crate::coordinate::CoordinateLike;
+use crate::mass_error::Tolerance;
+use crate::matching::match_peaks;
+use crate::peak::IntensityMeasurement;
+use crate::queryable::PeakQueryable;
+
+/// The intensities of `peaks`, in order
+fn intensities<C, A>(peaks: &A) -> Vec<f64>
+where
+    A: PeakQueryable<C> + ?Sized,
+    A::Peak: IntensityMeasurement,
+{
+    (0..peaks.len())
+        .filter_map(|i| peaks.get(i))
+        .map(|p| p.intensity() as f64)
+        .collect()
+}
+
+/// The Euclidean norm of the intensities of `peaks`
+fn magnitude<C, A>(peaks: &A) -> f64
+where
+    A: PeakQueryable<C> + ?Sized,
+    A::Peak: IntensityMeasurement,
+{
+    intensities(peaks).iter().map(|i| i * i).sum::<f64>().sqrt()
+}
+
+/// The sum of the products of the intensities of the peaks of `a` and `b` paired within
+/// `tolerance`
+pub fn dot_product<C, A, B>(a: &A, b: &B, tolerance: Tolerance) -> f64
+where
+    A: PeakQueryable<C> + ?Sized,
+    A::Peak: IntensityMeasurement,
+    B: PeakQueryable<C> + ?Sized,
+    B::Peak: IntensityMeasurement,
+{
+    match_peaks(a, b, tolerance)
+        .iter()
+        .map(|pair| pair.reference_intensity as f64 * pair.query_intensity as f64)
+        .sum()
+}
+
+/// The [`dot_product`] of `a` and `b` divided by the product of their magnitudes, between 0
+/// for spectra without any paired peaks and 1 for spectra with identical intensity patterns
+pub fn cosine_similarity<C, A, B>(a: &A, b: &B, tolerance: Tolerance) -> f64
+where
+    A: PeakQueryable<C> + ?Sized,
+    A::Peak: IntensityMeasurement,
+    B: PeakQueryable<C> + ?Sized,
+    B::Peak: IntensityMeasurement,
+{
+    let norm = magnitude(a) * magnitude(b);
+    if norm == 0.0 {
+        return 0.0;
+    }
+    dot_product(a, b, tolerance) / norm
+}
+
+/// Like [`cosine_similarity`], but a peak of `a` may also pair with a peak of `b` located
+/// `shift` units higher, e.g. the difference between the precursor masses of `b` and `a`, so
+/// that fragments carrying a modification still pair up. Candidate pairs are accepted in order
+/// of decreasing intensity product, and each peak is used at most once.
+pub fn modified_cosine_similarity<C, A, B>(a: &A, b: &B, tolerance: Tolerance, shift: f64) -> f64
+where
+    A: PeakQueryable<C> + ?Sized,
+    A::Peak: IntensityMeasurement,
+    B: PeakQueryable<C> + ?Sized,
+    B::Peak: IntensityMeasurement,
+{
+    let norm = magnitude(a) * magnitude(b);
+    if norm == 0.0 {
+        return 0.0;
+    }
+    let offsets: &[f64] = if shift == 0.0 { &[0.0] } else { &[0.0, shift] };
+    let mut candidates: Vec<(usize, usize, f64)> = Vec::new();
+    for (i, peak) in (0..a.len()).filter_map(|i| Some((i, a.get(i)?))) {
+        for offset in offsets {
+            let x = peak.coordinate() + offset;
+            for j in b.between(x, x, tolerance) {
+                if let Some(other) = b.get(j) {
+                    let product = peak.intensity() as f64 * other.intensity() as f64;
+                    candidates.push((i, j, product));
+                }
+            }
+        }
+    }
+    candidates.sort_by(|x, y| y.2.total_cmp(&x.2));
+    let mut used_a = vec![false; a.len()];
+    let mut used_b = vec![false; b.len()];
+    let mut score = 0.0;
+    for (i, j, product) in candidates {
+        if !used_a[i] && !used_b[j] {
+            used_a[i] = true;
+            used_b[j] = true;
+            score += product;
+        }
+    }
+    score / norm
+}
+
+/// The Shannon entropy, in nats, of `values` which sum to 1
+fn entropy(values: impl Iterator<Item = f64>) -> f64 {
+    values.filter(|p| *p > 0.0).map(|p| -p * p.ln()).sum()
+}
+
+/// The entropy similarity of `a` and `b`, between 0 and 1, from
+/// `1 - (2 S(ab) - S(a) - S(b)) / ln(4)`, where `S` is the spectral entropy of a spectrum with
+/// its intensities scaled to sum to 1 and `ab` is the equal mixture of the two spectra, with
+/// peaks paired within `tolerance` merged.
+///
+/// Li, Y., Kind, T., Folz, J. et al. "Spectral entropy outperforms MS/MS dot product
+/// similarity for small-molecule compound identification", Nature Methods 18, 1524–1531 (2021)
+pub fn entropy_similarity<C, A, B>(a: &A, b: &B, tolerance: Tolerance) -> f64
+where
+    A: PeakQueryable<C> + ?Sized,
+    A::Peak: IntensityMeasurement,
+    B: PeakQueryable<C> + ?Sized,
+    B::Peak: IntensityMeasurement,
+{
+    let mut pa = intensities(a);
+    let mut pb = intensities(b);
+    let total_a: f64 = pa.iter().sum();
+    let total_b: f64 = pb.iter().sum();
+    if total_a <= 0.0 || total_b <= 0.0 {
+        return 0.0;
+    }
+    pa.iter_mut().for_each(|p| *p /= total_a);
+    pb.iter_mut().for_each(|p| *p /= total_b);
+
+    let pairs = match_peaks(a, b, tolerance);
+    let mut mixture_a: Vec<f64> = pa.iter().map(|p| p / 2.0).collect();
+    let mut mixture_b: Vec<f64> = pb.iter().map(|p| p / 2.0).collect();
+    for pair in pairs.iter() {
+        mixture_a[pair.reference] += mixture_b[pair.query];
+        mixture_b[pair.query] = 0.0;
+    }
+    let mixed = entropy(mixture_a.into_iter().chain(mixture_b));
+    let divergence = 2.0 * mixed - entropy(pa.into_iter()) - entropy(pb.into_iter());
+    (1.0 - divergence / 4.0f64.ln()).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CentroidPeak, PeakSet};
+
+    #[test]
+    fn test_similarity() {
+        let a = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 30.0, 0),
+            CentroidPeak::new(200.0, 40.0, 0),
+            CentroidPeak::new(300.0, 0.0, 0),
+        ]);
+        let tol = Tolerance::Da(0.01);
+        assert!((cosine_similarity(&a, &a, tol) - 1.0).abs() < 1e-9);
+        assert!((entropy_similarity(&a, &a, tol) - 1.0).abs() < 1e-9);
+        assert_eq!(dot_product(&a, &a, tol), 2500.0);
+
+        let b = PeakSet::new(vec![
+            CentroidPeak::new(100.005, 30.0, 0),
+            CentroidPeak::new(250.0, 40.0, 0),
+        ]);
+        assert!((cosine_similarity(&a, &b, tol) - 900.0 / 2500.0).abs() < 1e-9);
+        let entropy = entropy_similarity(&a, &b, tol);
+        assert!(entropy > 0.0 && entropy < 1.0);
+
+        // Shifting by 50 pairs 200 with 250 as well as 100 with 100.005
+        let modified = modified_cosine_similarity(&a, &b, tol, 50.0);
+        assert!((modified - 1.0).abs() < 1e-9);
+        assert_eq!(
+            modified_cosine_similarity(&a, &b, tol, 0.0),
+            cosine_similarity(&a, &b, tol)
+        );
+
+        let disjoint = PeakSet::new(vec![CentroidPeak::new(500.0, 10.0, 0)]);
+        assert_eq!(cosine_similarity(&a, &disjoint, tol), 0.0);
+        assert!(entropy_similarity(&a, &disjoint, tol).abs() < 1e-9);
+        assert_eq!(cosine_similarity(&a, &PeakSet::empty(), tol), 0.0);
+    }
+}
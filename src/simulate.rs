@@ -0,0 +1,241 @@
+//! Generate random but reproducible peak lists.
+//!
+//! Testing and fuzzing code that consumes peak lists needs inputs which look like real
+//! spectra. [`PeakSetGenerator`] draws peak m/z values from an [`MZDistribution`] and their
+//! intensities from an [`IntensityDistribution`], using a small seedable random number generator,
+//! [`SimulationRng`], so the same seed always produces the same peak list.
+use crate::coordinate::IndexedCoordinate;
+use crate::peak::CentroidPeak;
+use crate::peak_set::{PeakSet, PeakSetVec};
+
+/// The mass difference between the carbon-13 and carbon-12 isotopes, which
+/// dominates the spacing of isotopic peaks
+pub const NEUTRON_SHIFT: f64 = 1.0033548378;
+
+/// A small, fast and seedable pseudo-random number generator using the SplitMix64 algorithm.
+///
+/// This is not suitable for cryptographic purposes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulationRng {
+    state: u64,
+}
+
+impl SimulationRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Draw a value uniformly from `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Draw a value uniformly from `[low, high)`
+    pub fn uniform(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    /// Draw a value from a standard normal distribution using the Box-Muller transform
+    pub fn standard_normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+/// How to place peaks along the m/z dimension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MZDistribution {
+    /// Place each peak independently and uniformly within `[low, high)`
+    Uniform { low: f64, high: f64 },
+    /// Place clusters of isotopic peaks whose monoisotopic peak is uniformly
+    /// distributed within `[low, high)`, with a charge state uniformly chosen from
+    /// `1..=max_charge` and relative intensities following an averagine-like
+    /// isotopic envelope.
+    AveragineClusters {
+        low: f64,
+        high: f64,
+        max_charge: i32,
+        peaks_per_cluster: usize,
+    },
+}
+
+/// How to draw peak intensities
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntensityDistribution {
+    /// Draw intensities uniformly within `[low, high)`
+    Uniform { low: f32, high: f32 },
+    /// Draw intensities whose logarithm is normally distributed with mean `mu`
+    /// and standard deviation `sigma`
+    LogNormal { mu: f64, sigma: f64 },
+}
+
+impl IntensityDistribution {
+    pub fn sample(&self, rng: &mut SimulationRng) -> f32 {
+        match self {
+            Self::Uniform { low, high } => rng.uniform(*low as f64, *high as f64) as f32,
+            Self::LogNormal { mu, sigma } => (mu + sigma * rng.standard_normal()).exp() as f32,
+        }
+    }
+}
+
+/// The relative abundance of the `k`th isotopic peak of a peptide-like molecule
+/// of `neutral_mass`, approximated by a Poisson distribution whose mean grows
+/// linearly with mass
+pub(crate) fn averagine_abundance(neutral_mass: f64, k: usize) -> f64 {
+    let lambda = neutral_mass / 1800.0;
+    let mut p = (-lambda).exp();
+    for i in 1..=k {
+        p *= lambda / i as f64;
+    }
+    p
+}
+
+/// Generate reproducible random peak lists
+#[derive(Debug, Clone)]
+pub struct PeakSetGenerator {
+    pub mz_distribution: MZDistribution,
+    pub intensity_distribution: IntensityDistribution,
+    rng: SimulationRng,
+}
+
+impl PeakSetGenerator {
+    pub fn new(
+        seed: u64,
+        mz_distribution: MZDistribution,
+        intensity_distribution: IntensityDistribution,
+    ) -> Self {
+        Self {
+            mz_distribution,
+            intensity_distribution,
+            rng: SimulationRng::new(seed),
+        }
+    }
+
+    fn generate_peaks(&mut self, n: usize) -> Vec<CentroidPeak> {
+        let mut peaks = Vec::with_capacity(n);
+        match self.mz_distribution {
+            MZDistribution::Uniform { low, high } => {
+                for _ in 0..n {
+                    let mz = self.rng.uniform(low, high);
+                    let intensity = self.intensity_distribution.sample(&mut self.rng);
+                    peaks.push(CentroidPeak::new(mz, intensity, 0));
+                }
+            }
+            MZDistribution::AveragineClusters {
+                low,
+                high,
+                max_charge,
+                peaks_per_cluster,
+            } => {
+                let max_charge = max_charge.max(1);
+                let peaks_per_cluster = peaks_per_cluster.max(1);
+                while peaks.len() < n {
+                    let mz = self.rng.uniform(low, high);
+                    let z = 1 + (self.rng.next_u64() % max_charge as u64) as i32;
+                    let mass = mz * z as f64;
+                    let base = self.intensity_distribution.sample(&mut self.rng) as f64;
+                    let envelope: Vec<f64> = (0..peaks_per_cluster)
+                        .map(|k| averagine_abundance(mass, k))
+                        .collect();
+                    let max_abundance = envelope.iter().copied().fold(0.0, f64::max);
+                    for (k, abundance) in envelope.into_iter().enumerate() {
+                        if peaks.len() == n {
+                            break;
+                        }
+                        peaks.push(CentroidPeak::new(
+                            mz + k as f64 * NEUTRON_SHIFT / z as f64,
+                            (base * abundance / max_abundance) as f32,
+                            0,
+                        ));
+                    }
+                }
+            }
+        }
+        peaks
+    }
+
+    /// Generate a sorted [`PeakSet`] with `n` peaks
+    pub fn generate(&mut self, n: usize) -> PeakSet {
+        PeakSet::new(self.generate_peaks(n))
+    }
+
+    /// Generate a sorted peak list with `n` peaks of any type which can be built
+    /// from a [`CentroidPeak`]
+    pub fn generate_as<P: IndexedCoordinate<C> + From<CentroidPeak>, C>(
+        &mut self,
+        n: usize,
+    ) -> PeakSetVec<P, C> {
+        PeakSetVec::new(self.generate_peaks(n).into_iter().map(P::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::peak::FittedPeak;
+    use crate::MZPeakSetType;
+
+    #[test]
+    fn test_rng() {
+        let mut a = SimulationRng::new(42);
+        let mut b = SimulationRng::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        let x = a.uniform(5.0, 6.0);
+        assert!((5.0..6.0).contains(&x));
+
+        let n = 10_000;
+        let mean = (0..n).map(|_| a.standard_normal()).sum::<f64>() / n as f64;
+        assert!(mean.abs() < 0.05);
+    }
+
+    #[test]
+    fn test_generator() {
+        let mut gen = PeakSetGenerator::new(
+            7,
+            MZDistribution::Uniform {
+                low: 100.0,
+                high: 2000.0,
+            },
+            IntensityDistribution::LogNormal {
+                mu: 8.0,
+                sigma: 1.0,
+            },
+        );
+        let peaks = gen.generate(100);
+        assert_eq!(peaks.len(), 100);
+        assert!(peaks.windows(2).all(|w| w[0].mz <= w[1].mz));
+        assert!(peaks.iter().all(|p| p.intensity > 0.0));
+        assert_eq!(peaks[99].index, 99);
+
+        let mut gen = PeakSetGenerator::new(
+            7,
+            MZDistribution::AveragineClusters {
+                low: 400.0,
+                high: 1200.0,
+                max_charge: 3,
+                peaks_per_cluster: 4,
+            },
+            IntensityDistribution::Uniform {
+                low: 1e3,
+                high: 1e4,
+            },
+        );
+        let peaks: MZPeakSetType<FittedPeak> = gen.generate_as(42);
+        assert_eq!(peaks.len(), 42);
+
+        let mut again = PeakSetGenerator::new(7, gen.mz_distribution, gen.intensity_distribution);
+        let mut gen = PeakSetGenerator::new(7, gen.mz_distribution, gen.intensity_distribution);
+        assert_eq!(gen.generate(20), again.generate(20));
+    }
+}
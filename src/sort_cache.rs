@@ -0,0 +1,107 @@
+//! Cache alternative orderings of a peak list.
+//!
+//! A [`PeakSetVec`] is always ordered by its coordinate, but many workflows also need to
+//! visit peaks from most to least intense, sometimes repeatedly. Rather than re-sorting a
+//! copy each time, a [`SortCache`] borrows the peak list and computes the intensity order
+//! permutation once, on first use. Because the peak list is borrowed, it can't change while
+//! the cache is alive, so the cached permutation can't go stale.
+use std::cell::OnceCell;
+
+use crate::coordinate::IndexedCoordinate;
+use crate::peak::IntensityMeasurement;
+use crate::peak_set::PeakSetVec;
+
+/// Lazily computed sort permutations over a borrowed [`PeakSetVec`]
+#[derive(Debug, Clone)]
+pub struct SortCache<'a, P: IndexedCoordinate<C>, C> {
+    peaks: &'a PeakSetVec<P, C>,
+    intensity_order: OnceCell<Vec<usize>>,
+}
+
+impl<'a, P: IndexedCoordinate<C> + IntensityMeasurement, C> SortCache<'a, P, C> {
+    pub fn new(peaks: &'a PeakSetVec<P, C>) -> Self {
+        Self {
+            peaks,
+            intensity_order: OnceCell::new(),
+        }
+    }
+
+    /// The peaks in their primary, coordinate order
+    pub fn by_coordinate(&self) -> &'a [P] {
+        self.peaks.as_slice()
+    }
+
+    /// The positions of the peaks ordered from most to least intense, with ties broken
+    /// by coordinate order. This is computed on the first call and reused afterwards.
+    pub fn by_intensity_order(&self) -> &[usize] {
+        self.intensity_order.get_or_init(|| {
+            let mut order: Vec<usize> = (0..self.peaks.len()).collect();
+            order.sort_by(|a, b| {
+                self.peaks[*b]
+                    .intensity()
+                    .total_cmp(&self.peaks[*a].intensity())
+            });
+            order
+        })
+    }
+
+    /// Iterate over the peaks from most to least intense
+    pub fn iter_by_intensity(&self) -> impl Iterator<Item = &'a P> + '_ {
+        let peaks = self.peaks;
+        self.by_intensity_order().iter().map(move |i| &peaks[*i])
+    }
+
+    /// The `n` most intense peaks, from most to least intense
+    pub fn top_n(&self, n: usize) -> impl Iterator<Item = &'a P> + '_ {
+        self.iter_by_intensity().take(n)
+    }
+
+    /// The rank of the peak at position `i` when ordered from most to least intense,
+    /// starting from zero
+    pub fn intensity_rank(&self, i: usize) -> Option<usize> {
+        self.by_intensity_order().iter().position(|j| *j == i)
+    }
+
+    /// Whether the intensity order has been computed yet
+    pub fn is_computed(&self) -> bool {
+        self.intensity_order.get().is_some()
+    }
+}
+
+impl<P: IndexedCoordinate<C> + IntensityMeasurement, C> PeakSetVec<P, C> {
+    /// Create a [`SortCache`] over this peak list
+    pub fn sort_cache(&self) -> SortCache<'_, P, C> {
+        SortCache::new(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_data;
+
+    #[test]
+    fn test_sort_cache() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let cache = peaks.sort_cache();
+        assert!(!cache.is_computed());
+        let order = cache.by_intensity_order().to_vec();
+        assert!(cache.is_computed());
+        assert_eq!(order.len(), peaks.len());
+        assert!(order
+            .windows(2)
+            .all(|w| peaks[w[0]].intensity >= peaks[w[1]].intensity));
+        assert_eq!(
+            cache.by_intensity_order().as_ptr(),
+            cache.by_intensity_order().as_ptr()
+        );
+
+        let base_peak = peaks
+            .iter()
+            .max_by(|a, b| a.intensity.total_cmp(&b.intensity))
+            .unwrap();
+        assert_eq!(cache.top_n(1).next().unwrap(), base_peak);
+        assert_eq!(cache.intensity_rank(base_peak.index as usize), Some(0));
+        assert_eq!(cache.by_coordinate()[0].mz, peaks[0].mz);
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+//! Histogram the spacing between peaks and detect periodic series.
+//!
+//! Many signals appear as a series of evenly spaced peaks: an isotopic envelope of charge `z`
+//! is spaced by about `1.00335 / z` m/z, and a polymer ladder by the mass of its repeating
+//! unit. [`spacing_histogram`] counts the coordinate differences of every pair of peaks up to
+//! a maximum distance, and [`detect_periodicity`] picks out the most common spacing, from which
+//! [`Periodicity::isotopic_charge`] infers the charge of an isotopic series.
+use crate::coordinate::IndexedCoordinate;
+use crate::peak_set::PeakCollection;
+use crate::simulate::NEUTRON_SHIFT;
+
+/// A histogram of the differences in coordinate between pairs of peaks, produced by
+/// [`spacing_histogram`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpacingHistogram {
+    bin_width: f64,
+    counts: Vec<usize>,
+    /// The sum of the spacings in each bin, for refining a bin to the mean of its spacings
+    sums: Vec<f64>,
+}
+
+impl SpacingHistogram {
+    fn new(bin_width: f64, max_delta: f64) -> Self {
+        assert!(
+            bin_width > 0.0,
+            "Bin width must be positive, got {bin_width}"
+        );
+        assert!(
+            max_delta.is_finite(),
+            "The maximum spacing must be finite, got {max_delta}"
+        );
+        let n = (max_delta / bin_width).floor() as usize + 1;
+        Self {
+            bin_width,
+            counts: vec![0; n],
+            sums: vec![0.0; n],
+        }
+    }
+
+    fn add(&mut self, delta: f64) {
+        let i = (delta / self.bin_width).floor() as usize;
+        if let Some(count) = self.counts.get_mut(i) {
+            *count += 1;
+            self.sums[i] += delta;
+        }
+    }
+
+    pub fn bin_width(&self) -> f64 {
+        self.bin_width
+    }
+
+    /// The number of bins
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// The number of pairs counted across all bins
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// The number of pairs in each bin, where bin `i` spans `[i * bin_width, (i + 1) * bin_width)`
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// The center of bin `i`
+    pub fn bin_center(&self, i: usize) -> f64 {
+        (i as f64 + 0.5) * self.bin_width
+    }
+
+    /// The mean of the spacings counted in bin `i`, or `None` if it is empty
+    pub fn mean_spacing(&self, i: usize) -> Option<f64> {
+        match self.counts.get(i) {
+            Some(count) if *count > 0 => Some(self.sums[i] / *count as f64),
+            _ => None,
+        }
+    }
+
+    /// Iterate over the center and count of each bin
+    pub fn iter(&self) -> impl Iterator<Item = (f64, usize)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (self.bin_center(i), *count))
+    }
+}
+
+/// Count the differences in coordinate between every pair of peaks no more than `max_delta`
+/// apart, in bins of `bin_width`
+///
+/// # Panics
+/// If `bin_width` is not positive, or if `max_delta` is not finite
+pub fn spacing_histogram<P, C, A>(peaks: &A, bin_width: f64, max_delta: f64) -> SpacingHistogram
+where
+    P: IndexedCoordinate<C>,
+    A: PeakCollection<P, C>,
+    A::Output: IndexedCoordinate<C>,
+{
+    let mut histogram = SpacingHistogram::new(bin_width, max_delta);
+    for (i, j) in peaks.pairs_within(max_delta) {
+        histogram.add(peaks.get_item(j).coordinate() - peaks.get_item(i).coordinate());
+    }
+    histogram
+}
+
+/// The most common spacing between peaks, found by [`detect_periodicity`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Periodicity {
+    /// The mean of the spacings in the most populated histogram bin
+    pub spacing: f64,
+    /// The number of pairs of peaks separated by about `spacing`
+    pub count: usize,
+    /// The fraction of the pairs in the searched range separated by about `spacing`
+    pub support: f64,
+}
+
+impl Periodicity {
+    /// The charge state, up to `max_charge`, whose isotopic spacing is closest to `spacing`,
+    /// or `None` if none of them are within `tolerance` m/z
+    pub fn isotopic_charge(&self, max_charge: i32, tolerance: f64) -> Option<i32> {
+        (1..=max_charge)
+            .map(|z| (z, (NEUTRON_SHIFT / z as f64 - self.spacing).abs()))
+            .filter(|(_, error)| *error <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(z, _)| z)
+    }
+}
+
+/// Find the most common spacing between peaks between `min_delta` and `max_delta` apart,
+/// in bins of `bin_width`. Returns `None` if no pair of peaks is spaced within that range.
+///
+/// Multiples of the fundamental spacing of a series are also counted, but the fundamental
+/// spacing occurs most often so long as the series has more than two members.
+///
+/// # Panics
+/// If `bin_width` is not positive, or if `max_delta` is not finite
+pub fn detect_periodicity<P, C, A>(
+    peaks: &A,
+    bin_width: f64,
+    min_delta: f64,
+    max_delta: f64,
+) -> Option<Periodicity>
+where
+    P: IndexedCoordinate<C>,
+    A: PeakCollection<P, C>,
+    A::Output: IndexedCoordinate<C>,
+{
+    let histogram = spacing_histogram(peaks, bin_width, max_delta);
+    let first = (min_delta / bin_width).floor() as usize;
+    let total: usize = histogram.counts().iter().skip(first).sum();
+    let (mut best, mut count) = (0, 0);
+    for (i, n) in histogram.counts().iter().enumerate().skip(first) {
+        if *n > count {
+            (best, count) = (i, *n);
+        }
+    }
+    if count == 0 {
+        return None;
+    }
+    Some(Periodicity {
+        spacing: histogram.mean_spacing(best)?,
+        count,
+        support: count as f64 / total as f64,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CentroidPeak, PeakSet};
+
+    #[test]
+    fn test_periodicity() {
+        let z = 3.0;
+        let mut peaks: Vec<CentroidPeak> = (0..5)
+            .map(|i| CentroidPeak::new(800.0 + i as f64 * NEUTRON_SHIFT / z, 100.0, 0))
+            .collect();
+        peaks.push(CentroidPeak::new(801.9, 5.0, 0));
+        let peaks = PeakSet::new(peaks);
+
+        let histogram = spacing_histogram(&peaks, 0.01, 1.0);
+        assert_eq!(histogram.len(), 101);
+        assert_eq!(histogram.counts()[33], 4);
+        assert!((histogram.bin_center(33) - 0.335).abs() < 1e-9);
+        assert!((histogram.mean_spacing(33).unwrap() - NEUTRON_SHIFT / z).abs() < 1e-9);
+        assert_eq!(histogram.iter().count(), histogram.len());
+
+        let periodicity = detect_periodicity(&peaks, 0.01, 0.05, 1.1).unwrap();
+        assert_eq!(periodicity.count, 4);
+        assert!(periodicity.support > 0.2);
+        assert_eq!(periodicity.isotopic_charge(8, 0.01), Some(3));
+        assert_eq!(periodicity.isotopic_charge(2, 0.01), None);
+
+        let ladder = PeakSet::new(
+            (0..8)
+                .map(|i| CentroidPeak::new(500.0 + i as f64 * 44.0262, 10.0, 0))
+                .collect(),
+        );
+        let periodicity = detect_periodicity(&ladder, 0.05, 10.0, 100.0).unwrap();
+        assert!((periodicity.spacing - 44.0262).abs() < 1e-6);
+        assert!(detect_periodicity(&PeakSet::empty(), 0.05, 10.0, 100.0).is_none());
+        for bin_width in [0.0, f64::NAN] {
+            let peaks = peaks.clone();
+            assert!(std::panic::catch_unwind(move || {
+                spacing_histogram(&peaks, bin_width, 1.0)
+            })
+            .is_err());
+        }
+    }
+}
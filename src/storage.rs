@@ -0,0 +1,243 @@
+//! Plug alternative backing storage into a searchable peak collection.
+//!
+//! [`PeakSetVec`](crate::PeakSetVec) always owns its peaks in a [`Vec`]. The search
+//! algorithms of [`PeakCollection`] only need the peaks to be contiguous and sorted, so
+//! [`StoredPeakSet`] provides them over any [`PeakStorage`] instead: a shared [`Arc<[P]>`],
+//! a borrowed slice from a [`SpectrumArena`](crate::arena::SpectrumArena) or a memory-mapped
+//! file, or a user-defined buffer. Storage which can also grow implements [`PeakStorageMut`]
+//! to make the collection a [`PeakCollectionMut`].
+//!
+//! Storage must present the peaks as a contiguous slice, so column-oriented layouts need
+//! to be materialized into peaks first.
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::ops;
+use std::sync::Arc;
+
+use crate::coordinate::IndexedCoordinate;
+use crate::error::MzPeaksError;
+use crate::peak_set::{OrderUpdateEvent, PeakCollection, PeakCollectionMut, PeakSetVec};
+
+/// Contiguous storage for peaks
+pub trait PeakStorage<P> {
+    fn peaks(&self) -> &[P];
+}
+
+/// Contiguous storage for peaks which can be modified and grown
+pub trait PeakStorageMut<P>: PeakStorage<P> {
+    fn peaks_mut(&mut self) -> &mut [P];
+
+    /// Add `peak` to the end of the storage
+    fn push(&mut self, peak: P);
+}
+
+impl<P> PeakStorage<P> for Vec<P> {
+    fn peaks(&self) -> &[P] {
+        self.as_slice()
+    }
+}
+
+impl<P> PeakStorageMut<P> for Vec<P> {
+    fn peaks_mut(&mut self) -> &mut [P] {
+        self.as_mut_slice()
+    }
+
+    fn push(&mut self, peak: P) {
+        Vec::push(self, peak)
+    }
+}
+
+impl<P> PeakStorage<P> for Box<[P]> {
+    fn peaks(&self) -> &[P] {
+        self
+    }
+}
+
+impl<P> PeakStorage<P> for Arc<[P]> {
+    fn peaks(&self) -> &[P] {
+        self
+    }
+}
+
+impl<P> PeakStorage<P> for &[P] {
+    fn peaks(&self) -> &[P] {
+        self
+    }
+}
+
+impl<P: Clone> PeakStorage<P> for Cow<'_, [P]> {
+    fn peaks(&self) -> &[P] {
+        self
+    }
+}
+
+/// A sorted, searchable peak collection over any [`PeakStorage`]
+#[derive(Debug, Clone, Default)]
+pub struct StoredPeakSet<P: IndexedCoordinate<C>, C, S: PeakStorage<P> = Vec<P>> {
+    storage: S,
+    _p: PhantomData<(P, C)>,
+}
+
+impl<P: IndexedCoordinate<C>, C, S: PeakStorage<P>> StoredPeakSet<P, C, S> {
+    /// Wrap `storage` after checking that its peaks are sorted by coordinate
+    pub fn try_new(storage: S) -> Result<Self, MzPeaksError> {
+        let peaks = storage.peaks();
+        for (i, pair) in peaks.windows(2).enumerate() {
+            if pair[0].coordinate().is_nan() {
+                return Err(MzPeaksError::NanCoordinate { index: i });
+            }
+            if pair[0].coordinate() > pair[1].coordinate() {
+                return Err(MzPeaksError::Unsorted { index: Some(i + 1) });
+            }
+        }
+        if let Some(p) = peaks.last() {
+            if p.coordinate().is_nan() {
+                return Err(MzPeaksError::NanCoordinate {
+                    index: peaks.len() - 1,
+                });
+            }
+        }
+        Ok(Self::wrap(storage))
+    }
+
+    /// Wrap `storage` without checking that its peaks are sorted. It is up to the caller
+    /// to ensure they are before searching the collection.
+    pub fn wrap(storage: S) -> Self {
+        Self {
+            storage,
+            _p: PhantomData,
+        }
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    pub fn into_storage(self) -> S {
+        self.storage
+    }
+
+    pub fn as_slice(&self) -> &[P] {
+        self.storage.peaks()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, P> {
+        self.storage.peaks().iter()
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C, S: PeakStorageMut<P>> StoredPeakSet<P, C, S> {
+    /// Wrap `storage`, sorting its peaks by coordinate and updating their indices
+    pub fn new(mut storage: S) -> Self {
+        PeakSetVec::<P, C>::_sort(storage.peaks_mut());
+        Self::wrap(storage)
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C, S: PeakStorage<P>> ops::Index<usize> for StoredPeakSet<P, C, S> {
+    type Output = P;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.storage.peaks()[i]
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C, S: PeakStorage<P>> PeakCollection<P, C>
+    for StoredPeakSet<P, C, S>
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.storage.peaks().len()
+    }
+
+    #[inline]
+    fn get_item(&self, i: usize) -> &P {
+        &self.storage.peaks()[i]
+    }
+
+    #[inline]
+    fn get_slice(&self, i: ops::Range<usize>) -> &[P] {
+        &self.storage.peaks()[i]
+    }
+
+    #[inline]
+    fn search_by(&self, query: f64) -> Result<usize, usize> {
+        self.storage.peaks().binary_search_by(|peak| {
+            peak.coordinate()
+                .partial_cmp(&query)
+                .unwrap_or(std::cmp::Ordering::Greater)
+        })
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &P> {
+        self.storage.peaks().iter()
+    }
+}
+
+impl<P: IndexedCoordinate<C>, C, S: PeakStorageMut<P>> PeakCollectionMut<P, C>
+    for StoredPeakSet<P, C, S>
+{
+    fn push(&mut self, peak: P) -> OrderUpdateEvent {
+        let in_order = self.storage.peaks().last().is_none_or(|p| p <= &peak);
+        let n = self.len();
+        self.storage.push(peak);
+        if in_order {
+            self.storage.peaks_mut()[n].set_index(n as crate::IndexType);
+            OrderUpdateEvent::TailAppend
+        } else {
+            self.sort();
+            OrderUpdateEvent::InsertResorted
+        }
+    }
+
+    fn sort(&mut self) {
+        PeakSetVec::<P, C>::_sort(self.storage.peaks_mut());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_data, CentroidPeak, Tolerance, MZ};
+
+    #[test]
+    fn test_stored_peak_set() -> std::io::Result<()> {
+        let peaks = test_data::read_peaks_from_file("./test/data/test.txt")?;
+        let shared: Arc<[CentroidPeak]> = peaks.peaks.clone().into();
+        let stored: StoredPeakSet<CentroidPeak, MZ, _> = StoredPeakSet::try_new(shared).unwrap();
+        assert_eq!(
+            stored.search(773.4414, Tolerance::Da(0.01)),
+            peaks.search(773.4414, Tolerance::Da(0.01))
+        );
+        assert_eq!(
+            stored.all_peaks_for(773.4414, Tolerance::Da(2.0)),
+            peaks.all_peaks_for(773.4414, Tolerance::Da(2.0))
+        );
+
+        let borrowed: StoredPeakSet<CentroidPeak, MZ, &[CentroidPeak]> =
+            StoredPeakSet::try_new(&peaks.peaks[10..20]).unwrap();
+        assert_eq!(borrowed.len(), 10);
+
+        let unsorted = [
+            CentroidPeak::new(200.0, 1.0, 0),
+            CentroidPeak::new(100.0, 1.0, 1),
+        ];
+        let err =
+            StoredPeakSet::<CentroidPeak, MZ, &[CentroidPeak]>::try_new(&unsorted[..]).unwrap_err();
+        assert!(matches!(err, MzPeaksError::Unsorted { index: Some(1) }));
+
+        let mut owned: StoredPeakSet<CentroidPeak, MZ> = StoredPeakSet::new(unsorted.to_vec());
+        assert_eq!(owned[0].mz, 100.0);
+        assert_eq!(
+            owned.push(CentroidPeak::new(150.0, 1.0, 0)),
+            OrderUpdateEvent::InsertResorted
+        );
+        assert_eq!(owned[1].index, 1);
+        assert_eq!(
+            owned.push(CentroidPeak::new(250.0, 1.0, 0)),
+            OrderUpdateEvent::TailAppend
+        );
+        assert_eq!(owned[3].index, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+//! Summary statistics over the intensities of a group of centroided peaks.
+//!
+//! These take any iterator over references to [`CentroidLike`] peaks, so they apply equally
+//! to a whole [`PeakSet`](crate::PeakSet), a slice returned by a search, or a filtered subset.
+use crate::coordinate::MZLocated;
+use crate::peak::CentroidLike;
+
+/// The total intensity of all peaks, the total ion current
+pub fn total_ion_current<'a, P: CentroidLike + 'a, I: IntoIterator<Item = &'a P>>(peaks: I) -> f64 {
+    peaks.into_iter().map(|p| p.intensity() as f64).sum()
+}
+
+/// The intensity-weighted mean m/z of the peaks, or `None` if they have no intensity
+pub fn weighted_mean_mz<'a, P: CentroidLike + 'a, I: IntoIterator<Item = &'a P>>(
+    peaks: I,
+) -> Option<f64> {
+    let (weighted, total) = peaks.into_iter().fold((0.0, 0.0), |(weighted, total), p| {
+        let intensity = p.intensity() as f64;
+        (weighted + p.mz() * intensity, total + intensity)
+    });
+    (total > 0.0).then(|| weighted / total)
+}
+
+/// The most intense peak, preferring the earliest on a tie, or `None` if there are no peaks
+pub fn base_peak<'a, P: CentroidLike + 'a, I: IntoIterator<Item = &'a P>>(
+    peaks: I,
+) -> Option<&'a P> {
+    peaks
+        .into_iter()
+        .fold(None, |best: Option<&'a P>, p| match best {
+            Some(b) if b.intensity() >= p.intensity() => Some(b),
+            _ => Some(p),
+        })
+}
+
+/// The intensity at each of `percentiles`, between 0 and 100, using the nearest rank. Returns
+/// `None` if there are no peaks.
+///
+/// # Panics
+/// If any of `percentiles` is outside of `0..=100`
+pub fn intensity_percentiles<'a, P: CentroidLike + 'a, I: IntoIterator<Item = &'a P>>(
+    peaks: I,
+    percentiles: &[f32],
+) -> Option<Vec<f32>> {
+    for p in percentiles {
+        assert!(
+            (0.0..=100.0).contains(p),
+            "The intensity percentile must be between 0 and 100, got {p}"
+        );
+    }
+    let mut intensities: Vec<f32> = peaks.into_iter().map(|p| p.intensity()).collect();
+    if intensities.is_empty() {
+        return None;
+    }
+    intensities.sort_by(|a, b| a.total_cmp(b));
+    let last = (intensities.len() - 1) as f32;
+    Some(
+        percentiles
+            .iter()
+            .map(|p| intensities[(p / 100.0 * last).round() as usize])
+            .collect(),
+    )
+}
+
+/// The Shannon entropy, in nats, of the peaks' intensities treated as a probability
+/// distribution. A spectrum dominated by a single peak has an entropy near zero, and `n`
+/// peaks of equal intensity have an entropy of `ln(n)`. Peaks without intensity are ignored.
+pub fn spectral_entropy<'a, P: CentroidLike + 'a, I: IntoIterator<Item = &'a P>>(peaks: I) -> f64 {
+    let intensities: Vec<f64> = peaks
+        .into_iter()
+        .map(|p| p.intensity() as f64)
+        .filter(|i| *i > 0.0)
+        .collect();
+    let total: f64 = intensities.iter().sum();
+    intensities
+        .iter()
+        .map(|i| {
+            let p = i / total;
+            -p * p.ln()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CentroidPeak, PeakCollection, PeakSet, Tolerance};
+
+    #[test]
+    fn test_summary() {
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(100.0, 10.0, 0),
+            CentroidPeak::new(200.0, 30.0, 1),
+            CentroidPeak::new(300.0, 30.0, 2),
+            CentroidPeak::new(400.0, 30.0, 3),
+        ]);
+        assert_eq!(total_ion_current(&peaks), 100.0);
+        assert_eq!(weighted_mean_mz(&peaks), Some(280.0));
+        assert_eq!(base_peak(&peaks).unwrap().mz, 200.0);
+        assert_eq!(
+            intensity_percentiles(&peaks, &[0.0, 50.0, 100.0]),
+            Some(vec![10.0, 30.0, 30.0])
+        );
+
+        let window = peaks.all_peaks_for(300.0, Tolerance::Da(150.0));
+        assert_eq!(total_ion_current(window), 90.0);
+        assert_eq!(weighted_mean_mz(window), Some(300.0));
+        assert!((spectral_entropy(window) - 3.0f64.ln()).abs() < 1e-9);
+        assert!(spectral_entropy(&peaks) < 4.0f64.ln());
+
+        let empty = PeakSet::empty();
+        assert_eq!(total_ion_current(&empty), 0.0);
+        assert_eq!(weighted_mean_mz(&empty), None);
+        assert!(base_peak(&empty).is_none());
+        assert_eq!(intensity_percentiles(&empty, &[50.0]), None);
+        assert_eq!(spectral_entropy(&empty), 0.0);
+    }
+}
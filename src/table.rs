@@ -0,0 +1,215 @@
+//! Render peak lists as aligned text tables.
+//!
+//! The [`Debug`] representation of a peak list prints every field of every peak, which is
+//! hard to read at a REPL or in a CLI tool's output. [`PeakSetVec::to_table`] instead prints
+//! the most intense peaks as one row each with aligned columns for the coordinate, intensity,
+//! charge and index, and [`PeakSetVec::to_markdown_table`] produces the same table as markdown.
+use std::fmt::Write;
+
+use crate::coordinate::{IndexedCoordinate, IonMobility, Mass, Time, MZ};
+use crate::peak::{
+    CentroidPeak, CentroidPeakF32, DeconvolutedPeak, FittedPeak, IntensityMeasurement,
+    IonMobilityAwareCentroidPeak, MZPoint,
+};
+use crate::peak_set::PeakSetVec;
+
+/// A coordinate system with a column header for tables
+pub trait CoordinateLabel {
+    const LABEL: &'static str;
+}
+
+impl CoordinateLabel for MZ {
+    const LABEL: &'static str = "m/z";
+}
+
+impl CoordinateLabel for Mass {
+    const LABEL: &'static str = "mass";
+}
+
+impl CoordinateLabel for Time {
+    const LABEL: &'static str = "time";
+}
+
+impl CoordinateLabel for IonMobility {
+    const LABEL: &'static str = "ion mobility";
+}
+
+/// A peak which can be rendered as a table row
+pub trait TableRow {
+    /// Whether peaks of this type have a charge column, even when there are no peaks
+    const HAS_CHARGE: bool = false;
+
+    /// The value of the charge column, or `None` if the peak has no charge
+    fn charge_cell(&self) -> Option<i32> {
+        None
+    }
+}
+
+impl TableRow for CentroidPeak {}
+impl TableRow for CentroidPeakF32 {}
+impl TableRow for FittedPeak {}
+impl TableRow for IonMobilityAwareCentroidPeak {}
+impl TableRow for MZPoint {}
+
+impl TableRow for DeconvolutedPeak {
+    const HAS_CHARGE: bool = true;
+
+    fn charge_cell(&self) -> Option<i32> {
+        Some(self.charge)
+    }
+}
+
+/// How to lay out a rendered table
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Columns padded with spaces beneath a dashed rule
+    #[default]
+    Plain,
+    /// A GitHub-flavored markdown table
+    Markdown,
+}
+
+fn write_row(buffer: &mut String, cells: &[String], widths: &[usize], style: TableStyle) {
+    match style {
+        TableStyle::Plain => {
+            let row: Vec<String> = cells
+                .iter()
+                .zip(widths)
+                .map(|(cell, w)| format!("{cell:>w$}"))
+                .collect();
+            buffer.push_str(row.join("  ").trim_end());
+        }
+        TableStyle::Markdown => {
+            buffer.push('|');
+            for (cell, w) in cells.iter().zip(widths) {
+                write!(buffer, " {cell:>w$} |").unwrap();
+            }
+        }
+    }
+    buffer.push('\n');
+}
+
+impl<P: IndexedCoordinate<C> + IntensityMeasurement + TableRow, C: CoordinateLabel>
+    PeakSetVec<P, C>
+{
+    /// Render the `limit` most intense peaks, or all of them if `limit` is `None`, as a
+    /// table in coordinate order, with coordinates and intensities written to `precision`
+    /// decimal places. The charge column is only included if some peak has a charge.
+    pub fn to_table_with_style(
+        &self,
+        precision: usize,
+        limit: Option<usize>,
+        style: TableStyle,
+    ) -> String {
+        let mut selected: Vec<&P> = self.iter().collect();
+        if let Some(limit) = limit {
+            if limit < selected.len() {
+                selected.sort_by(|a, b| b.intensity().total_cmp(&a.intensity()));
+                selected.truncate(limit);
+                selected.sort_by(|a, b| a.coordinate().total_cmp(&b.coordinate()));
+            }
+        }
+        let show_charge = selected.iter().any(|p| p.charge_cell().is_some());
+
+        let mut header = vec![C::LABEL.to_string(), "intensity".to_string()];
+        if show_charge {
+            header.push("charge".to_string());
+        }
+        header.push("index".to_string());
+
+        let rows: Vec<Vec<String>> = selected
+            .iter()
+            .map(|p| {
+                let mut row = vec![
+                    format!("{:.precision$}", p.coordinate()),
+                    format!("{:.precision$}", p.intensity()),
+                ];
+                if show_charge {
+                    row.push(p.charge_cell().map(|z| z.to_string()).unwrap_or_default());
+                }
+                row.push(p.get_index().to_string());
+                row
+            })
+            .collect();
+
+        let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+        for row in rows.iter() {
+            for (w, cell) in widths.iter_mut().zip(row) {
+                *w = (*w).max(cell.len());
+            }
+        }
+
+        let mut buffer = String::new();
+        write_row(&mut buffer, &header, &widths, style);
+        match style {
+            TableStyle::Plain => {
+                let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+                buffer.push_str(&rule.join("  "));
+                buffer.push('\n');
+            }
+            TableStyle::Markdown => {
+                buffer.push('|');
+                for w in widths.iter() {
+                    write!(buffer, " {}: |", "-".repeat(w.saturating_sub(1))).unwrap();
+                }
+                buffer.push('\n');
+            }
+        }
+        for row in rows.iter() {
+            write_row(&mut buffer, row, &widths, style);
+        }
+        let omitted = self.len() - selected.len();
+        if omitted > 0 {
+            writeln!(buffer, "... {omitted} more peaks").unwrap();
+        }
+        buffer
+    }
+
+    /// Render the `limit` most intense peaks as a plain text table. See
+    /// [`PeakSetVec::to_table_with_style`].
+    pub fn to_table(&self, precision: usize, limit: Option<usize>) -> String {
+        self.to_table_with_style(precision, limit, TableStyle::Plain)
+    }
+
+    /// Render the `limit` most intense peaks as a markdown table. See
+    /// [`PeakSetVec::to_table_with_style`].
+    pub fn to_markdown_table(&self, precision: usize, limit: Option<usize>) -> String {
+        self.to_table_with_style(precision, limit, TableStyle::Markdown)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{DeconvolutedPeakSet, PeakSet};
+
+    #[test]
+    fn test_to_table() {
+        let peaks = PeakSet::new(vec![
+            CentroidPeak::new(204.0872, 9800.0, 0),
+            CentroidPeak::new(186.0766, 522.0, 0),
+            CentroidPeak::new(1205.5, 150.0, 0),
+        ]);
+        let table = peaks.to_table(2, Some(2));
+        let expected = concat!(
+            "   m/z  intensity  index\n",
+            "------  ---------  -----\n",
+            "186.08     522.00      0\n",
+            "204.09    9800.00      1\n",
+            "... 1 more peaks\n",
+        );
+        assert_eq!(table, expected);
+
+        let table = peaks.to_markdown_table(1, None);
+        let mut lines = table.lines();
+        assert_eq!(lines.next(), Some("|    m/z | intensity | index |"));
+        assert_eq!(lines.next(), Some("| -----: | --------: | ----: |"));
+        assert_eq!(lines.count(), 3);
+
+        let deconvoluted =
+            DeconvolutedPeakSet::new(vec![DeconvolutedPeak::new(1000.0, 50.0, 2, 0)]);
+        let table = deconvoluted.to_table(0, None);
+        assert!(table.starts_with("mass  intensity  charge  index\n"));
+        assert!(table.ends_with("1000         50       2      0\n"));
+    }
+}
@@ -0,0 +1,175 @@
+//! Canned fixtures for writing tests against this crate.
+//!
+//! Everything here is constructed in code rather than read from files, so crates which depend
+//! on `mzpeaks` can use the same realistic inputs in their own integration tests by enabling
+//! the `test_support` feature. The fixtures are derived from a short list of reference species,
+//! [`REFERENCE_SPECIES`], so the expected answers are known ahead of time:
+//!
+//! - [`reference_spectrum`] is a centroid spectrum with an isotopic envelope for each species
+//!   plus low-intensity noise peaks
+//! - [`reference_deconvoluted_peaks`] is the deconvoluted form of that spectrum
+//! - [`reference_feature_map`] is an LC-MS feature map with one eluting feature per species
+//!
+//! These fixtures are stable: changing them is treated as a breaking change.
+use crate::coordinate::{Time, MZ, PROTON};
+use crate::feature::LCMSFeature;
+use crate::feature_map::FeatureMap;
+use crate::peak::{CentroidPeak, DeconvolutedPeak};
+use crate::peak_set::{DeconvolutedPeakSet, PeakSet};
+use crate::simulate::{averagine_abundance, SimulationRng, NEUTRON_SHIFT};
+
+/// A species present in every reference fixture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReferenceSpecies {
+    pub neutral_mass: f64,
+    pub charge: i32,
+    /// The summed intensity of the species' isotopic peaks
+    pub intensity: f32,
+    /// The time at which the species' feature reaches its apex
+    pub apex_time: f64,
+}
+
+impl ReferenceSpecies {
+    /// The m/z of the monoisotopic peak
+    pub fn mz(&self) -> f64 {
+        (self.neutral_mass + self.charge as f64 * PROTON) / self.charge as f64
+    }
+}
+
+/// The species from which the reference fixtures are built
+pub const REFERENCE_SPECIES: [ReferenceSpecies; 5] = [
+    ReferenceSpecies {
+        neutral_mass: 799.3604,
+        charge: 1,
+        intensity: 2.5e5,
+        apex_time: 12.1,
+    },
+    ReferenceSpecies {
+        neutral_mass: 1295.6782,
+        charge: 2,
+        intensity: 8.0e5,
+        apex_time: 18.4,
+    },
+    ReferenceSpecies {
+        neutral_mass: 1570.6774,
+        charge: 2,
+        intensity: 4.2e5,
+        apex_time: 25.0,
+    },
+    ReferenceSpecies {
+        neutral_mass: 2211.1040,
+        charge: 3,
+        intensity: 1.5e5,
+        apex_time: 31.7,
+    },
+    ReferenceSpecies {
+        neutral_mass: 3156.5210,
+        charge: 4,
+        intensity: 6.0e4,
+        apex_time: 40.2,
+    },
+];
+
+/// The number of isotopic peaks generated for each reference species
+pub const ISOTOPIC_PEAKS_PER_SPECIES: usize = 4;
+
+/// The number of noise peaks in [`reference_spectrum`]
+pub const NOISE_PEAK_COUNT: usize = 40;
+
+/// The relative abundances of the isotopic peaks of a peptide-like molecule of
+/// `neutral_mass`, normalized to sum to one
+fn isotopic_abundances(neutral_mass: f64) -> [f64; ISOTOPIC_PEAKS_PER_SPECIES] {
+    let abundances: [f64; ISOTOPIC_PEAKS_PER_SPECIES] =
+        std::array::from_fn(|k| averagine_abundance(neutral_mass, k));
+    let total: f64 = abundances.iter().sum();
+    abundances.map(|a| a / total)
+}
+
+/// A centroid spectrum containing [`ISOTOPIC_PEAKS_PER_SPECIES`] isotopic peaks for each
+/// of [`REFERENCE_SPECIES`] and [`NOISE_PEAK_COUNT`] noise peaks with intensities between
+/// 10 and 500, well below any isotopic peak.
+pub fn reference_spectrum() -> PeakSet {
+    let mut peaks = Vec::new();
+    for species in REFERENCE_SPECIES.iter() {
+        let mz = species.mz();
+        let abundances = isotopic_abundances(species.neutral_mass);
+        for (k, abundance) in abundances.iter().enumerate() {
+            peaks.push(CentroidPeak::new(
+                mz + k as f64 * NEUTRON_SHIFT / species.charge as f64,
+                (species.intensity as f64 * abundance) as f32,
+                0,
+            ));
+        }
+    }
+    let mut rng = SimulationRng::new(20240101);
+    for _ in 0..NOISE_PEAK_COUNT {
+        peaks.push(CentroidPeak::new(
+            rng.uniform(150.0, 2000.0),
+            rng.uniform(10.0, 500.0) as f32,
+            0,
+        ));
+    }
+    PeakSet::new(peaks)
+}
+
+/// The deconvoluted form of [`reference_spectrum`], one peak per member of [`REFERENCE_SPECIES`]
+pub fn reference_deconvoluted_peaks() -> DeconvolutedPeakSet {
+    REFERENCE_SPECIES
+        .iter()
+        .map(|s| DeconvolutedPeak::new(s.neutral_mass, s.intensity, s.charge, 0))
+        .collect()
+}
+
+/// An LC-MS feature map with one feature at the monoisotopic m/z of each member of
+/// [`REFERENCE_SPECIES`], following a Gaussian elution profile sampled every 0.1 minutes
+/// for two minutes either side of the species' apex.
+pub fn reference_feature_map() -> FeatureMap<MZ, Time, LCMSFeature> {
+    let features = REFERENCE_SPECIES
+        .iter()
+        .map(|species| {
+            let mz = species.mz();
+            (-20..=20)
+                .map(|i| {
+                    let offset = i as f64 * 0.1;
+                    let intensity = species.intensity as f64 * (-offset.powi(2) / 0.5).exp();
+                    (mz, species.apex_time + offset, intensity as f32)
+                })
+                .collect::<LCMSFeature>()
+        })
+        .collect();
+    FeatureMap::new(features)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::feature::TimeInterval;
+    use crate::{MZLocated, PeakCollection, Tolerance};
+
+    #[test]
+    fn test_fixtures() {
+        let peaks = reference_spectrum();
+        assert_eq!(
+            peaks.len(),
+            REFERENCE_SPECIES.len() * ISOTOPIC_PEAKS_PER_SPECIES + NOISE_PEAK_COUNT
+        );
+        assert_eq!(peaks, reference_spectrum());
+        for species in REFERENCE_SPECIES.iter() {
+            assert!(peaks.has_peak(species.mz(), Tolerance::PPM(1.0)).is_some());
+        }
+
+        let deconvoluted = reference_deconvoluted_peaks();
+        assert_eq!(deconvoluted.len(), REFERENCE_SPECIES.len());
+        assert!((deconvoluted[1].mz() - REFERENCE_SPECIES[1].mz()).abs() < 1e-6);
+
+        let features = reference_feature_map();
+        assert_eq!(features.len(), REFERENCE_SPECIES.len());
+        for species in REFERENCE_SPECIES.iter() {
+            let feature = features
+                .iter()
+                .find(|f| (f.mz() - species.mz()).abs() < 1e-6)
+                .unwrap();
+            assert!((feature.apex_time().unwrap() - species.apex_time).abs() < 1e-6);
+        }
+    }
+}